@@ -1,6 +1,14 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use phoenix::program::status::{MarketStatus, SeatApprovalStatus};
+use phoenix::state::{SelfTradeBehavior, Side};
+use phoenix_cli_processor::helpers::transaction_helpers::TransactionEncoding;
+use phoenix_cli_processor::processor::process_export_trades::TradeExportFormat;
+use phoenix_cli_processor::processor::process_get_candles::CandleResolution;
+use phoenix_cli_processor::processor::process_place_orders::OrderFileFormat;
+use phoenix_cli_processor::processor::process_stream_events::MarketEventType;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signature;
+use std::path::PathBuf;
 
 // #[clap(author, version, about)]
 #[derive(Debug, Clone, Parser)]
@@ -13,42 +21,183 @@ pub enum PhoenixCLICommand {
         no_gpa: bool,
     },
     /// Get detailed information on a specific market
-    GetMarket { market_pubkey: Pubkey },
+    GetMarket {
+        market_pubkey: String,
+        #[clap(flatten)]
+        account_file_options: AccountFileOptions,
+    },
     /// Get active traders for a given market
-    GetTradersForMarket { market_pubkey: Pubkey },
+    GetTradersForMarket {
+        market_pubkey: String,
+        #[clap(flatten)]
+        account_file_options: AccountFileOptions,
+    },
     /// Get the best bid and ask price for a given market
-    GetTopOfBook { market_pubkey: Pubkey },
+    GetTopOfBook {
+        market_pubkey: String,
+        /// Keep the book on screen and redraw it as it changes, like watch-book
+        #[clap(long, required = false)]
+        watch: bool,
+        #[clap(flatten)]
+        watch_options: WatchOptions,
+    },
     /// Get the first N levels of the order book for a given market.
     /// Default is 10 levels
     GetBookLevels {
-        market_pubkey: Pubkey,
+        market_pubkey: String,
         #[clap(short, long, required = false, default_value = "10")]
         levels: u64,
+        /// Keep the book on screen and redraw it as it changes, like watch-book
+        #[clap(long, required = false, conflicts_with = "account_file")]
+        watch: bool,
+        #[clap(flatten)]
+        watch_options: WatchOptions,
+        #[clap(flatten)]
+        account_file_options: AccountFileOptions,
     },
     /// Get the full order book for a given market
-    GetFullBook { market_pubkey: Pubkey },
+    GetFullBook {
+        market_pubkey: String,
+        /// Keep the book on screen and redraw it as it changes, like watch-book
+        #[clap(long, required = false, conflicts_with = "account_file")]
+        watch: bool,
+        #[clap(flatten)]
+        watch_options: WatchOptions,
+        #[clap(flatten)]
+        account_file_options: AccountFileOptions,
+    },
+    /// Watch the first N levels of the order book for a given market, redrawing it in place as it changes.
+    /// Levels that changed since the last update are highlighted, and the spread and mid price are shown below the book.
+    /// Updates come from a websocket subscription to the market account, with RPC polling as a fallback
+    WatchBook {
+        market_pubkey: String,
+        #[clap(short, long, required = false, default_value = "10")]
+        levels: u64,
+        #[clap(flatten)]
+        watch_options: WatchOptions,
+    },
+    /// Follow a market and print its events as new transactions land, oldest first.
+    /// New signatures are found by polling getSignaturesForAddress. Events can be filtered by type, maker, taker and size
+    StreamEvents {
+        market_pubkey: String,
+        /// Only print events of these types. Repeat the flag or separate types with commas. Defaults to all types
+        #[clap(short, long = "event-type", value_enum, value_delimiter = ',')]
+        event_types: Vec<MarketEventType>,
+        /// Only print events where this pubkey is the maker
+        #[clap(long)]
+        maker: Option<Pubkey>,
+        /// Only print events where this pubkey is the taker
+        #[clap(long)]
+        taker: Option<Pubkey>,
+        /// Only print events of at least this size, in base units
+        #[clap(long)]
+        min_size: Option<f64>,
+        /// Milliseconds between polls for new signatures
+        #[clap(long, required = false, default_value = "1000")]
+        poll_interval_ms: u64,
+    },
+    /// Export the trades of a market to a CSV or newline-delimited JSON file, newest first.
+    /// Transactions are walked backwards from the latest one, or from --before, until the start of the range
+    ExportTrades {
+        market_pubkey: String,
+        /// File to write the trades to
+        #[clap(short, long)]
+        out_file: PathBuf,
+        /// Format of the exported trades
+        #[clap(short, long, value_enum, default_value = "csv")]
+        format: TradeExportFormat,
+        /// Only export trades from transactions older than this signature
+        #[clap(long)]
+        before: Option<Signature>,
+        /// Only export trades from transactions newer than this signature
+        #[clap(long)]
+        until: Option<Signature>,
+        /// Only export trades at or after this slot
+        #[clap(long)]
+        start_slot: Option<u64>,
+        /// Only export trades at or before this slot
+        #[clap(long)]
+        end_slot: Option<u64>,
+        /// Only export trades at or after this unix timestamp, in seconds
+        #[clap(long)]
+        start_time: Option<i64>,
+        /// Only export trades at or before this unix timestamp, in seconds
+        #[clap(long)]
+        end_time: Option<i64>,
+        /// File to save progress to. If it exists, the export resumes where it left off and appends to the output file
+        #[clap(long)]
+        checkpoint: Option<PathBuf>,
+    },
+    /// Get OHLCV bars for a market, built from the fills in a time window.
+    /// The window defaults to the last --bars bars up to now
+    GetCandles {
+        market_pubkey: String,
+        /// Length of each bar
+        #[clap(short, long, value_enum, default_value = "1m")]
+        resolution: CandleResolution,
+        /// Number of bars to cover when --start-time is not given
+        #[clap(short, long, default_value = "60")]
+        bars: u64,
+        /// Start of the window as a unix timestamp, in seconds
+        #[clap(long)]
+        start_time: Option<i64>,
+        /// End of the window as a unix timestamp, in seconds. Defaults to now
+        #[clap(long)]
+        end_time: Option<i64>,
+    },
     /// Get the market events that occured in a given transaction signature
     GetTransaction { signature: Signature },
     /// Get the current status of a market
-    GetMarketStatus { market_pubkey: Pubkey },
+    GetMarketStatus { market_pubkey: String },
     /// Get the status and address of a seat for a given market and trader
     GetSeatInfo {
-        market_pubkey: Pubkey,
+        market_pubkey: String,
         /// Pubkey of the trader associated with the seat. Defaults to the current payer
         #[clap(short, long, required = false)]
         trader_pubkey: Option<Pubkey>,
     },
     /// Get all open orders on a given market for a trader
     GetOpenOrders {
-        market_pubkey: Pubkey,
+        market_pubkey: String,
         /// Pubkey of the trader for whom to get open orders. Defaults to the current payer
         #[clap(short, long, required = false)]
         trader_pubkey: Option<Pubkey>,
+        #[clap(flatten)]
+        account_file_options: AccountFileOptions,
+    },
+    /// Get the open orders of a trader that expire within a window of slots or seconds, soonest first.
+    /// Without a window, every order with an expiry is listed
+    GetExpiringOrders {
+        market_pubkey: String,
+        /// Pubkey of the trader for whom to get expiring orders. Defaults to the current payer
+        #[clap(short, long, required = false)]
+        trader_pubkey: Option<Pubkey>,
+        /// Include orders that expire within this many slots
+        #[clap(long)]
+        within_slots: Option<u64>,
+        /// Include orders that expire within this many seconds
+        #[clap(long)]
+        within_seconds: Option<u64>,
     },
     /// Send a transaction on chain to allocate a seat for the payer on the given market. This will cost ~.0018 SOL for rent.
     /// Note that the seat will have to then be approved by the market authority. Only relevant for permissioned markets.
     /// For permissionless markets (with an automated seat manager), you can claim a seat with the claim-seat CLI command.
-    RequestSeat { market_pubkey: Pubkey },
+    RequestSeat { market_pubkey: String },
+    /// List the seat requests on a market that the market authority has not approved yet
+    ListPendingSeats { market_pubkey: String },
+    /// Approve, unapprove or retire the seats of one or more traders. Must be signed by the market authority.
+    /// Approving a seat registers the trader on the market
+    ChangeSeatStatus {
+        market_pubkey: String,
+        #[clap(value_enum)]
+        status: SeatStatusArg,
+        /// Traders whose seats to change
+        #[clap(required_unless_present = "traders_file")]
+        traders: Vec<Pubkey>,
+        /// File with one trader pubkey per line, or the CSV output of list-pending-seats
+        #[clap(short = 'f', long)]
+        traders_file: Option<PathBuf>,
+    },
     /// Mint tokens to a recipient for a given ticker string (for example SOL or USDC). Default amount is 100_000_000_000.
     /// This is only for markets associated with the ellipsis token faucet.
     MintTokens {
@@ -63,7 +212,7 @@ pub enum PhoenixCLICommand {
     /// Mint both base and quote tokens to a recipient for a given market. Default amounts are 100_000_000_000 for base and 100_000_000 for quote.
     /// This is only for markets associated with the ellipsis token faucet.
     MintTokensForMarket {
-        market_pubkey: Pubkey,
+        market_pubkey: String,
         /// Pubkey of the recipient of the tokens
         recipient_pubkey: Pubkey,
         /// Amount in atoms (1 * 10*(-decimals))
@@ -74,15 +223,558 @@ pub enum PhoenixCLICommand {
         quote_amount: u64,
     },
     /// For the given market, get the seat manager data fields, including authority, successor, and designated market makers.
-    GetSeatManagerInfo { market_pubkey: Pubkey },
+    GetSeatManagerInfo { market_pubkey: String },
     /// On the given market, claim a maker seat for the public key of the keypair at the indicated file path.
     /// Indicate a different keypair file to use by specifying the file path with flag `-k`.
-    ClaimSeat { market_pubkey: Pubkey },
+    ClaimSeat { market_pubkey: String },
     /// Evict a trader from the given market if that market's trader state is at capacity.
     /// If no trader is given, this function will greedily find a trader to evict.
     /// Note that eviction will not work if the market's trader state is not at capacity.
     EvictSeat {
-        market_pubkey: Pubkey,
+        market_pubkey: String,
         trader_to_evict: Option<Pubkey>,
     },
+    /// Place a limit order on the given market. Price is in quote units per base unit and size is in base units.
+    /// Missing token accounts are created and a seat is claimed if needed before the order is sent.
+    PlaceLimitOrder {
+        market_pubkey: String,
+        /// Side of the order: bid (buy) or ask (sell)
+        #[clap(value_enum)]
+        side: OrderSide,
+        /// Price in quote units per base unit, example: 22.5
+        price: f64,
+        /// Size in base units, example: 1.5
+        size: f64,
+        /// Optional client order id to tag the order with
+        #[clap(long, required = false, default_value = "0")]
+        client_order_id: u128,
+        /// Reject the order instead of crossing the spread
+        #[clap(long, required = false, conflicts_with = "ioc")]
+        post_only: bool,
+        /// Send as immediate-or-cancel: match against the book and cancel any remainder
+        #[clap(long, required = false)]
+        ioc: bool,
+        /// How the matching engine should handle the order crossing one of your own resting orders
+        #[clap(long, value_enum, required = false, default_value = "cancel-provide")]
+        self_trade_behavior: SelfTradeBehaviorArg,
+        /// Cancel the order once this many slots have passed after the current slot
+        #[clap(long, group = "expiry")]
+        expire_in_slots: Option<u64>,
+        /// Cancel the order once this many seconds have passed after the current on-chain time
+        #[clap(long, group = "expiry")]
+        expire_in_seconds: Option<u64>,
+        /// Cancel the order after this unix timestamp, in seconds
+        #[clap(long, group = "expiry")]
+        expire_at: Option<u64>,
+    },
+    /// Place many orders on the given market from a JSON, TOML or CSV file. Every order is checked against the
+    /// tick and lot sizes of the market, and the valid orders are packed into as few transactions as possible
+    PlaceOrders {
+        market_pubkey: String,
+        /// File listing the orders. Each order has a side, price and size, and optionally a client_order_id,
+        /// last_valid_slot and last_valid_unix_timestamp
+        order_file: PathBuf,
+        /// Format of the order file. Defaults to the format matching the file extension
+        #[clap(short, long, value_enum)]
+        format: Option<OrderFileFormat>,
+        /// Reject orders that would cross the spread. A rejected order fails its whole transaction
+        #[clap(long, required = false)]
+        post_only: bool,
+        /// How the matching engine should handle an order crossing one of your own resting orders
+        #[clap(long, value_enum, required = false, default_value = "cancel-provide")]
+        self_trade_behavior: SelfTradeBehaviorArg,
+        /// Validate and pack the orders without sending any transaction
+        #[clap(long, required = false)]
+        dry_run: bool,
+    },
+    /// Cancel the payer's open orders on the given market by order ID, as printed by get-open-orders
+    CancelOrder {
+        market_pubkey: String,
+        /// One or more order IDs to cancel
+        #[clap(
+            required = true,
+            num_args = 1..,
+            allow_negative_numbers = true,
+            value_parser = parse_order_sequence_number
+        )]
+        order_ids: Vec<u64>,
+    },
+    /// Cancel all of the payer's open orders on the given market, optionally only on one side of the book
+    CancelAllOrders {
+        market_pubkey: String,
+        /// Only cancel orders on this side: bid (buy) or ask (sell)
+        #[clap(short, long, value_enum, required = false)]
+        side: Option<OrderSide>,
+    },
+    /// Cancel the payer's orders on one side of the book, from the top of the book down to a price threshold (inclusive)
+    CancelUpTo {
+        market_pubkey: String,
+        /// Side of the book to cancel: bid (buy) or ask (sell)
+        #[clap(value_enum)]
+        side: OrderSide,
+        /// Price threshold in ticks
+        #[clap(long, required_unless_present = "price", conflicts_with = "price")]
+        ticks: Option<u64>,
+        /// Price threshold in quote units per base unit
+        #[clap(long)]
+        price: Option<f64>,
+    },
+    /// Reprice or resize one of the payer's resting orders. The order is cancelled and its replacement placed
+    /// in a single transaction, so either both happen or neither does
+    AmendOrder {
+        market_pubkey: String,
+        /// ID of the order to amend, as printed by get-open-orders
+        #[clap(allow_negative_numbers = true, value_parser = parse_order_sequence_number)]
+        order_id: u64,
+        /// New price in quote units per base unit. Defaults to the current price
+        #[clap(short, long, required_unless_present = "size")]
+        price: Option<f64>,
+        /// New size in base units. Defaults to the size left on the order
+        #[clap(short, long)]
+        size: Option<f64>,
+        /// Optional client order id to tag the replacement with
+        #[clap(long, required = false, default_value = "0")]
+        client_order_id: u128,
+        /// Reject the replacement instead of crossing the spread. The original order is then kept
+        #[clap(long, required = false)]
+        post_only: bool,
+        /// How the matching engine should handle the replacement crossing one of your own resting orders
+        #[clap(long, value_enum, required = false, default_value = "cancel-provide")]
+        self_trade_behavior: SelfTradeBehaviorArg,
+    },
+    /// Send an immediate-or-cancel market order for the payer on the given market. Size is in base units.
+    /// Prints a pre-trade quote from the current book and refuses to send if the expected average price moves
+    /// further than the max slippage from the best price.
+    #[clap(alias = "market-order")]
+    Swap {
+        market_pubkey: String,
+        /// Side of the order: bid (buy) or ask (sell)
+        #[clap(value_enum)]
+        side: OrderSide,
+        /// Size in base units, example: 1.5
+        size: f64,
+        /// Maximum allowed price impact, from the best price to the expected average price, in basis points
+        #[clap(long, required = false, default_value = "50")]
+        max_slippage_bps: u64,
+    },
+    /// Deposit base and/or quote tokens from the payer's token accounts into the payer's seat on the given market.
+    /// Amounts are in whole base and quote units. Prints the trader state before and after the deposit.
+    DepositFunds {
+        market_pubkey: String,
+        /// Amount of base tokens to deposit, example: 1.5
+        #[clap(short, long, required_unless_present = "quote_amount")]
+        base_amount: Option<f64>,
+        /// Amount of quote tokens to deposit, example: 100
+        #[clap(short, long)]
+        quote_amount: Option<f64>,
+    },
+    /// Withdraw free base and/or quote tokens from the payer's seat on the given market to the payer's token accounts.
+    /// Amounts are in whole base and quote units. Prints the trader state before and after the withdrawal.
+    WithdrawFunds {
+        market_pubkey: String,
+        /// Amount of base tokens to withdraw, example: 1.5
+        #[clap(
+            short,
+            long,
+            required_unless_present_any = ["quote_amount", "all"],
+            conflicts_with = "all"
+        )]
+        base_amount: Option<f64>,
+        /// Amount of quote tokens to withdraw, example: 100
+        #[clap(short, long, conflicts_with = "all")]
+        quote_amount: Option<f64>,
+        /// Withdraw all free base and quote tokens
+        #[clap(long, required = false)]
+        all: bool,
+    },
+    /// Add signatures from local keypairs to a transaction exported with --export-tx or --sign-only.
+    /// Prints the transaction with the signers that are still missing
+    SignTx {
+        /// Encoded transaction, or the path of a file holding it
+        transaction: String,
+        /// Keypair to sign with. Can be given several times. Defaults to the keypair from --keypair-path
+        #[clap(short, long = "signer")]
+        signers: Vec<String>,
+        /// Encoding of the signed transaction. Defaults to the encoding of the given transaction
+        #[clap(short, long, value_enum)]
+        encoding: Option<TransactionEncoding>,
+    },
+    /// Submit a fully signed transaction, such as one signed with sign-tx, and wait for it to be confirmed
+    BroadcastTx {
+        /// Encoded transaction, or the path of a file holding it
+        transaction: String,
+    },
+    /// Create a market and its vaults. Every parameter is checked against the program's rules before
+    /// sending. The market starts out PostOnly, with the payer as its authority
+    CreateMarket {
+        base_mint: Pubkey,
+        quote_mint: Pubkey,
+        /// Tick size in quote units per base unit, example: 0.001
+        #[clap(long)]
+        tick_size: String,
+        /// Base lot size in base units, example: 0.001
+        #[clap(long)]
+        base_lot_size: String,
+        /// Quote lot size in quote units. Defaults to one quote atom
+        #[clap(long)]
+        quote_lot_size: Option<String>,
+        #[clap(long, default_value = "512")]
+        bids_size: u64,
+        #[clap(long, default_value = "512")]
+        asks_size: u64,
+        /// Number of trader seats. Defaults to 128, or to bids + asks + 129 with --seat-manager
+        #[clap(long)]
+        num_seats: Option<u64>,
+        /// Fee charged to takers, in basis points
+        #[clap(long, default_value = "0")]
+        taker_fee_bps: u16,
+        /// Account that receives the collected fees. Defaults to the payer
+        #[clap(long)]
+        fee_recipient: Option<Pubkey>,
+        /// Base units are 10^decimals * this many base atoms
+        #[clap(long)]
+        raw_base_units_per_base_unit: Option<u32>,
+        /// Keypair for the new market account. Defaults to a new keypair, and is required with --export-tx or --sign-only
+        #[clap(long)]
+        market_keypair: Option<String>,
+        /// Make the seat manager program the market authority, with the payer as the seat manager authority
+        #[clap(long)]
+        seat_manager: bool,
+    },
+    /// Market authority commands. Each checks that the payer is the market authority before sending
+    Admin {
+        #[clap(subcommand)]
+        command: AdminCommand,
+    },
+    /// Seat manager authority commands for markets governed by the seat manager program. Each checks that
+    /// the payer is the seat manager authority before sending, and shows the designated market makers
+    /// before and after
+    SeatManager {
+        #[clap(subcommand)]
+        command: SeatManagerCommand,
+    },
+    /// Token symbols and markets known for the cluster. The registry layers the Phoenix master config,
+    /// cached locally, under user entries kept per cluster genesis hash
+    Registry {
+        #[clap(subcommand)]
+        command: RegistryCommand,
+    },
+    /// Bootstrap a test environment on a local test validator or devnet
+    Sandbox {
+        #[clap(subcommand)]
+        command: SandboxCommand,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum AdminCommand {
+    /// Change the status of a market. The transition is checked against the current status first
+    ChangeMarketStatus {
+        market_pubkey: String,
+        #[clap(value_enum)]
+        status: MarketStatusArg,
+    },
+    /// Change the account that receives the market's collected fees
+    ChangeFeeRecipient {
+        market_pubkey: String,
+        new_fee_recipient: Pubkey,
+        /// Keypair of the current fee recipient, which must also sign while the market has uncollected fees
+        #[clap(long)]
+        fee_recipient_keypair: Option<String>,
+    },
+    /// Name the account that can take over the market authority with claim-authority
+    NameSuccessor {
+        market_pubkey: String,
+        successor: Pubkey,
+    },
+    /// Take over the market authority. Must be signed by the successor named by the current authority
+    ClaimAuthority { market_pubkey: String },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum SeatManagerCommand {
+    /// Add a designated market maker. The trader must have a seat, and DMMs cannot be evicted
+    AddDmm {
+        market_pubkey: String,
+        trader: Pubkey,
+    },
+    /// Remove a designated market maker
+    RemoveDmm {
+        market_pubkey: String,
+        trader: Pubkey,
+    },
+    /// Name the account that can take over the seat manager authority with claim-authority
+    NameSuccessor {
+        market_pubkey: String,
+        successor: Pubkey,
+    },
+    /// Take over the seat manager authority. Must be signed by the successor named by the current authority
+    ClaimAuthority { market_pubkey: String },
+    /// Claim and approve seats on behalf of traders. The authority covers the seat rent and deposit
+    ClaimSeat {
+        market_pubkey: String,
+        #[clap(required = true)]
+        traders: Vec<Pubkey>,
+    },
+    /// Evict traders without locked funds, even when the market is not full. Free funds are withdrawn to
+    /// the traders' token accounts
+    EvictSeat {
+        market_pubkey: String,
+        #[clap(required = true)]
+        traders: Vec<Pubkey>,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum RegistryCommand {
+    /// List the tokens and markets known for the cluster, and whether each comes from the user registry
+    /// or the master config
+    List,
+    /// Add a token mint or a Phoenix market to the user registry for the cluster, or update its entry
+    Add {
+        /// Token mint or market address. The kind of entry is read from the account owner
+        address: Pubkey,
+        /// Symbol of a token mint, example: SOL
+        #[clap(long)]
+        symbol: Option<String>,
+        /// Name of a token mint. Defaults to the symbol
+        #[clap(long)]
+        name: Option<String>,
+        /// Name for a market, accepted wherever a market address is, example: sol-usdc
+        #[clap(long)]
+        alias: Option<String>,
+    },
+    /// Download the master config again, regardless of the age of the cached copy
+    Refresh,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum SandboxCommand {
+    /// Create faucet mints for a pair and a seat manager market, then fund traders, claim their seats and
+    /// seed a symmetric book around a mid. The trader keypairs and a manifest of every address created are
+    /// written to the output directory. Refuses to run against mainnet
+    Init {
+        /// Pair of faucet tickers, as BASE/QUOTE
+        #[clap(default_value = "SOL/USDC")]
+        pair: String,
+        /// Decimals of the base mint, if the faucet has to create it
+        #[clap(long, default_value = "9")]
+        base_decimals: u8,
+        /// Decimals of the quote mint, if the faucet has to create it
+        #[clap(long, default_value = "6")]
+        quote_decimals: u8,
+        /// Tick size in quote units per base unit
+        #[clap(long, default_value = "0.001")]
+        tick_size: String,
+        /// Base lot size in base units
+        #[clap(long, default_value = "0.001")]
+        base_lot_size: String,
+        #[clap(long, default_value = "0")]
+        taker_fee_bps: u16,
+        #[clap(short, long, default_value = "2")]
+        num_traders: usize,
+        /// Directory for the trader keypairs and manifest.json
+        #[clap(long, default_value = "sandbox")]
+        out_dir: PathBuf,
+        /// SOL sent to each trader for fees
+        #[clap(long, default_value = "1")]
+        sol_per_trader: f64,
+        /// Base units airdropped to each trader
+        #[clap(long, default_value = "1000")]
+        base_amount: u64,
+        /// Quote units airdropped to each trader
+        #[clap(long, default_value = "100000")]
+        quote_amount: u64,
+        /// Price the book is centered on, in quote units per base unit
+        #[clap(long, default_value = "100")]
+        mid: f64,
+        /// Price levels on each side of the book
+        #[clap(long, default_value = "5")]
+        levels: usize,
+        /// Distance between price levels, in quote units per base unit
+        #[clap(long, default_value = "0.1")]
+        level_spacing: f64,
+        /// Size of each order, in base units
+        #[clap(long, default_value = "1")]
+        order_size: f64,
+    },
+}
+
+impl PhoenixCLICommand {
+    /// Whether the command signs transactions, and so needs the payer keypair. Read-only commands run
+    /// without one, and so do authority commands whose transaction is exported with both the fee payer
+    /// and the authority given, since no local keypair signs it then
+    pub fn requires_keypair(&self, exported_for_other_signers: bool) -> bool {
+        match self {
+            PhoenixCLICommand::GetAllMarkets { .. }
+            | PhoenixCLICommand::GetMarket { .. }
+            | PhoenixCLICommand::GetTradersForMarket { .. }
+            | PhoenixCLICommand::GetTopOfBook { .. }
+            | PhoenixCLICommand::GetBookLevels { .. }
+            | PhoenixCLICommand::GetFullBook { .. }
+            | PhoenixCLICommand::WatchBook { .. }
+            | PhoenixCLICommand::StreamEvents { .. }
+            | PhoenixCLICommand::ExportTrades { .. }
+            | PhoenixCLICommand::GetCandles { .. }
+            | PhoenixCLICommand::GetTransaction { .. }
+            | PhoenixCLICommand::GetMarketStatus { .. }
+            | PhoenixCLICommand::GetSeatInfo { .. }
+            | PhoenixCLICommand::GetOpenOrders { .. }
+            | PhoenixCLICommand::GetExpiringOrders { .. }
+            | PhoenixCLICommand::GetSeatManagerInfo { .. }
+            | PhoenixCLICommand::ListPendingSeats { .. }
+            | PhoenixCLICommand::Registry { .. }
+            | PhoenixCLICommand::BroadcastTx { .. } => false,
+            PhoenixCLICommand::SignTx { signers, .. } => signers.is_empty(),
+            PhoenixCLICommand::RequestSeat { .. }
+            | PhoenixCLICommand::MintTokens { .. }
+            | PhoenixCLICommand::MintTokensForMarket { .. }
+            | PhoenixCLICommand::ClaimSeat { .. }
+            | PhoenixCLICommand::EvictSeat { .. }
+            | PhoenixCLICommand::PlaceLimitOrder { .. }
+            | PhoenixCLICommand::PlaceOrders { .. }
+            | PhoenixCLICommand::CancelOrder { .. }
+            | PhoenixCLICommand::CancelAllOrders { .. }
+            | PhoenixCLICommand::CancelUpTo { .. }
+            | PhoenixCLICommand::AmendOrder { .. }
+            | PhoenixCLICommand::Swap { .. }
+            | PhoenixCLICommand::DepositFunds { .. }
+            | PhoenixCLICommand::WithdrawFunds { .. } => true,
+            PhoenixCLICommand::ChangeSeatStatus { .. }
+            | PhoenixCLICommand::CreateMarket { .. }
+            | PhoenixCLICommand::Admin { .. }
+            | PhoenixCLICommand::SeatManager { .. } => !exported_for_other_signers,
+            PhoenixCLICommand::Sandbox { .. } => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct WatchOptions {
+    /// When watching, milliseconds between RPC polls if the websocket is unavailable or --poll is set
+    #[clap(long, required = false, default_value = "1000")]
+    pub poll_interval_ms: u64,
+    /// When watching, poll the RPC instead of subscribing through a websocket
+    #[clap(long, required = false)]
+    pub poll: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct AccountFileOptions {
+    /// Decode the market account from this file instead of fetching it from the RPC. The file can hold the raw
+    /// account data, the data encoded as base64, or the output of `solana account --output json`
+    #[clap(long, required = false)]
+    pub account_file: Option<PathBuf>,
+}
+
+// Bid order IDs are printed as negative numbers by get-open-orders, so accept both representations
+fn parse_order_sequence_number(order_id: &str) -> Result<u64, String> {
+    order_id
+        .parse::<u64>()
+        .or_else(|_| order_id.parse::<i64>().map(|id| id as u64))
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum OrderSide {
+    #[value(alias = "buy")]
+    Bid,
+    #[value(alias = "sell")]
+    Ask,
+}
+
+impl From<OrderSide> for Side {
+    fn from(side: OrderSide) -> Self {
+        match side {
+            OrderSide::Bid => Side::Bid,
+            OrderSide::Ask => Side::Ask,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SelfTradeBehaviorArg {
+    /// Fail the transaction
+    Abort,
+    /// Cancel the resting order and continue matching
+    CancelProvide,
+    /// Reduce both orders by the overlapping size
+    DecrementTake,
+}
+
+impl From<SelfTradeBehaviorArg> for SelfTradeBehavior {
+    fn from(behavior: SelfTradeBehaviorArg) -> Self {
+        match behavior {
+            SelfTradeBehaviorArg::Abort => SelfTradeBehavior::Abort,
+            SelfTradeBehaviorArg::CancelProvide => SelfTradeBehavior::CancelProvide,
+            SelfTradeBehaviorArg::DecrementTake => SelfTradeBehavior::DecrementTake,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum MarketStatusArg {
+    /// All orders are accepted
+    Active,
+    /// Only orders that do not cross the book, reductions and withdrawals are accepted
+    PostOnly,
+    /// Only reductions and withdrawals are accepted
+    Paused,
+    /// Only reductions and withdrawals are accepted, and the authority can cancel all orders
+    Closed,
+    /// Removes a closed market with no orders, traders or uncollected fees
+    Tombstoned,
+}
+
+impl From<MarketStatusArg> for MarketStatus {
+    fn from(status: MarketStatusArg) -> Self {
+        match status {
+            MarketStatusArg::Active => MarketStatus::Active,
+            MarketStatusArg::PostOnly => MarketStatus::PostOnly,
+            MarketStatusArg::Paused => MarketStatus::Paused,
+            MarketStatusArg::Closed => MarketStatus::Closed,
+            MarketStatusArg::Tombstoned => MarketStatus::Tombstoned,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SeatStatusArg {
+    #[value(alias = "approve")]
+    Approved,
+    #[value(alias = "reject")]
+    NotApproved,
+    #[value(alias = "retire")]
+    Retired,
+}
+
+impl From<SeatStatusArg> for SeatApprovalStatus {
+    fn from(status: SeatStatusArg) -> Self {
+        match status {
+            SeatStatusArg::Approved => SeatApprovalStatus::Approved,
+            SeatStatusArg::NotApproved => SeatApprovalStatus::NotApproved,
+            SeatStatusArg::Retired => SeatApprovalStatus::Retired,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_order_sequence_number;
+
+    #[test]
+    fn order_ids_parse_in_their_printed_form() {
+        // Bids are printed as negative numbers since their sequence numbers have the top bit set
+        let bid = 1u64 << 63 | 7;
+        for (printed, expected) in [
+            ("42".to_string(), 42),
+            ((bid as i64).to_string(), bid),
+            (u64::MAX.to_string(), u64::MAX),
+        ] {
+            assert_eq!(parse_order_sequence_number(&printed), Ok(expected));
+        }
+        for invalid in ["", "abc", "1.5", "18446744073709551616"] {
+            assert!(parse_order_sequence_number(invalid).is_err(), "{}", invalid);
+        }
+    }
 }
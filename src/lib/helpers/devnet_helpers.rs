@@ -1,4 +1,5 @@
-use ellipsis_client::EllipsisClient;
+use crate::helpers::transaction_helpers::send_instructions;
+use phoenix_sdk::sdk_client::SDKClient;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signer::Signer;
 
@@ -145,10 +146,11 @@ pub mod devnet_token_faucet {
 
 // Create_mint on devnet, utilizing devnet-token-faucet
 pub async fn find_or_create_devnet_mint(
-    client: &EllipsisClient,
+    sdk: &SDKClient,
     ticker: &str,
     decimals: u8,
 ) -> anyhow::Result<Pubkey> {
+    let client = &sdk.client;
     let (mint, _) = Pubkey::find_program_address(
         &["mint".as_bytes(), ticker.to_lowercase().as_ref()],
         &devnet_token_faucet::ID,
@@ -160,9 +162,7 @@ pub async fn find_or_create_devnet_mint(
             ticker.to_string(),
             decimals,
         );
-        client
-            .sign_send_instructions(vec![mint_ix], vec![&client.payer])
-            .await?;
+        send_instructions(sdk, vec![mint_ix], vec![]).await?;
     }
     Ok(mint)
 }
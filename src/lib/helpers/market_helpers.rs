@@ -1,26 +1,35 @@
+use base64::Engine;
 use borsh::BorshDeserialize;
 use borsh::BorshSerialize;
 use ellipsis_client::EllipsisClient;
-use phoenix::program::{load_with_dispatch, status::SeatApprovalStatus, MarketHeader};
+use phoenix::program::{
+    get_seat_address, load_with_dispatch, status::SeatApprovalStatus, MarketHeader, Seat,
+};
 use phoenix::state::markets::FIFOOrderId;
 use phoenix::state::markets::FIFORestingOrder;
-use phoenix::state::markets::{Ladder, Market};
-use phoenix::state::OrderPacket;
+use phoenix::state::markets::{Ladder, Market, RestingOrder};
+use phoenix::state::{OrderPacket, Side, TraderState};
 
+use crate::helpers::registry_helpers::{get_phoenix_config, MasterConfig};
 use phoenix_sdk::sdk_client::*;
+use phoenix_sdk::utils::create_ata_ix_if_needed;
 use phoenix_seat_manager::get_seat_manager_address;
 use phoenix_seat_manager::seat_manager::SeatManager;
-use solana_account_decoder::UiAccountEncoding;
+use serde::Deserialize;
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
 use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
 use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
 use solana_sdk::account::Account;
 use solana_sdk::clock::Clock;
 use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
 use solana_sdk::keccak;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::sysvar;
 use std::collections::BTreeMap;
 use std::mem::size_of;
+use std::path::Path;
+use std::str::FromStr;
 
 pub fn get_discriminant(type_name: &str) -> anyhow::Result<u64> {
     Ok(u64::from_le_bytes(
@@ -41,6 +50,170 @@ pub async fn get_seat_status(
     Ok(seat_status)
 }
 
+/// Resolves a market given by address, alias or BASE/QUOTE symbols. Symbols are looked up in the registry
+/// tokens, and may also be mint addresses. The matching registry markets are checked against their
+/// on-chain headers, and all markets on chain are searched when the registry has none. More than one
+/// match is an error listing the candidates
+pub async fn resolve_market(client: &EllipsisClient, market: &str) -> anyhow::Result<Pubkey> {
+    if let Ok(market_pubkey) = Pubkey::from_str(market) {
+        return Ok(market_pubkey);
+    }
+    let config = get_phoenix_config(client).await?;
+    if let Some((alias, address)) = config
+        .aliases
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(market))
+    {
+        return Pubkey::from_str(address).map_err(|_| {
+            anyhow::anyhow!("Alias {} is set to an invalid address {}", alias, address)
+        });
+    }
+    let (base, quote) = market.split_once('/').ok_or_else(|| {
+        let aliases = config.aliases.keys().cloned().collect::<Vec<_>>();
+        anyhow::anyhow!(
+            "{} is not a market address, a BASE/QUOTE pair or a known alias{}",
+            market,
+            if aliases.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", aliases.join(", "))
+            }
+        )
+    })?;
+    let base_mints = get_token_mints(&config, base)?;
+    let quote_mints = get_token_mints(&config, quote)?;
+    let is_match = |header: &MarketHeader| {
+        base_mints.contains(&header.base_params.mint_key)
+            && quote_mints.contains(&header.quote_params.mint_key)
+    };
+
+    let registry_markets = config
+        .markets
+        .iter()
+        .filter(|m| {
+            base_mints
+                .iter()
+                .any(|mint| mint.to_string() == m.base_mint)
+                && quote_mints
+                    .iter()
+                    .any(|mint| mint.to_string() == m.quote_mint)
+        })
+        .filter_map(|m| Pubkey::from_str(&m.market).ok())
+        .collect::<Vec<_>>();
+    let mut candidates = vec![];
+    for chunk in registry_markets.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = client.get_multiple_accounts(chunk).await?;
+        for (market_pubkey, account) in chunk.iter().zip(accounts) {
+            if account
+                .filter(|account| account.owner == phoenix::id())
+                .and_then(|account| read_market_header(&account.data).ok())
+                .is_some_and(|header| is_match(&header))
+            {
+                candidates.push(*market_pubkey);
+            }
+        }
+    }
+    if candidates.is_empty() {
+        candidates = get_all_markets(client)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "No {} market in the registry, and listing the markets on chain failed: {}",
+                    market,
+                    e
+                )
+            })?
+            .into_iter()
+            .filter(|(_, account)| read_market_header(&account.data).is_ok_and(|h| is_match(&h)))
+            .map(|(market_pubkey, _)| market_pubkey)
+            .collect();
+    }
+
+    match candidates.as_slice() {
+        [] => Err(anyhow::anyhow!("No {} market found", market)),
+        [market_pubkey] => Ok(*market_pubkey),
+        _ => Err(anyhow::anyhow!(
+            "{} matches {} markets:\n{}\nPass the market address instead, or name one with `registry add <MARKET> --alias <NAME>`",
+            market,
+            candidates.len(),
+            candidates
+                .iter()
+                .map(|market_pubkey| {
+                    let aliases = config
+                        .aliases
+                        .iter()
+                        .filter(|(_, address)| **address == market_pubkey.to_string())
+                        .map(|(alias, _)| alias.as_str())
+                        .collect::<Vec<_>>();
+                    if aliases.is_empty() {
+                        format!("  {}", market_pubkey)
+                    } else {
+                        format!("  {} (alias {})", market_pubkey, aliases.join(", "))
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        )),
+    }
+}
+
+// Mints of a token symbol, or the mint itself when given by address
+fn get_token_mints(config: &MasterConfig, token: &str) -> anyhow::Result<Vec<Pubkey>> {
+    if let Ok(mint) = Pubkey::from_str(token) {
+        return Ok(vec![mint]);
+    }
+    let mints = config
+        .tokens
+        .iter()
+        .filter(|t| t.symbol.eq_ignore_ascii_case(token))
+        .filter_map(|t| Pubkey::from_str(&t.mint).ok())
+        .collect::<Vec<_>>();
+    if mints.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Unknown token symbol {}. Add its mint with `registry add <MINT> --symbol {}`",
+            token,
+            token
+        ));
+    }
+    Ok(mints)
+}
+
+/// Reads the header of a market account. Fails instead of panicking on accounts too short to be a market
+pub fn read_market_header(market_account_data: &[u8]) -> anyhow::Result<MarketHeader> {
+    let header_bytes = market_account_data
+        .get(..size_of::<MarketHeader>())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Account of {} bytes is too short to be a Phoenix market",
+                market_account_data.len()
+            )
+        })?;
+    bytemuck::try_pod_read_unaligned(header_bytes)
+        .map_err(|e| anyhow::anyhow!("Error getting market header. Error: {:?}", e))
+}
+
+/// Loads the market stored after the header of a market account, along with the header that sizes it
+pub fn load_market(
+    market_account_data: &[u8],
+) -> anyhow::Result<(
+    &MarketHeader,
+    &dyn Market<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>,
+)> {
+    if market_account_data.len() < size_of::<MarketHeader>() {
+        return Err(anyhow::anyhow!(
+            "Account of {} bytes is too short to be a Phoenix market",
+            market_account_data.len()
+        ));
+    }
+    let (header_bytes, market_bytes) = market_account_data.split_at(size_of::<MarketHeader>());
+    let header: &MarketHeader = bytemuck::try_from_bytes(header_bytes)
+        .map_err(|e| anyhow::anyhow!("Error getting market header. Error: {:?}", e))?;
+    let market = load_with_dispatch(&header.market_size_params, market_bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to load market. Error {:?}", e))?
+        .inner;
+    Ok((header, market))
+}
+
 pub async fn get_all_markets(client: &EllipsisClient) -> anyhow::Result<Vec<(Pubkey, Account)>> {
     // Get discriminant for market account
     let market_account_discriminant = get_discriminant("phoenix::program::accounts::MarketHeader")?;
@@ -118,6 +291,15 @@ pub async fn get_book_levels(
     client: &EllipsisClient,
     levels: u64,
 ) -> anyhow::Result<Ladder> {
+    let (market_account_data, clock) = get_market_account_and_clock(client, market_pubkey).await?;
+    get_ladder(&market_account_data, Some(&clock), levels)
+}
+
+/// Fetches the market account data and the clock at the same slot
+pub async fn get_market_account_and_clock(
+    client: &EllipsisClient,
+    market_pubkey: &Pubkey,
+) -> anyhow::Result<(Vec<u8>, Clock)> {
     // Get market account
     let mut market_and_clock = client
         .get_multiple_accounts_with_commitment(
@@ -140,23 +322,173 @@ pub async fn get_book_levels(
     let clock: Clock = bincode::deserialize(&clock_account_data)
         .map_err(|_| anyhow::Error::msg("Error deserializing clock"))?;
 
-    let (header_bytes, market_bytes) = market_account_data.split_at(size_of::<MarketHeader>());
-    let header: &MarketHeader = bytemuck::try_from_bytes(header_bytes)
-        .map_err(|e| anyhow::anyhow!("Error getting market header. Error: {:?}", e))?;
+    Ok((market_account_data, clock))
+}
 
-    // Derserialize data and load into correct type
-    let market = load_with_dispatch(&header.market_size_params, market_bytes)?.inner;
+/// Fetches the on-chain clock
+pub async fn get_clock(client: &EllipsisClient) -> anyhow::Result<Clock> {
+    let clock_account_data = client
+        .get_account_with_commitment(&sysvar::clock::id(), CommitmentConfig::confirmed())
+        .await?
+        .value
+        .ok_or_else(|| anyhow::Error::msg("Clock account not found"))?
+        .data;
+
+    bincode::deserialize(&clock_account_data)
+        .map_err(|_| anyhow::Error::msg("Error deserializing clock"))
+}
+
+// Get the first `levels` levels of the book. Expired orders are only left out when the clock is known
+pub fn get_ladder(
+    market_account_data: &[u8],
+    clock: Option<&Clock>,
+    levels: u64,
+) -> anyhow::Result<Ladder> {
+    let (_, market) = load_market(market_account_data)?;
 
     Ok(market.get_ladder_with_expiration(
         levels,
-        Some(clock.slot),
-        Some(clock.unix_timestamp as u64),
+        clock.map(|clock| clock.slot),
+        clock.map(|clock| clock.unix_timestamp as u64),
     ))
 }
 
+pub struct TraderOpenOrders {
+    pub clock: Clock,
+    pub bids: Vec<(FIFOOrderId, FIFORestingOrder)>,
+    pub asks: Vec<(FIFOOrderId, FIFORestingOrder)>,
+}
+
+// Get a trader's unexpired resting orders on a market, in book order
+pub async fn get_open_orders_for_trader(
+    client: &EllipsisClient,
+    market_pubkey: &Pubkey,
+    trader_pubkey: &Pubkey,
+) -> anyhow::Result<TraderOpenOrders> {
+    let (market_account_data, clock) = get_market_account_and_clock(client, market_pubkey).await?;
+    let (bids, asks) = get_trader_orders(&market_account_data, trader_pubkey, Some(&clock))?;
+    Ok(TraderOpenOrders { clock, bids, asks })
+}
+
+// Get a trader's resting bids and asks, in book order. Expired orders are only left out when the clock is known
+#[allow(clippy::type_complexity)]
+pub fn get_trader_orders(
+    market_account_data: &[u8],
+    trader_pubkey: &Pubkey,
+    clock: Option<&Clock>,
+) -> anyhow::Result<(
+    Vec<(FIFOOrderId, FIFORestingOrder)>,
+    Vec<(FIFOOrderId, FIFORestingOrder)>,
+)> {
+    let (_, market) = load_market(market_account_data)?;
+
+    let trader_index = market
+        .get_trader_index(trader_pubkey)
+        .ok_or_else(|| anyhow::anyhow!("Trader not found"))?;
+
+    let get_orders = |side: Side| {
+        market
+            .get_book(side)
+            .iter()
+            .filter(|(_, order)| {
+                order.trader_index as u32 == trader_index
+                    && !clock.is_some_and(|clock| {
+                        order.is_expired(clock.slot, clock.unix_timestamp as u64)
+                    })
+            })
+            .map(|(order_id, order)| (*order_id, *order))
+            .collect::<Vec<_>>()
+    };
+
+    Ok((get_orders(Side::Bid), get_orders(Side::Ask)))
+}
+
+/// Reads a market account from a file holding either the raw account data, the account data encoded
+/// as base64, or the output of `solana account --output json`
+pub fn read_market_account_file(market_pubkey: &Pubkey, path: &Path) -> anyhow::Result<Vec<u8>> {
+    #[derive(Deserialize)]
+    struct CliAccount {
+        pubkey: Option<String>,
+        account: UiAccount,
+    }
+
+    let contents = std::fs::read(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let text = std::str::from_utf8(&contents).map(str::trim).ok();
+
+    let data = match text {
+        Some(text) if text.starts_with('{') => {
+            let cli_account: CliAccount = serde_json::from_str(text).map_err(|e| {
+                anyhow::anyhow!("Invalid account JSON in {}: {}", path.display(), e)
+            })?;
+            if let Some(pubkey) = cli_account.pubkey {
+                if pubkey != market_pubkey.to_string() {
+                    return Err(anyhow::anyhow!(
+                        "{} holds account {}, not market {}",
+                        path.display(),
+                        pubkey,
+                        market_pubkey
+                    ));
+                }
+            }
+            let account: Account = cli_account.account.decode().ok_or_else(|| {
+                anyhow::anyhow!("Failed to decode account data in {}", path.display())
+            })?;
+            if account.owner != phoenix::id() {
+                return Err(anyhow::anyhow!(
+                    "Account in {} is owned by {}, not the Phoenix program",
+                    path.display(),
+                    account.owner
+                ));
+            }
+            account.data
+        }
+        Some(text) => match base64::engine::general_purpose::STANDARD.decode(text) {
+            Ok(data) => data,
+            Err(_) => contents,
+        },
+        None => contents,
+    };
+
+    if data.len() < size_of::<MarketHeader>() {
+        return Err(anyhow::anyhow!(
+            "{} is too small to be a market account ({} bytes)",
+            path.display(),
+            data.len()
+        ));
+    }
+    Ok(data)
+}
+
+/// Adds a market to the SDK. With an account file, the market is read from the file without any
+/// network access and its account data is returned. Otherwise the market is fetched from the RPC
+pub async fn add_market_from_rpc_or_file(
+    sdk: &mut SDKClient,
+    market_pubkey: &Pubkey,
+    account_file: Option<&Path>,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let Some(path) = account_file else {
+        sdk.add_market(market_pubkey).await?;
+        return Ok(None);
+    };
+    let market_account_data = read_market_account_file(market_pubkey, path)?;
+    let header = read_market_header(&market_account_data)?;
+    sdk.markets
+        .insert(*market_pubkey, MarketMetadata::from_header(&header)?);
+    Ok(Some(market_account_data))
+}
+
 pub async fn get_all_approved_seats_for_market(
     sdk: &SDKClient,
     market: &Pubkey,
+) -> anyhow::Result<Vec<(Pubkey, Account)>> {
+    get_seats_for_market_with_status(&sdk.client, market, SeatApprovalStatus::Approved).await
+}
+
+pub async fn get_seats_for_market_with_status(
+    client: &EllipsisClient,
+    market: &Pubkey,
+    status: SeatApprovalStatus,
 ) -> anyhow::Result<Vec<(Pubkey, Account)>> {
     // Get discriminant for seat account
     let seat_account_discriminant = get_discriminant("phoenix::program::accounts::Seat")?;
@@ -171,13 +503,10 @@ pub async fn get_all_approved_seats_for_market(
         .concat(),
     ));
 
-    let approved = RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
-        72,
-        SeatApprovalStatus::Approved.try_to_vec()?,
-    ));
+    let status = RpcFilterType::Memcmp(Memcmp::new_raw_bytes(72, status.try_to_vec()?));
 
     let config = RpcProgramAccountsConfig {
-        filters: Some(vec![memcmp, approved]),
+        filters: Some(vec![memcmp, status]),
         account_config: RpcAccountInfoConfig {
             encoding: Some(UiAccountEncoding::Base64),
             commitment: Some(CommitmentConfig::confirmed()),
@@ -186,23 +515,83 @@ pub async fn get_all_approved_seats_for_market(
         ..RpcProgramAccountsConfig::default()
     };
 
-    let accounts = sdk
-        .client
+    let accounts = client
         .get_program_accounts_with_config(&phoenix::id(), config)
         .await?;
     Ok(accounts)
 }
 
+/// Maximum number of accounts fetched in one getMultipleAccounts call
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Gets the seat status of each trader on a market, or `None` for traders that never requested a seat
+pub async fn get_seat_statuses(
+    client: &EllipsisClient,
+    market: &Pubkey,
+    traders: &[Pubkey],
+) -> anyhow::Result<Vec<Option<SeatApprovalStatus>>> {
+    let seat_addresses = traders
+        .iter()
+        .map(|trader| get_seat_address(market, trader).0)
+        .collect::<Vec<_>>();
+    let mut statuses = vec![];
+    for chunk in seat_addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        for account in client.get_multiple_accounts(chunk).await? {
+            statuses.push(
+                account
+                    .filter(|account| account.owner == phoenix::id())
+                    .and_then(|account| {
+                        let data = account.data.get(..size_of::<Seat>())?;
+                        bytemuck::try_pod_read_unaligned::<Seat>(data).ok()
+                    })
+                    .map(|seat| SeatApprovalStatus::from(seat.approval_status)),
+            );
+        }
+    }
+    Ok(statuses)
+}
+
 pub async fn get_market_header(
     sdk: &SDKClient,
     market_pubkey: &Pubkey,
 ) -> anyhow::Result<MarketHeader> {
     let market_account_data = sdk.client.get_account_data(market_pubkey).await?;
-    let (header_bytes, _market_bytes) = market_account_data.split_at(size_of::<MarketHeader>());
-    let header: &MarketHeader = bytemuck::try_from_bytes(header_bytes)
-        .map_err(|e| anyhow::anyhow!("Error getting market header. Error: {:?}", e))?;
+    read_market_header(&market_account_data)
+}
 
-    Ok(*header)
+pub async fn get_market_taker_fee_bps(
+    client: &EllipsisClient,
+    market_pubkey: &Pubkey,
+) -> anyhow::Result<u64> {
+    let market_account_data = client.get_account_data(market_pubkey).await?;
+    let (_, market) = load_market(&market_account_data)?;
+
+    Ok(market.get_taker_fee_bps())
+}
+
+pub async fn get_trader_state(
+    client: &EllipsisClient,
+    market_pubkey: &Pubkey,
+    trader_pubkey: &Pubkey,
+) -> anyhow::Result<Option<TraderState>> {
+    let market_account_data = client.get_account_data(market_pubkey).await?;
+    let (_, market) = load_market(&market_account_data)?;
+
+    Ok(market.get_trader_state(trader_pubkey).copied())
+}
+
+// Create the trader's base and quote token accounts for a market if they don't exist yet
+pub async fn get_token_account_setup_instructions(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+) -> anyhow::Result<Vec<Instruction>> {
+    let meta = sdk.get_market_metadata(market_pubkey).await?;
+    let trader = sdk.get_trader();
+    let mut instructions =
+        create_ata_ix_if_needed(&sdk.client, &trader, &trader, &meta.base_mint).await;
+    instructions
+        .extend(create_ata_ix_if_needed(&sdk.client, &trader, &trader, &meta.quote_mint).await);
+    Ok(instructions)
 }
 
 pub async fn get_seat_manager_data_with_market(
@@ -222,3 +611,107 @@ pub async fn get_seat_manager_data_with_pubkey(
 
     Ok(*seat_manager_data)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // Account data of a 512 bid, 512 ask, 128 seat market with three registered traders
+    const MARKET_DUMP: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/market.bin"
+    ));
+
+    // Writes the contents to a file of its own in the temp directory, removed when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: impl AsRef<[u8]>) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("phoenix-cli-{}-{}", std::process::id(), name));
+            std::fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            std::fs::remove_file(&self.0).ok();
+        }
+    }
+
+    fn cli_account_json(pubkey: &Pubkey, owner: &Pubkey, data: &[u8]) -> String {
+        serde_json::json!({
+            "pubkey": pubkey.to_string(),
+            "account": {
+                "lamports": 1_000_000_000u64,
+                "data": [base64::engine::general_purpose::STANDARD.encode(data), "base64"],
+                "owner": owner.to_string(),
+                "executable": false,
+                "rentEpoch": 0,
+                "space": data.len(),
+            },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn market_dumps_decode_in_every_format() {
+        let market = Pubkey::new_unique();
+        let files = [
+            TempFile::new("raw", MARKET_DUMP),
+            TempFile::new(
+                "base64",
+                base64::engine::general_purpose::STANDARD.encode(MARKET_DUMP) + "\n",
+            ),
+            TempFile::new(
+                "json",
+                cli_account_json(&market, &phoenix::id(), MARKET_DUMP),
+            ),
+        ];
+        for file in &files {
+            let data = read_market_account_file(&market, &file.0).unwrap();
+            assert!(
+                data == MARKET_DUMP,
+                "{} decoded differently",
+                file.0.display()
+            );
+        }
+
+        // Market accounts are read into an allocation, which is aligned unlike the included bytes
+        let data = MARKET_DUMP.to_vec();
+        let (header, market) = load_market(&data).unwrap();
+        assert_eq!(header.market_size_params.bids_size, 512);
+        assert_eq!(header.market_size_params.asks_size, 512);
+        assert_eq!(header.market_size_params.num_seats, 128);
+        assert_eq!(market.get_registered_traders().len(), 3);
+        assert_eq!(
+            read_market_header(MARKET_DUMP).unwrap().authority,
+            header.authority
+        );
+    }
+
+    #[test]
+    fn truncated_and_foreign_dumps_are_rejected() {
+        let market = Pubkey::new_unique();
+        let truncated = TempFile::new("truncated", &MARKET_DUMP[..100]);
+        let error = read_market_account_file(&market, &truncated.0).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("too small to be a market account"));
+        assert!(load_market(&MARKET_DUMP[..100]).is_err());
+        assert!(read_market_header(&MARKET_DUMP[..100]).is_err());
+
+        let owner = Pubkey::new_unique();
+        let foreign = TempFile::new("foreign", cli_account_json(&market, &owner, MARKET_DUMP));
+        let error = read_market_account_file(&market, &foreign.0).unwrap_err();
+        assert!(error.to_string().contains("not the Phoenix program"));
+
+        let other_market = TempFile::new(
+            "other",
+            cli_account_json(&Pubkey::new_unique(), &phoenix::id(), MARKET_DUMP),
+        );
+        assert!(read_market_account_file(&market, &other_market.0).is_err());
+    }
+}
@@ -1,3 +1,6 @@
 pub mod devnet_helpers;
 pub mod market_helpers;
+pub mod output_helpers;
 pub mod print_helpers;
+pub mod registry_helpers;
+pub mod transaction_helpers;
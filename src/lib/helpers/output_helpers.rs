@@ -0,0 +1,105 @@
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable output
+    #[default]
+    Text,
+    /// Pretty printed JSON
+    Json,
+    /// JSON on a single line
+    JsonCompact,
+    /// Comma separated values with a header row
+    Csv,
+}
+
+/// The result of a command, renderable in every supported output format
+pub trait CommandOutput: Serialize {
+    /// Prints the human readable form of the result
+    fn print_text(&self);
+
+    /// Column names of the CSV form of the result
+    fn csv_header(&self) -> Vec<&'static str>;
+
+    /// Rows of the CSV form of the result, each with one field per header column
+    fn csv_records(&self) -> Vec<Vec<String>>;
+}
+
+pub fn render<T: CommandOutput>(output: &T, format: OutputFormat) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Text => output.print_text(),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(output)?),
+        OutputFormat::JsonCompact => println!("{}", serde_json::to_string(output)?),
+        OutputFormat::Csv => {
+            println!("{}", format_csv_record(output.csv_header()));
+            for record in output.csv_records() {
+                println!("{}", format_csv_record(record));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Renders one result of a stream, such as a live update. The JSON formats print one document per
+/// result, and CSV prints the header only before the first result
+pub fn render_stream_item<T: CommandOutput>(
+    output: &T,
+    format: OutputFormat,
+    is_first: bool,
+) -> anyhow::Result<()> {
+    if format == OutputFormat::Csv {
+        if is_first {
+            println!("{}", format_csv_record(output.csv_header()));
+        }
+        for record in output.csv_records() {
+            println!("{}", format_csv_record(record));
+        }
+        return Ok(());
+    }
+    render(output, format)
+}
+
+pub fn format_csv_record<S: AsRef<str>>(fields: impl IntoIterator<Item = S>) -> String {
+    fields
+        .into_iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Formats an optional value as a CSV field, leaving it empty when absent
+pub fn optional_field<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_csv_record, optional_field};
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        let cases: &[(&[&str], &str)] = &[
+            (&["a", "1.5", ""], "a,1.5,"),
+            (&["a,b", "c"], "\"a,b\",c"),
+            (&["line\nbreak", "cr\r"], "\"line\nbreak\",\"cr\r\""),
+            (&["say \"hi\""], "\"say \"\"hi\"\"\""),
+        ];
+        for (fields, expected) in cases {
+            assert_eq!(format_csv_record(fields.iter()), *expected);
+        }
+    }
+
+    #[test]
+    fn missing_optional_fields_are_empty() {
+        assert_eq!(optional_field(&Some(5)), "5");
+        assert_eq!(optional_field::<u64>(&None), "");
+    }
+}
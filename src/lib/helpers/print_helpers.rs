@@ -1,56 +1,196 @@
-use std::mem::size_of;
+use std::collections::HashSet;
 
-use colored::Colorize;
+use crate::helpers::market_helpers::{get_trader_state, load_market};
+use crate::helpers::output_helpers::{optional_field, CommandOutput};
+use colored::{ColoredString, Colorize};
 use phoenix::program::status::MarketStatus;
 use phoenix::program::MarketHeader;
-use phoenix::program::{get_vault_address, load_with_dispatch};
 use phoenix::quantities::WrapperU64;
-use phoenix::state::{markets::Ladder, Side, TraderState};
+use phoenix::state::{
+    markets::{Ladder, LadderOrder},
+    Side, TraderState,
+};
 use phoenix_sdk::sdk_client::*;
-use solana_sdk::program_pack::Pack;
+use serde::Serialize;
 use solana_sdk::pubkey::Pubkey;
 
-pub fn print_book(sdk: &SDKClient, market: &Pubkey, book: &Ladder) -> anyhow::Result<()> {
+#[derive(Debug, Clone, Serialize)]
+pub struct BookLevel {
+    pub price: f64,
+    pub size: f64,
+    pub price_in_ticks: u64,
+    pub size_in_base_lots: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trader_present: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderBook {
+    pub market: String,
+    /// Bid levels, best price first
+    pub bids: Vec<BookLevel>,
+    /// Ask levels, best price first
+    pub asks: Vec<BookLevel>,
+    #[serde(skip)]
+    pub price_precision: usize,
+    #[serde(skip)]
+    pub size_precision: usize,
+    #[serde(skip)]
+    pub show_trader_markers: bool,
+}
+
+pub struct LadderLevelEntry {
+    pub tick: u64,
+    pub lots: u64,
+    pub trader_present: bool,
+}
+
+pub fn get_order_book(
+    sdk: &SDKClient,
+    market: &Pubkey,
+    book: &Ladder,
+) -> anyhow::Result<OrderBook> {
+    let to_levels = |levels: &[LadderOrder]| {
+        levels
+            .iter()
+            .map(|lvl| (lvl.price_in_ticks, lvl.size_in_base_lots, None))
+            .collect::<Vec<_>>()
+    };
+    build_order_book(
+        sdk,
+        market,
+        to_levels(&book.bids),
+        to_levels(&book.asks),
+        false,
+    )
+}
+
+pub fn get_order_book_with_trader(
+    sdk: &SDKClient,
+    market: &Pubkey,
+    bid_entries: &[LadderLevelEntry],
+    ask_entries: &[LadderLevelEntry],
+) -> anyhow::Result<OrderBook> {
+    let to_levels = |entries: &[LadderLevelEntry]| {
+        entries
+            .iter()
+            .map(|lvl| (lvl.tick, lvl.lots, Some(lvl.trader_present)))
+            .collect::<Vec<_>>()
+    };
+    build_order_book(
+        sdk,
+        market,
+        to_levels(bid_entries),
+        to_levels(ask_entries),
+        true,
+    )
+}
+
+fn build_order_book(
+    sdk: &SDKClient,
+    market: &Pubkey,
+    bids: Vec<(u64, u64, Option<bool>)>,
+    asks: Vec<(u64, u64, Option<bool>)>,
+    show_trader_markers: bool,
+) -> anyhow::Result<OrderBook> {
     let meta = sdk.get_market_metadata_from_cache(market)?;
     let raw_base_units_per_base_lot =
         meta.base_atoms_per_base_lot as f64 / meta.base_atoms_per_raw_base_unit as f64;
 
-    let asks = book.asks.iter().filter_map(|lvl| {
-        Some((
-            sdk.ticks_to_float_price(market, lvl.price_in_ticks).ok()?,
-            lvl.size_in_base_lots as f64 * raw_base_units_per_base_lot,
-        ))
-    });
-
-    let bids = book.bids.iter().filter_map(|lvl| {
-        Some((
-            sdk.ticks_to_float_price(market, lvl.price_in_ticks).ok()?,
-            lvl.size_in_base_lots as f64 * raw_base_units_per_base_lot,
-        ))
-    });
+    let to_book_levels = |levels: Vec<(u64, u64, Option<bool>)>| {
+        levels
+            .into_iter()
+            .filter_map(|(tick, lots, trader_present)| {
+                Some(BookLevel {
+                    price: sdk.ticks_to_float_price(market, tick).ok()?,
+                    size: lots as f64 * raw_base_units_per_base_lot,
+                    price_in_ticks: tick,
+                    size_in_base_lots: lots,
+                    trader_present,
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+
     let price_precision: usize = get_precision(
         10_u64.pow(meta.quote_decimals) * meta.raw_base_units_per_base_unit as u64
             / meta.tick_size_in_quote_atoms_per_base_unit,
     );
     let size_precision: usize =
         get_precision(meta.num_base_lots_per_base_unit / meta.raw_base_units_per_base_unit as u64);
-    let bid_strings = bids
-        .into_iter()
-        .map(|(price, size)| {
-            let p = format_float(price, price_precision);
-            let s = format_float(size, size_precision).green();
+
+    Ok(OrderBook {
+        market: market.to_string(),
+        bids: to_book_levels(bids),
+        asks: to_book_levels(asks),
+        price_precision,
+        size_precision,
+        show_trader_markers,
+    })
+}
+
+impl CommandOutput for OrderBook {
+    fn print_text(&self) {
+        if self.show_trader_markers {
+            print_book_with_trader(self);
+        } else if self.bids.is_empty() && self.asks.is_empty() {
+            println!("Book is empty");
+        } else {
+            print_book(self);
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "side",
+            "price",
+            "size",
+            "price_in_ticks",
+            "size_in_base_lots",
+            "trader_present",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        let bids = self.bids.iter().map(|level| ("bid", level));
+        let asks = self.asks.iter().map(|level| ("ask", level));
+        bids.chain(asks)
+            .map(|(side, level)| {
+                vec![
+                    side.to_string(),
+                    level.price.to_string(),
+                    level.size.to_string(),
+                    level.price_in_ticks.to_string(),
+                    level.size_in_base_lots.to_string(),
+                    optional_field(&level.trader_present),
+                ]
+            })
+            .collect()
+    }
+}
+
+pub fn print_book(book: &OrderBook) {
+    let price_precision = book.price_precision;
+    let size_precision = book.size_precision;
+    let bid_strings = book
+        .bids
+        .iter()
+        .map(|lvl| {
+            let p = format_float(lvl.price, price_precision);
+            let s = format_float(lvl.size, size_precision).green();
             (s, p)
         })
         .collect::<Vec<_>>();
 
     let bid_width = bid_strings.iter().map(|(s, _)| s.len()).max().unwrap_or(0) + 1;
 
-    let ask_strings = asks
-        .into_iter()
+    let ask_strings = book
+        .asks
+        .iter()
         .rev()
-        .map(|(price, size)| {
-            let p = format_float(price, price_precision);
-            let s = format_float(size, size_precision).red();
+        .map(|lvl| {
+            let p = format_float(lvl.price, price_precision);
+            let s = format_float(lvl.size, size_precision).red();
             (p, s)
         })
         .collect::<Vec<_>>();
@@ -78,52 +218,35 @@ pub fn print_book(sdk: &SDKClient, market: &Pubkey, book: &Ladder) -> anyhow::Re
         );
         println!("{}", str);
     }
-    Ok(())
 }
 
-pub struct LadderLevelEntry {
-    pub tick: u64,
-    pub lots: u64,
-    pub trader_present: bool,
+pub fn print_book_with_trader(book: &OrderBook) {
+    print_book_with_trader_and_changes(book, &HashSet::new(), &HashSet::new());
 }
 
-pub fn print_book_with_trader(
-    sdk: &SDKClient,
-    market: &Pubkey,
-    bid_entries: &[LadderLevelEntry],
-    ask_entries: &[LadderLevelEntry],
-) -> anyhow::Result<()> {
-    let meta = sdk.get_market_metadata_from_cache(market)?;
-    let raw_base_units_per_base_lot =
-        meta.base_atoms_per_base_lot as f64 / meta.base_atoms_per_raw_base_unit as f64;
-
-    let asks = ask_entries.iter().filter_map(|lvl| {
-        Some((
-            sdk.ticks_to_float_price(market, lvl.tick).ok()?,
-            lvl.lots as f64 * raw_base_units_per_base_lot,
-            lvl.trader_present,
-        ))
-    });
-
-    let bids = bid_entries.iter().filter_map(|lvl| {
-        Some((
-            sdk.ticks_to_float_price(market, lvl.tick).ok()?,
-            lvl.lots as f64 * raw_base_units_per_base_lot,
-            lvl.trader_present,
-        ))
-    });
-    let price_precision: usize = get_precision(
-        10_u64.pow(meta.quote_decimals) * meta.raw_base_units_per_base_unit as u64
-            / meta.tick_size_in_quote_atoms_per_base_unit,
-    );
-    let size_precision: usize =
-        get_precision(meta.num_base_lots_per_base_unit / meta.raw_base_units_per_base_unit as u64);
-    let bid_strings = bids
-        .into_iter()
-        .map(|(price, size, present)| {
-            let p = format_float(price, price_precision);
-            let s = format_float(size, size_precision).green();
-            let m = if present { "→".green() } else { " ".green() };
+/// Prints the book with trader markers, highlighting the bid and ask levels at the given ticks
+pub fn print_book_with_trader_and_changes(
+    book: &OrderBook,
+    changed_bid_ticks: &HashSet<u64>,
+    changed_ask_ticks: &HashSet<u64>,
+) {
+    let highlight = |s: ColoredString, changed: bool| if changed { s.reversed() } else { s };
+    let price_precision = book.price_precision;
+    let size_precision = book.size_precision;
+    let bid_strings = book
+        .bids
+        .iter()
+        .map(|lvl| {
+            let p = format_float(lvl.price, price_precision);
+            let s = highlight(
+                format_float(lvl.size, size_precision).green(),
+                changed_bid_ticks.contains(&lvl.price_in_ticks),
+            );
+            let m = if lvl.trader_present == Some(true) {
+                "→".green()
+            } else {
+                " ".green()
+            };
 
             (m, s, p)
         })
@@ -136,13 +259,21 @@ pub fn print_book_with_trader(
         .unwrap_or(0)
         + 1;
 
-    let ask_strings = asks
-        .into_iter()
+    let ask_strings = book
+        .asks
+        .iter()
         .rev()
-        .map(|(price, size, present)| {
-            let p = format_float(price, price_precision);
-            let s = format_float(size, size_precision).red();
-            let m = if present { "←".red() } else { " ".red() };
+        .map(|lvl| {
+            let p = format_float(lvl.price, price_precision);
+            let s = highlight(
+                format_float(lvl.size, size_precision).red(),
+                changed_ask_ticks.contains(&lvl.price_in_ticks),
+            );
+            let m = if lvl.trader_present == Some(true) {
+                "←".red()
+            } else {
+                " ".red()
+            };
 
             (p, s, m)
         })
@@ -176,18 +307,17 @@ pub fn print_book_with_trader(
         );
         println!("{}", str);
     }
-    Ok(())
 }
 
 pub fn get_precision(mut target: u64) -> usize {
     let mut fives = 0;
     let mut twos = 0;
     let initial = target;
-    while target > 0 && target % 5 == 0 {
+    while target > 0 && target.is_multiple_of(5) {
         target /= 5;
         fives += 1;
     }
-    while target > 0 && target % 2 == 0 {
+    while target > 0 && target.is_multiple_of(2) {
         target /= 2;
         twos += 1;
     }
@@ -216,201 +346,450 @@ pub fn format_float(float: f64, precision: usize) -> String {
     }
 }
 
-pub fn print_market_summary_data(
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketSummary {
+    /// Market name as BASE/QUOTE, when both token symbols are known
+    pub market: Option<String>,
+    pub market_address: String,
+    pub base_token: String,
+    pub quote_token: String,
+    pub authority: String,
+}
+
+pub fn get_market_summary(
     market_pubkey: &Pubkey,
     header: &MarketHeader,
     base_mint_symbol: Option<String>,
     quote_mint_symbol: Option<String>,
-) {
-    let base_pubkey = header.base_params.mint_key;
-    let quote_pubkey = header.quote_params.mint_key;
-
-    println!("--------------------------------------------");
-    if let (Some(base), Some(quote)) = (base_mint_symbol, quote_mint_symbol) {
-        println!("Market: {}/{}", base, quote);
+) -> MarketSummary {
+    MarketSummary {
+        market: get_market_name(base_mint_symbol, quote_mint_symbol),
+        market_address: market_pubkey.to_string(),
+        base_token: header.base_params.mint_key.to_string(),
+        quote_token: header.quote_params.mint_key.to_string(),
+        authority: header.authority.to_string(),
     }
-    println!("Market Address: {:?}", market_pubkey);
-    println!("Base Token: {:?}", base_pubkey);
-    println!("Quote Token: {:?}", quote_pubkey);
-    println!("Authority: {:?}", header.authority);
 }
 
-pub async fn print_market_details(
-    sdk: &SDKClient,
-    market_pubkey: &Pubkey,
-    market_metadata: &MarketMetadata,
-    market_header: &MarketHeader,
-    taker_fees: u64,
+fn get_market_name(
     base_mint_symbol: Option<String>,
     quote_mint_symbol: Option<String>,
-) -> anyhow::Result<()> {
-    let base_pubkey = market_metadata.base_mint;
-    let quote_pubkey = market_metadata.quote_mint;
-
-    let meta = sdk.get_market_metadata_from_cache(market_pubkey)?;
-
-    let base_vault = get_vault_address(market_pubkey, &base_pubkey).0;
-    let quote_vault = get_vault_address(market_pubkey, &quote_pubkey).0;
+) -> Option<String> {
+    match (base_mint_symbol, quote_mint_symbol) {
+        (Some(base), Some(quote)) => Some(format!("{}/{}", base, quote)),
+        _ => None,
+    }
+}
 
-    let base_vault_acct =
-        spl_token::state::Account::unpack(&sdk.client.get_account(&base_vault).await?.data)?;
+impl MarketSummary {
+    pub const CSV_HEADER: [&'static str; 5] = [
+        "market",
+        "market_address",
+        "base_token",
+        "quote_token",
+        "authority",
+    ];
 
-    let quote_vault_acct =
-        spl_token::state::Account::unpack(&sdk.client.get_account(&quote_vault).await?.data)?;
+    pub fn csv_record(&self) -> Vec<String> {
+        vec![
+            optional_field(&self.market),
+            self.market_address.clone(),
+            self.base_token.clone(),
+            self.quote_token.clone(),
+            self.authority.clone(),
+        ]
+    }
+}
 
-    // Get market account
-    let mut market_account_data = sdk.client.get_account_data(market_pubkey).await?;
-    let (header_bytes, market_bytes) = market_account_data.split_at_mut(size_of::<MarketHeader>());
-    let header: &MarketHeader = bytemuck::try_from_bytes(header_bytes)
-        .map_err(|e| anyhow::anyhow!("Error getting market header. Error: {:?}", e))?;
+impl CommandOutput for MarketSummary {
+    fn print_text(&self) {
+        println!("--------------------------------------------");
+        if let Some(market) = &self.market {
+            println!("Market: {}", market);
+        }
+        println!("Market Address: {}", self.market_address);
+        println!("Base Token: {}", self.base_token);
+        println!("Quote Token: {}", self.quote_token);
+        println!("Authority: {}", self.authority);
+    }
 
-    // Derserialize data and load into correct type
-    let market = load_with_dispatch(&header.market_size_params, market_bytes)?.inner;
+    fn csv_header(&self) -> Vec<&'static str> {
+        Self::CSV_HEADER.to_vec()
+    }
 
-    println!("--------------------------------------------");
-    if let (Some(base), Some(quote)) = (base_mint_symbol, quote_mint_symbol) {
-        println!("Market: {}/{}", base, quote);
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![self.csv_record()]
     }
-    println!("Market Address: {}", market_pubkey);
-    println!("Status: {}", MarketStatus::from(market_header.status));
-    println!("Authority: {}", market_header.authority);
-    println!("Sequence number: {}", market_header.market_sequence_number);
+}
 
-    println!(
-        "Base Vault balance: {:.3}",
-        get_decimal_string(base_vault_acct.amount, meta.base_decimals).parse::<f64>()?
-    );
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketSizeSummary {
+    pub bids_size: u64,
+    pub asks_size: u64,
+    pub num_seats: u64,
+}
 
-    println!(
-        "Quote Vault balance: {:.3}",
-        get_decimal_string(quote_vault_acct.amount, meta.quote_decimals).parse::<f64>()?
-    );
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketDetails {
+    /// Market name as BASE/QUOTE, when both token symbols are known
+    pub market: Option<String>,
+    pub market_address: String,
+    pub status: String,
+    pub authority: String,
+    pub sequence_number: u64,
+    /// Vault balances are unknown when the market is read from an account file
+    pub base_vault_balance: Option<f64>,
+    pub quote_vault_balance: Option<f64>,
+    pub base_token: String,
+    pub quote_token: String,
+    pub base_vault: String,
+    pub quote_vault: String,
+    pub raw_base_units_per_base_lot: String,
+    pub quote_units_per_quote_lot: String,
+    pub tick_size_in_quote_units_per_base_unit: String,
+    pub num_base_lots_per_base_unit: u64,
+    pub tick_size_in_quote_atoms_per_base_unit: u64,
+    pub taker_fee_bps: u64,
+    pub fee_recipient: String,
+    pub raw_base_units_per_base_unit: u32,
+    pub market_size_params: MarketSizeSummary,
+    pub successor: String,
+    pub uncollected_fees: String,
+    pub collected_fees: String,
+}
 
-    println!("Base Token: {}", base_pubkey);
-    println!("Quote Token: {}", quote_pubkey);
+/// Summarizes a market account. The vault balances are the base and quote vault token amounts, in atoms
+pub fn get_market_details(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+    market_account_data: &[u8],
+    vault_balances: Option<(u64, u64)>,
+    base_mint_symbol: Option<String>,
+    quote_mint_symbol: Option<String>,
+) -> anyhow::Result<MarketDetails> {
+    let market_metadata = sdk.get_market_metadata_from_cache(market_pubkey)?;
+    let base_pubkey = market_metadata.base_mint;
+    let quote_pubkey = market_metadata.quote_mint;
 
-    println!("Base vault key: {}", market_header.base_params.vault_key);
-    println!("Quote vault key: {}", market_header.quote_params.vault_key);
+    let (market_header, market) = load_market(market_account_data)?;
 
-    println!(
-        "Raw base units per base lot: {}",
-        get_decimal_string(
+    let (base_vault_balance, quote_vault_balance) = match vault_balances {
+        Some((base_amount, quote_amount)) => (
+            Some(get_decimal_string(base_amount, market_metadata.base_decimals).parse::<f64>()?),
+            Some(get_decimal_string(quote_amount, market_metadata.quote_decimals).parse::<f64>()?),
+        ),
+        None => (None, None),
+    };
+
+    Ok(MarketDetails {
+        market: get_market_name(base_mint_symbol, quote_mint_symbol),
+        market_address: market_pubkey.to_string(),
+        status: MarketStatus::from(market_header.status).to_string(),
+        authority: market_header.authority.to_string(),
+        sequence_number: market_header.market_sequence_number,
+        base_vault_balance,
+        quote_vault_balance,
+        base_token: base_pubkey.to_string(),
+        quote_token: quote_pubkey.to_string(),
+        base_vault: market_header.base_params.vault_key.to_string(),
+        quote_vault: market_header.quote_params.vault_key.to_string(),
+        raw_base_units_per_base_lot: get_decimal_string(
             market_metadata.base_atoms_per_base_lot,
-            market_metadata.base_decimals
+            market_metadata.base_decimals,
         ),
-    );
-    println!(
-        "Quote units per quote lot: {}",
-        get_decimal_string(
+        quote_units_per_quote_lot: get_decimal_string(
             market_metadata.quote_atoms_per_quote_lot,
-            market_metadata.quote_decimals
-        )
-    );
-    println!(
-        "Tick size in quote units per base unit: {}",
-        get_decimal_string(
+            market_metadata.quote_decimals,
+        ),
+        tick_size_in_quote_units_per_base_unit: get_decimal_string(
             market_metadata.tick_size_in_quote_atoms_per_base_unit,
-            market_metadata.quote_decimals
-        )
-    );
-    println!(
-        "Num base lots per base unit: {}",
-        market_metadata.num_base_lots_per_base_unit,
-    );
-    println!(
-        "Tick size in quote atoms per base unit: {}",
-        market_metadata.tick_size_in_quote_atoms_per_base_unit,
-    );
-    println!("Taker fees in basis points: {}", taker_fees);
-    println!("Fee destination pubkey: {:?}", market_header.fee_recipient);
-    println!(
-        "Raw base units per base unit: {}",
-        market_metadata.raw_base_units_per_base_unit
-    );
-    println!("Market Size Params: {:?}", market_header.market_size_params);
-    println!("Successor pubkey: {:?}", market_header.successor);
-
-    println!(
-        "Uncollected fees, in quote units: {}",
-        get_decimal_string(
+            market_metadata.quote_decimals,
+        ),
+        num_base_lots_per_base_unit: market_metadata.num_base_lots_per_base_unit,
+        tick_size_in_quote_atoms_per_base_unit: market_metadata
+            .tick_size_in_quote_atoms_per_base_unit,
+        taker_fee_bps: market.get_taker_fee_bps(),
+        fee_recipient: market_header.fee_recipient.to_string(),
+        raw_base_units_per_base_unit: market_metadata.raw_base_units_per_base_unit,
+        market_size_params: MarketSizeSummary {
+            bids_size: market_header.market_size_params.bids_size,
+            asks_size: market_header.market_size_params.asks_size,
+            num_seats: market_header.market_size_params.num_seats,
+        },
+        successor: market_header.successor.to_string(),
+        uncollected_fees: get_decimal_string(
             sdk.quote_lots_to_quote_atoms(
                 market_pubkey,
-                market.get_uncollected_fee_amount().as_u64()
+                market.get_uncollected_fee_amount().as_u64(),
             )?,
-            market_metadata.quote_decimals
-        )
-    );
-    println!(
-        "Collected fees, in quote units: {}",
-        get_decimal_string(
+            market_metadata.quote_decimals,
+        ),
+        collected_fees: get_decimal_string(
             sdk.quote_lots_to_quote_atoms(
                 market_pubkey,
-                market.get_collected_fee_amount().as_u64()
+                market.get_collected_fee_amount().as_u64(),
             )?,
-            market_metadata.quote_decimals
-        )
-    );
+            market_metadata.quote_decimals,
+        ),
+    })
+}
+
+impl CommandOutput for MarketDetails {
+    fn print_text(&self) {
+        println!("--------------------------------------------");
+        if let Some(market) = &self.market {
+            println!("Market: {}", market);
+        }
+        println!("Market Address: {}", self.market_address);
+        println!("Status: {}", self.status);
+        println!("Authority: {}", self.authority);
+        println!("Sequence number: {}", self.sequence_number);
+        match (self.base_vault_balance, self.quote_vault_balance) {
+            (Some(base_vault_balance), Some(quote_vault_balance)) => {
+                println!("Base Vault balance: {:.3}", base_vault_balance);
+                println!("Quote Vault balance: {:.3}", quote_vault_balance);
+            }
+            _ => println!("Vault balances: unknown, the market was read from an account file"),
+        }
+        println!("Base Token: {}", self.base_token);
+        println!("Quote Token: {}", self.quote_token);
+        println!("Base vault key: {}", self.base_vault);
+        println!("Quote vault key: {}", self.quote_vault);
+        println!(
+            "Raw base units per base lot: {}",
+            self.raw_base_units_per_base_lot
+        );
+        println!(
+            "Quote units per quote lot: {}",
+            self.quote_units_per_quote_lot
+        );
+        println!(
+            "Tick size in quote units per base unit: {}",
+            self.tick_size_in_quote_units_per_base_unit
+        );
+        println!(
+            "Num base lots per base unit: {}",
+            self.num_base_lots_per_base_unit
+        );
+        println!(
+            "Tick size in quote atoms per base unit: {}",
+            self.tick_size_in_quote_atoms_per_base_unit
+        );
+        println!("Taker fees in basis points: {}", self.taker_fee_bps);
+        println!("Fee destination pubkey: {}", self.fee_recipient);
+        println!(
+            "Raw base units per base unit: {}",
+            self.raw_base_units_per_base_unit
+        );
+        println!(
+            "Market Size Params: MarketSizeParams {{ bids_size: {}, asks_size: {}, num_seats: {} }}",
+            self.market_size_params.bids_size,
+            self.market_size_params.asks_size,
+            self.market_size_params.num_seats
+        );
+        println!("Successor pubkey: {}", self.successor);
+        println!(
+            "Uncollected fees, in quote units: {}",
+            self.uncollected_fees
+        );
+        println!("Collected fees, in quote units: {}", self.collected_fees);
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "market",
+            "market_address",
+            "status",
+            "authority",
+            "sequence_number",
+            "base_vault_balance",
+            "quote_vault_balance",
+            "base_token",
+            "quote_token",
+            "base_vault",
+            "quote_vault",
+            "raw_base_units_per_base_lot",
+            "quote_units_per_quote_lot",
+            "tick_size_in_quote_units_per_base_unit",
+            "num_base_lots_per_base_unit",
+            "tick_size_in_quote_atoms_per_base_unit",
+            "taker_fee_bps",
+            "fee_recipient",
+            "raw_base_units_per_base_unit",
+            "bids_size",
+            "asks_size",
+            "num_seats",
+            "successor",
+            "uncollected_fees",
+            "collected_fees",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            optional_field(&self.market),
+            self.market_address.clone(),
+            self.status.clone(),
+            self.authority.clone(),
+            self.sequence_number.to_string(),
+            optional_field(&self.base_vault_balance),
+            optional_field(&self.quote_vault_balance),
+            self.base_token.clone(),
+            self.quote_token.clone(),
+            self.base_vault.clone(),
+            self.quote_vault.clone(),
+            self.raw_base_units_per_base_lot.clone(),
+            self.quote_units_per_quote_lot.clone(),
+            self.tick_size_in_quote_units_per_base_unit.clone(),
+            self.num_base_lots_per_base_unit.to_string(),
+            self.tick_size_in_quote_atoms_per_base_unit.to_string(),
+            self.taker_fee_bps.to_string(),
+            self.fee_recipient.clone(),
+            self.raw_base_units_per_base_unit.to_string(),
+            self.market_size_params.bids_size.to_string(),
+            self.market_size_params.asks_size.to_string(),
+            self.market_size_params.num_seats.to_string(),
+            self.successor.clone(),
+            self.uncollected_fees.clone(),
+            self.collected_fees.clone(),
+        ]]
+    }
+}
 
-    Ok(())
+#[derive(Debug, Clone, Serialize)]
+pub struct TraderStateSummary {
+    pub trader: String,
+    pub base_locked: String,
+    pub base_free: String,
+    pub quote_locked: String,
+    pub quote_free: String,
 }
 
-pub fn print_trader_state(
+pub fn get_trader_state_summary(
     sdk: &SDKClient,
     market_pubkey: &Pubkey,
     pubkey: &Pubkey,
     state: &TraderState,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<TraderStateSummary> {
     let meta = sdk.get_market_metadata_from_cache(market_pubkey)?;
-    if state.base_lots_locked == 0
-        && state.base_lots_free == 0
-        && state.quote_lots_locked == 0
-        && state.quote_lots_free == 0
-    {
-        return Ok(());
-    }
-    println!("--------------------------------");
-    println!("Trader pubkey: {:?}", pubkey);
-    println!(
-        "Base token locked: {}",
-        get_decimal_string(
+    Ok(TraderStateSummary {
+        trader: pubkey.to_string(),
+        base_locked: get_decimal_string(
             sdk.base_lots_to_base_atoms(market_pubkey, state.base_lots_locked.into())?,
-            meta.base_decimals
-        )
-    );
-    println!(
-        "Base token free: {}",
-        get_decimal_string(
+            meta.base_decimals,
+        ),
+        base_free: get_decimal_string(
             sdk.base_lots_to_base_atoms(market_pubkey, state.base_lots_free.into())?,
-            meta.base_decimals
-        )
-    );
-    println!(
-        "Quote token locked: {}",
-        get_decimal_string(
+            meta.base_decimals,
+        ),
+        quote_locked: get_decimal_string(
             sdk.quote_lots_to_quote_atoms(market_pubkey, state.quote_lots_locked.into())?,
-            meta.quote_decimals
-        )
-    );
-    println!(
-        "Quote token free: {}",
-        get_decimal_string(
+            meta.quote_decimals,
+        ),
+        quote_free: get_decimal_string(
             sdk.quote_lots_to_quote_atoms(market_pubkey, state.quote_lots_free.into())?,
-            meta.quote_decimals
-        )
-    );
-    Ok(())
+            meta.quote_decimals,
+        ),
+    })
+}
+
+/// Returns the trader's state on the market, or `None` if the trader has no seat
+pub async fn get_trader_state_summary_for_market(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+    trader_pubkey: &Pubkey,
+) -> anyhow::Result<Option<TraderStateSummary>> {
+    get_trader_state(&sdk.client, market_pubkey, trader_pubkey)
+        .await?
+        .map(|state| get_trader_state_summary(sdk, market_pubkey, trader_pubkey, &state))
+        .transpose()
+}
+
+impl TraderStateSummary {
+    pub const CSV_HEADER: [&'static str; 5] = [
+        "trader",
+        "base_locked",
+        "base_free",
+        "quote_locked",
+        "quote_free",
+    ];
+
+    pub fn print_text(&self) {
+        println!("--------------------------------");
+        println!("Trader pubkey: {}", self.trader);
+        println!("Base token locked: {}", self.base_locked);
+        println!("Base token free: {}", self.base_free);
+        println!("Quote token locked: {}", self.quote_locked);
+        println!("Quote token free: {}", self.quote_free);
+    }
+
+    pub fn csv_record(&self) -> Vec<String> {
+        vec![
+            self.trader.clone(),
+            self.base_locked.clone(),
+            self.base_free.clone(),
+            self.quote_locked.clone(),
+            self.quote_free.clone(),
+        ]
+    }
+}
+
+pub fn print_trader_state_or_missing_seat(trader: &str, state: &Option<TraderStateSummary>) {
+    match state {
+        Some(state) => state.print_text(),
+        None => println!("Trader {} does not have a seat on this market", trader),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketEventRecord {
+    pub market: String,
+    pub event_type: String,
+    pub timestamp: i64,
+    pub signature: String,
+    pub slot: u64,
+    pub sequence_number: u64,
+    pub event_index: u64,
+    pub maker: Option<String>,
+    pub taker: Option<String>,
+    pub price: Option<f64>,
+    pub side: Option<String>,
+    pub quantity: Option<String>,
+    pub total_quote_fees: Option<f64>,
 }
 
-pub async fn log_market_events(
-    sdk: &mut SDKClient,
+pub async fn get_market_event_records(
+    sdk: &SDKClient,
     market_events: Vec<PhoenixEvent>,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<Vec<MarketEventRecord>> {
+    let mut records = vec![];
+    // Markets the client has not loaded are looked up once, without adding them to the client
+    let mut markets = sdk.markets.clone();
     for event in market_events {
         let market_pubkey = event.market;
-        if !sdk.markets.contains_key(&market_pubkey) {
-            sdk.add_market(&market_pubkey).await?;
-        }
-        let metadata = sdk.get_market_metadata_from_cache(&market_pubkey)?;
+        let metadata = match markets.get(&market_pubkey) {
+            Some(metadata) => *metadata,
+            None => {
+                let metadata = sdk.get_market_metadata(&market_pubkey).await?;
+                markets.insert(market_pubkey, metadata);
+                metadata
+            }
+        };
+        let record = |event_type: &str| MarketEventRecord {
+            market: event.market.to_string(),
+            event_type: event_type.to_string(),
+            timestamp: event.timestamp,
+            signature: event.signature.to_string(),
+            slot: event.slot,
+            sequence_number: event.sequence_number,
+            event_index: event.event_index,
+            maker: None,
+            taker: None,
+            price: None,
+            side: None,
+            quantity: None,
+            total_quote_fees: None,
+        };
         match event.details {
             MarketEventDetails::Fill(fill) => {
                 let Fill {
@@ -421,18 +800,17 @@ pub async fn log_market_events(
                     side_filled,
                     ..
                 } = fill;
-                let keys = initialize_log(&event, "Fill".to_string());
-                let fill_data = vec![
-                    maker.to_string(),
-                    taker.to_string(),
-                    (sdk.ticks_to_float_price(&market_pubkey, price_in_ticks))?.to_string(),
-                    format!("{:?}", side_filled),
-                    get_decimal_string(
-                        sdk.base_lots_to_base_atoms(&market_pubkey, base_lots_filled)?,
+                records.push(MarketEventRecord {
+                    maker: Some(maker.to_string()),
+                    taker: Some(taker.to_string()),
+                    price: Some(metadata.ticks_to_float_price(price_in_ticks)),
+                    side: Some(format!("{:?}", side_filled)),
+                    quantity: Some(get_decimal_string(
+                        metadata.base_lots_to_base_atoms(base_lots_filled),
                         metadata.base_decimals,
-                    ),
-                ];
-                println!("{}", finalize_log(keys, fill_data));
+                    )),
+                    ..record("Fill")
+                });
             }
             MarketEventDetails::Place(place) => {
                 let Place {
@@ -443,20 +821,16 @@ pub async fn log_market_events(
                     base_lots_placed,
                 } = place;
                 let side = Side::from_order_sequence_number(order_sequence_number);
-                let keys = initialize_log(&event, "Place".to_string());
-                let place_data = vec![
-                    maker.to_string(),
-                    "".to_string(),
-                    sdk.ticks_to_float_price(&market_pubkey, price_in_ticks)?
-                        .to_string(),
-                    format!("{:?}", side),
-                    get_decimal_string(
-                        sdk.base_lots_to_base_atoms(&market_pubkey, base_lots_placed)?,
+                records.push(MarketEventRecord {
+                    maker: Some(maker.to_string()),
+                    price: Some(metadata.ticks_to_float_price(price_in_ticks)),
+                    side: Some(format!("{:?}", side)),
+                    quantity: Some(get_decimal_string(
+                        metadata.base_lots_to_base_atoms(base_lots_placed),
                         metadata.base_decimals,
-                    ),
-                ];
-
-                println!("{}", finalize_log(keys, place_data));
+                    )),
+                    ..record("Place")
+                });
             }
             MarketEventDetails::Reduce(reduce) => {
                 let Reduce {
@@ -467,38 +841,140 @@ pub async fn log_market_events(
                     ..
                 } = reduce;
                 let side = Side::from_order_sequence_number(order_sequence_number);
-                let keys = initialize_log(&event, "Reduce".to_string());
-
-                let reduce_data = vec![
-                    maker.to_string(),
-                    "".to_string(),
-                    sdk.ticks_to_float_price(&market_pubkey, price_in_ticks)?
-                        .to_string(),
-                    format!("{:?}", side),
-                    get_decimal_string(
-                        sdk.base_lots_to_base_atoms(&market_pubkey, base_lots_removed)?,
+                records.push(MarketEventRecord {
+                    maker: Some(maker.to_string()),
+                    price: Some(metadata.ticks_to_float_price(price_in_ticks)),
+                    side: Some(format!("{:?}", side)),
+                    quantity: Some(get_decimal_string(
+                        metadata.base_lots_to_base_atoms(base_lots_removed),
                         metadata.base_decimals,
-                    ),
-                ];
-                println!("{}", finalize_log(keys, reduce_data));
+                    )),
+                    ..record("Reduce")
+                });
+            }
+            MarketEventDetails::Evict(evict) => {
+                let Evict {
+                    order_sequence_number,
+                    maker,
+                    price_in_ticks,
+                    base_lots_evicted,
+                } = evict;
+                let side = Side::from_order_sequence_number(order_sequence_number);
+                records.push(MarketEventRecord {
+                    maker: Some(maker.to_string()),
+                    price: Some(metadata.ticks_to_float_price(price_in_ticks)),
+                    side: Some(format!("{:?}", side)),
+                    quantity: Some(get_decimal_string(
+                        metadata.base_lots_to_base_atoms(base_lots_evicted),
+                        metadata.base_decimals,
+                    )),
+                    ..record("Evict")
+                });
             }
             MarketEventDetails::FillSummary(fill_summary) => {
                 let FillSummary {
                     total_quote_fees, ..
                 } = fill_summary;
-                println!(
-                    "Total quote token fees paid: {}",
-                    sdk.quote_atoms_to_quote_units_as_float(&market_pubkey, total_quote_fees)?
-                );
+                records.push(MarketEventRecord {
+                    total_quote_fees: Some(
+                        metadata.quote_atoms_to_quote_units_as_float(total_quote_fees),
+                    ),
+                    ..record("FillSummary")
+                });
             }
             _ => {
                 continue;
             }
         }
     }
-    Ok(())
+    Ok(records)
+}
+
+impl MarketEventRecord {
+    pub const CSV_HEADER: [&'static str; 13] = [
+        "market",
+        "event_type",
+        "timestamp",
+        "signature",
+        "slot",
+        "sequence_number",
+        "event_index",
+        "maker",
+        "taker",
+        "price",
+        "side",
+        "quantity",
+        "total_quote_fees",
+    ];
+
+    pub fn csv_record(&self) -> Vec<String> {
+        vec![
+            self.market.clone(),
+            self.event_type.clone(),
+            self.timestamp.to_string(),
+            self.signature.clone(),
+            self.slot.to_string(),
+            self.sequence_number.to_string(),
+            self.event_index.to_string(),
+            optional_field(&self.maker),
+            optional_field(&self.taker),
+            optional_field(&self.price),
+            optional_field(&self.side),
+            optional_field(&self.quantity),
+            optional_field(&self.total_quote_fees),
+        ]
+    }
+
+    pub fn format_text(&self) -> String {
+        if let Some(total_quote_fees) = self.total_quote_fees {
+            return format!("Total quote token fees paid: {}", total_quote_fees);
+        }
+        let keys = initialize_log(self);
+        let data = vec![
+            optional_field(&self.maker),
+            optional_field(&self.taker),
+            optional_field(&self.price),
+            optional_field(&self.side),
+            optional_field(&self.quantity),
+        ];
+        finalize_log(keys, data)
+    }
 }
-pub fn initialize_log(event: &PhoenixEvent, event_type: String) -> Vec<String> {
+
+impl CommandOutput for MarketEventRecord {
+    fn print_text(&self) {
+        println!("{}", self.format_text());
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        Self::CSV_HEADER.to_vec()
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![self.csv_record()]
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketEvents {
+    pub events: Vec<MarketEventRecord>,
+}
+
+impl CommandOutput for MarketEvents {
+    fn print_text(&self) {
+        self.events.iter().for_each(|event| event.print_text());
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        MarketEventRecord::CSV_HEADER.to_vec()
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        self.events.iter().map(|event| event.csv_record()).collect()
+    }
+}
+
+pub fn initialize_log(event: &MarketEventRecord) -> Vec<String> {
     let base_schema: Vec<String> = vec![
         "market".to_string(),
         "event_type".to_string(),
@@ -508,11 +984,11 @@ pub fn initialize_log(event: &PhoenixEvent, event_type: String) -> Vec<String> {
         "sequence_number".to_string(),
         "event_index".to_string(),
     ];
-    let base = vec![
-        event.market.to_string(),
-        event_type,
+    let base = [
+        event.market.clone(),
+        event.event_type.clone(),
         event.timestamp.to_string(),
-        event.signature.to_string(),
+        event.signature.clone(),
         event.slot.to_string(),
         event.sequence_number.to_string(),
         event.event_index.to_string(),
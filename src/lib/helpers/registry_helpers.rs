@@ -0,0 +1,365 @@
+use anyhow::anyhow;
+use ellipsis_client::EllipsisClient;
+use phoenix::program::MarketHeader;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MASTER_CONFIG_URL: &str =
+    "https://raw.githubusercontent.com/Ellipsis-Labs/phoenix-sdk/master/master_config.json";
+
+// How long the downloaded config is used before it is fetched again
+pub const REGISTRY_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+const DEFAULT_REGISTRY_PATH: &str = "~/.config/phoenix-cli/registry.json";
+const CACHE_PATH: &str = "~/.cache/phoenix-cli/master_config.json";
+
+static REGISTRY_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MasterConfig {
+    #[serde(default)]
+    pub tokens: Vec<TokenConfig>,
+    #[serde(default)]
+    pub markets: Vec<MarketConfig>,
+    /// User-defined market names, mapped to market addresses
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenConfig {
+    #[serde(default)]
+    pub name: String,
+    pub symbol: String,
+    pub mint: String,
+    #[serde(default)]
+    pub logo_uri: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketConfig {
+    pub market: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+}
+
+/// Downloaded master config of every public cluster, with the time it was fetched
+#[derive(Serialize, Deserialize)]
+struct RegistryCache {
+    fetched_at: u64,
+    clusters: HashMap<String, MasterConfig>,
+}
+
+/// The two layers of the registry for one cluster. User entries take precedence over the downloaded
+/// ones for the same mint or market
+#[derive(Clone, Debug, Default)]
+pub struct RegistryLayers {
+    pub genesis_hash: String,
+    /// Name of the cluster in the downloaded config, for mainnet and devnet
+    pub cluster: Option<&'static str>,
+    pub remote: MasterConfig,
+    /// Age of the downloaded config, if one was available
+    pub remote_age: Option<Duration>,
+    pub user: MasterConfig,
+}
+
+impl RegistryLayers {
+    pub fn merged(&self) -> MasterConfig {
+        let mut config = self.user.clone();
+        for token in &self.remote.tokens {
+            if !config.tokens.iter().any(|t| t.mint == token.mint) {
+                config.tokens.push(token.clone());
+            }
+        }
+        for market in &self.remote.markets {
+            if !config.markets.iter().any(|m| m.market == market.market) {
+                config.markets.push(market.clone());
+            }
+        }
+        for (alias, market) in &self.remote.aliases {
+            config
+                .aliases
+                .entry(alias.clone())
+                .or_insert_with(|| market.clone());
+        }
+        config
+    }
+}
+
+/// Sets the user registry file from `--registry`. Without it, the registry is read from
+/// ~/.config/phoenix-cli/registry.json
+pub fn set_registry_path(path: Option<String>) -> anyhow::Result<()> {
+    let path = path.unwrap_or_else(|| DEFAULT_REGISTRY_PATH.to_string());
+    REGISTRY_PATH
+        .set(PathBuf::from(&*shellexpand::tilde(&path)))
+        .map_err(|_| anyhow!("The registry path was already set"))
+}
+
+pub fn get_registry_path() -> PathBuf {
+    REGISTRY_PATH
+        .get()
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from(&*shellexpand::tilde(DEFAULT_REGISTRY_PATH)))
+}
+
+pub fn get_registry_cache_path() -> PathBuf {
+    PathBuf::from(&*shellexpand::tilde(CACHE_PATH))
+}
+
+/// Genesis hashes of the clusters in the master config, with their names there
+pub const KNOWN_CLUSTERS: [(&str, &str); 2] = [
+    (
+        "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d",
+        "mainnet-beta",
+    ),
+    ("EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG", "devnet"),
+];
+
+pub fn get_cluster_name(genesis_hash: &str) -> Option<&'static str> {
+    KNOWN_CLUSTERS
+        .iter()
+        .find(|(hash, _)| *hash == genesis_hash)
+        .map(|(_, cluster)| *cluster)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn write_file(path: &Path, contents: &str) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| anyhow!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::write(path, contents).map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+}
+
+/// User registry entries keyed by genesis hash. A missing file is an empty registry
+pub fn read_user_registry(path: &Path) -> anyhow::Result<HashMap<String, MasterConfig>> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read registry {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| anyhow!("Invalid registry {}: {}", path.display(), e))
+}
+
+pub fn write_user_registry(
+    path: &Path,
+    registry: &HashMap<String, MasterConfig>,
+) -> anyhow::Result<()> {
+    write_file(path, &serde_json::to_string_pretty(registry)?)
+}
+
+fn read_cache() -> Option<RegistryCache> {
+    let contents = fs::read_to_string(get_registry_cache_path()).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Downloads the master config of every public cluster and stores it in the cache
+pub async fn refresh_registry_cache() -> anyhow::Result<HashMap<String, MasterConfig>> {
+    let body = reqwest::get(MASTER_CONFIG_URL)
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let clusters: HashMap<String, MasterConfig> = serde_json::from_str(&body)?;
+    let cache = RegistryCache {
+        fetched_at: now(),
+        clusters,
+    };
+    write_file(&get_registry_cache_path(), &serde_json::to_string(&cache)?)?;
+    Ok(cache.clusters)
+}
+
+// Uses the cache while it is fresh. When the download fails, a stale cache beats no symbols at all
+async fn get_remote_config(cluster: &str) -> anyhow::Result<(MasterConfig, Option<Duration>)> {
+    let cache = read_cache();
+    if let Some(cache) = cache
+        .as_ref()
+        .filter(|cache| now().saturating_sub(cache.fetched_at) < REGISTRY_CACHE_TTL.as_secs())
+    {
+        return Ok((
+            cache.clusters.get(cluster).cloned().unwrap_or_default(),
+            Some(Duration::from_secs(now().saturating_sub(cache.fetched_at))),
+        ));
+    }
+    match refresh_registry_cache().await {
+        Ok(clusters) => Ok((
+            clusters.get(cluster).cloned().unwrap_or_default(),
+            Some(Duration::ZERO),
+        )),
+        Err(e) => match cache {
+            Some(cache) => {
+                eprintln!(
+                    "Failed to refresh the market registry, using the cached copy: {}",
+                    e
+                );
+                Ok((
+                    cache.clusters.get(cluster).cloned().unwrap_or_default(),
+                    Some(Duration::from_secs(now().saturating_sub(cache.fetched_at))),
+                ))
+            }
+            None => Err(anyhow!("Failed to fetch the market registry: {}", e)),
+        },
+    }
+}
+
+/// Reads both layers of the registry for the cluster of the client. Clusters other than mainnet and
+/// devnet only have user entries
+pub async fn get_registry_layers(client: &EllipsisClient) -> anyhow::Result<RegistryLayers> {
+    let genesis_hash = client.get_genesis_hash().await?.to_string();
+    let cluster = get_cluster_name(&genesis_hash);
+    let user = read_user_registry(&get_registry_path())?
+        .remove(&genesis_hash)
+        .unwrap_or_default();
+    let (remote, remote_age) = match cluster {
+        Some(cluster) => match get_remote_config(cluster).await {
+            Ok(remote) => remote,
+            Err(e) => {
+                eprintln!("{}", e);
+                (MasterConfig::default(), None)
+            }
+        },
+        None => (MasterConfig::default(), None),
+    };
+    Ok(RegistryLayers {
+        genesis_hash,
+        cluster,
+        remote,
+        remote_age,
+        user,
+    })
+}
+
+/// Tokens and markets known for the cluster of the client, from the user registry and the cached
+/// master config
+pub async fn get_phoenix_config(client: &EllipsisClient) -> anyhow::Result<MasterConfig> {
+    Ok(get_registry_layers(client).await?.merged())
+}
+
+pub fn get_base_and_quote_symbols(
+    config: &MasterConfig,
+    header: &MarketHeader,
+) -> (Option<String>, Option<String>) {
+    let base_mint = header.base_params.mint_key;
+    let quote_mint = header.quote_params.mint_key;
+    (
+        config
+            .tokens
+            .iter()
+            .find(|t| t.mint == base_mint.to_string())
+            .map(|t| t.symbol.clone()),
+        config
+            .tokens
+            .iter()
+            .find(|t| t.mint == quote_mint.to_string())
+            .map(|t| t.symbol.clone()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(tokens: &[(&str, &str)], markets: &[(&str, &str, &str)]) -> MasterConfig {
+        MasterConfig {
+            tokens: tokens
+                .iter()
+                .map(|(symbol, mint)| TokenConfig {
+                    name: String::new(),
+                    symbol: symbol.to_string(),
+                    mint: mint.to_string(),
+                    logo_uri: String::new(),
+                })
+                .collect(),
+            markets: markets
+                .iter()
+                .map(|(market, base_mint, quote_mint)| MarketConfig {
+                    market: market.to_string(),
+                    base_mint: base_mint.to_string(),
+                    quote_mint: quote_mint.to_string(),
+                })
+                .collect(),
+            aliases: BTreeMap::new(),
+        }
+    }
+
+    fn with_aliases(mut config: MasterConfig, aliases: &[(&str, &str)]) -> MasterConfig {
+        config.aliases = aliases
+            .iter()
+            .map(|(alias, market)| (alias.to_string(), market.to_string()))
+            .collect();
+        config
+    }
+
+    #[test]
+    fn merged_prefers_user_entries_for_the_same_address() {
+        let merged = RegistryLayers {
+            remote: layer(
+                &[("SOL", "mint-a"), ("USDC", "mint-b")],
+                &[("market-1", "mint-a", "mint-b")],
+            ),
+            user: layer(&[("WSOL", "mint-a")], &[("market-1", "mint-c", "mint-b")]),
+            ..Default::default()
+        }
+        .merged();
+
+        let symbol_of = |mint: &str| {
+            merged
+                .tokens
+                .iter()
+                .find(|t| t.mint == mint)
+                .map(|t| t.symbol.as_str())
+        };
+        assert_eq!(symbol_of("mint-a"), Some("WSOL"));
+        assert_eq!(symbol_of("mint-b"), Some("USDC"));
+        assert_eq!(merged.tokens.len(), 2);
+        assert_eq!(merged.markets.len(), 1);
+        assert_eq!(merged.markets[0].base_mint, "mint-c");
+    }
+
+    #[test]
+    fn merged_falls_back_to_the_remote_layer() {
+        let remote = layer(&[("SOL", "mint-a")], &[("market-1", "mint-a", "mint-b")]);
+        let merged = RegistryLayers {
+            remote: remote.clone(),
+            ..Default::default()
+        }
+        .merged();
+        assert_eq!(
+            serde_json::to_value(&merged).unwrap(),
+            serde_json::to_value(&remote).unwrap()
+        );
+    }
+
+    #[test]
+    fn aliases_merge_with_user_names_taking_precedence() {
+        let merged = RegistryLayers {
+            remote: with_aliases(
+                layer(&[], &[]),
+                &[("sol", "market-1"), ("main", "market-1")],
+            ),
+            user: with_aliases(layer(&[], &[]), &[("sol", "market-2")]),
+            ..Default::default()
+        }
+        .merged();
+        assert_eq!(
+            merged.aliases,
+            BTreeMap::from([
+                ("main".to_string(), "market-1".to_string()),
+                ("sol".to_string(), "market-2".to_string()),
+            ])
+        );
+    }
+}
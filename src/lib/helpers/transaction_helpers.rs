@@ -0,0 +1,1187 @@
+use crate::helpers::output_helpers::{optional_field, CommandOutput};
+use crate::helpers::print_helpers::{get_market_event_records, MarketEventRecord};
+use base64::Engine;
+use clap::ValueEnum;
+use ellipsis_client::EllipsisClient;
+use phoenix::program::{PhoenixError, PhoenixInstruction};
+use phoenix_sdk::sdk_client::SDKClient;
+use serde::Serialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::nonce_utils;
+use solana_client::rpc_config::{
+    RpcSendTransactionConfig, RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+    RpcTransactionConfig,
+};
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_sdk::account::Account;
+use solana_sdk::bs58;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::message::Message;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sanitize::Sanitize;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{uses_durable_nonce, Transaction, TransactionError};
+use solana_transaction_status::{
+    UiInnerInstructions, UiInstruction, UiParsedInstruction, UiTransactionEncoding,
+};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Highest compute unit limit a transaction can request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Extra compute units on top of the simulated usage when the limit is set automatically, in percent
+pub const AUTO_COMPUTE_UNIT_MARGIN_PERCENT: u64 = 10;
+
+/// Number of attempts to fetch a confirmed transaction before its fee and compute usage are reported as unknown
+const TRANSACTION_FETCH_ATTEMPTS: u32 = 20;
+
+/// How often the status of a sent transaction is checked
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often a sent transaction is sent again until it lands
+const RESEND_INTERVAL: Duration = Duration::from_secs(2);
+
+// Check whether the instructions can be sent in a single transaction signed by the payer, along with
+// the compute budget and nonce instructions that are prepended when sending
+pub fn instructions_fit_in_transaction(instructions: &[Instruction], payer: &Pubkey) -> bool {
+    let options = get_transaction_options();
+    let compute_unit_limit = options
+        .compute_unit_limit
+        .or(options.auto_compute.then_some(MAX_COMPUTE_UNIT_LIMIT));
+    let mut instructions =
+        with_compute_budget(instructions, options.priority_fee, compute_unit_limit);
+    if let Some(nonce_account) = options.nonce_account {
+        instructions.insert(
+            0,
+            system_instruction::advance_nonce_account(&nonce_account, payer),
+        );
+    }
+    let fee_payer = options.fee_payer.unwrap_or(*payer);
+    let transaction = Transaction::new_unsigned(Message::new(&instructions, Some(&fee_payer)));
+    bincode::serialized_size(&transaction)
+        .map(|size| size as usize <= PACKET_DATA_SIZE)
+        .unwrap_or(false)
+}
+
+/// Packs the setup instructions and the indexed instructions, in order, into as few transactions
+/// as possible. The setup instructions open the first transaction. Returns the instructions of each
+/// transaction with the indices of the indexed instructions it holds
+pub fn pack_instructions(
+    setup_instructions: Vec<Instruction>,
+    indexed_instructions: Vec<(usize, Instruction)>,
+    payer: &Pubkey,
+) -> Vec<(Vec<Instruction>, Vec<usize>)> {
+    let mut batches = vec![];
+    let mut instructions = setup_instructions;
+    let mut indices = vec![];
+    for (index, instruction) in indexed_instructions {
+        instructions.push(instruction);
+        if !indices.is_empty() && !instructions_fit_in_transaction(&instructions, payer) {
+            let instruction = instructions.pop().unwrap();
+            batches.push((
+                std::mem::replace(&mut instructions, vec![instruction]),
+                std::mem::take(&mut indices),
+            ));
+        }
+        indices.push(index);
+    }
+    if !indices.is_empty() {
+        batches.push((instructions, indices));
+    }
+    batches
+}
+
+/// How transactions built by commands are handled, set once from the global CLI flags
+#[derive(Debug, Clone, Default)]
+pub struct TransactionOptions {
+    /// Simulate the transaction instead of sending it
+    pub simulate: bool,
+    /// Priority fee in micro-lamports per compute unit
+    pub priority_fee: Option<u64>,
+    /// Compute unit limit requested by the transaction
+    pub compute_unit_limit: Option<u32>,
+    /// Simulate the transaction before sending it and set the compute unit limit to the usage plus a margin
+    pub auto_compute: bool,
+    /// Output the unsigned transaction instead of sending it
+    pub export_tx: bool,
+    /// Output the transaction signed by the local keypairs instead of sending it
+    pub sign_only: bool,
+    /// Encoding of exported transactions
+    pub tx_encoding: TransactionEncoding,
+    /// Durable nonce account whose nonce replaces the recent blockhash of exported transactions
+    pub nonce_account: Option<Pubkey>,
+    /// Fee payer of exported transactions, when it is not the payer
+    pub fee_payer: Option<Pubkey>,
+    /// Authority that signs the admin instructions of exported transactions, when it is not the payer
+    pub authority: Option<Pubkey>,
+    /// Build exported admin transactions without checking the authority against the market
+    pub skip_authority_check: bool,
+}
+
+impl TransactionOptions {
+    /// Whether transactions are printed for signing elsewhere instead of sent
+    pub fn is_offline(&self) -> bool {
+        self.export_tx || self.sign_only
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionEncoding {
+    Base58,
+    #[default]
+    Base64,
+}
+
+static TRANSACTION_OPTIONS: OnceLock<TransactionOptions> = OnceLock::new();
+
+pub fn set_transaction_options(options: TransactionOptions) -> anyhow::Result<()> {
+    if options
+        .compute_unit_limit
+        .is_some_and(|limit| limit == 0 || limit > MAX_COMPUTE_UNIT_LIMIT)
+    {
+        return Err(anyhow::anyhow!(
+            "Compute unit limit must be between 1 and {}",
+            MAX_COMPUTE_UNIT_LIMIT
+        ));
+    }
+    if (options.nonce_account.is_some()
+        || options.fee_payer.is_some()
+        || options.authority.is_some()
+        || options.skip_authority_check)
+        && !options.is_offline()
+    {
+        return Err(anyhow::anyhow!(
+            "A nonce account, fee payer or authority, or skipping the authority check, is only allowed when exporting or signing offline"
+        ));
+    }
+    TRANSACTION_OPTIONS
+        .set(options)
+        .map_err(|_| anyhow::anyhow!("Transaction options are already set"))
+}
+
+pub fn get_transaction_options() -> TransactionOptions {
+    TRANSACTION_OPTIONS.get().cloned().unwrap_or_default()
+}
+
+/// Refuses to export several transactions with a durable nonce. Each exported transaction would
+/// advance the same nonce, so only the first one could land
+pub fn check_nonce_batches(num_transactions: usize) -> anyhow::Result<()> {
+    let options = get_transaction_options();
+    if num_transactions > 1 && options.nonce_account.is_some() && options.is_offline() {
+        return Err(anyhow::anyhow!(
+            "The instructions need {} transactions, but a durable nonce can only be used by one",
+            num_transactions
+        ));
+    }
+    Ok(())
+}
+
+/// Account that signs admin instructions as the authority: the one from --authority when exporting,
+/// such as a multisig, and otherwise the payer
+pub fn get_signing_authority(client: &EllipsisClient) -> Pubkey {
+    get_transaction_options()
+        .authority
+        .unwrap_or_else(|| client.payer.pubkey())
+}
+
+/// Prepends the ComputeBudget instructions setting the priority fee and compute unit limit, if any
+pub fn with_compute_budget(
+    instructions: &[Instruction],
+    priority_fee: Option<u64>,
+    compute_unit_limit: Option<u32>,
+) -> Vec<Instruction> {
+    let mut budget_instructions = vec![];
+    if let Some(compute_unit_limit) = compute_unit_limit {
+        budget_instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(
+            compute_unit_limit,
+        ));
+    }
+    if let Some(priority_fee) = priority_fee {
+        budget_instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
+            priority_fee,
+        ));
+    }
+    budget_instructions.extend_from_slice(instructions);
+    budget_instructions
+}
+
+/// Signs and sends the instructions with the client's payer, unless the transaction options say
+/// otherwise. When simulating or exporting, nothing is sent and the simulation report or the encoded
+/// transaction is returned instead of the signature
+///
+/// A sent transaction is resent until it reaches the client's commitment or its blockhash expires,
+/// and its receipt is printed to stderr. A transaction that lands but fails is returned as an error
+pub async fn send_instructions(
+    sdk: &SDKClient,
+    instructions: Vec<Instruction>,
+    signers: Vec<&Keypair>,
+) -> anyhow::Result<TransactionOutcome<Signature>> {
+    let client = &sdk.client;
+    let options = get_transaction_options();
+    if options.simulate {
+        let compute_unit_limit = options
+            .compute_unit_limit
+            .or(options.auto_compute.then_some(MAX_COMPUTE_UNIT_LIMIT));
+        let instructions =
+            with_compute_budget(&instructions, options.priority_fee, compute_unit_limit);
+        let report = simulate_instructions(sdk, &instructions).await?;
+        return Ok(TransactionOutcome::Unsent(UnsentTransaction::Simulated(
+            report,
+        )));
+    }
+
+    let compute_unit_limit = get_compute_unit_limit(sdk, &instructions, &options).await?;
+    let instructions = with_compute_budget(&instructions, options.priority_fee, compute_unit_limit);
+    if options.is_offline() {
+        let transaction =
+            build_offline_transaction(client, instructions, signers, &options).await?;
+        return Ok(TransactionOutcome::Unsent(UnsentTransaction::Exported(
+            transaction,
+        )));
+    }
+
+    let payer = client.payer.pubkey();
+    let blockhash = client
+        .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+        .await?
+        .0;
+    let mut transaction = Transaction::new_unsigned(Message::new_with_blockhash(
+        &instructions,
+        Some(&payer),
+        &blockhash,
+    ));
+    let local_signers = get_local_signers(&transaction, &client.payer, signers);
+    let missing_signers = transaction
+        .message
+        .signer_keys()
+        .into_iter()
+        .filter(|key| !local_signers.iter().any(|signer| signer.pubkey() == **key))
+        .map(|key| key.to_string())
+        .collect::<Vec<_>>();
+    if !missing_signers.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Transaction needs signatures from {}, but no keypair was given for them",
+            missing_signers.join(", ")
+        ));
+    }
+    transaction.try_sign(&local_signers, blockhash)?;
+
+    let mut receipt = send_and_confirm_transaction(sdk, &transaction).await?;
+    receipt.priority_fee = options.priority_fee;
+    receipt.compute_unit_limit = compute_unit_limit;
+    // Reported on stderr so the command output stays parseable
+    eprintln!("{}", receipt);
+    match receipt.error {
+        Some(error) => Err(anyhow::anyhow!(
+            "Transaction {} failed: {}",
+            receipt.signature,
+            error
+        )),
+        None => Ok(TransactionOutcome::Sent(transaction.signatures[0])),
+    }
+}
+
+/// Sends a signed transaction and resends it until it reaches the client's commitment. The
+/// transaction is given up on once its blockhash expires, or once its durable nonce has been
+/// advanced by another transaction, without it having landed
+pub async fn send_and_confirm_transaction(
+    sdk: &SDKClient,
+    transaction: &Transaction,
+) -> anyhow::Result<TransactionReceipt> {
+    let client = &sdk.client;
+    let signature = transaction.signatures[0];
+    let commitment = client.commitment();
+    // The first send is simulated by the RPC node, so a transaction that would fail is not sent and
+    // pays no fee. Resends skip the simulation, which may no longer match once the first send lands
+    let first_send_config = RpcSendTransactionConfig {
+        skip_preflight: false,
+        preflight_commitment: Some(commitment.commitment),
+        max_retries: Some(0),
+        ..RpcSendTransactionConfig::default()
+    };
+    let resend_config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        max_retries: Some(0),
+        ..RpcSendTransactionConfig::default()
+    };
+    client
+        .send_transaction_with_config(transaction, first_send_config)
+        .await
+        .map_err(|e| describe_preflight_failure(&signature, e, &transaction.message))?;
+    let mut last_sent = Instant::now();
+
+    let status = loop {
+        tokio::time::sleep(STATUS_POLL_INTERVAL).await;
+        let status = client
+            .get_signature_statuses(&[signature])
+            .await?
+            .value
+            .remove(0);
+        match status {
+            Some(status) if status.satisfies_commitment(commitment) => break status,
+            // Landed but not yet at the commitment, so it no longer depends on the blockhash
+            Some(_) => continue,
+            None => {}
+        }
+        if transaction_expired(client, transaction).await? {
+            return Err(anyhow::anyhow!(
+                "Transaction {} expired before it landed. It was not processed and can be retried",
+                signature
+            ));
+        }
+        if last_sent.elapsed() >= RESEND_INTERVAL {
+            // A failed resend is not fatal, the transaction may still land from an earlier send
+            client
+                .send_transaction_with_config(transaction, resend_config)
+                .await
+                .ok();
+            last_sent = Instant::now();
+        }
+    };
+
+    let mut receipt = TransactionReceipt {
+        signature: signature.to_string(),
+        commitment: format!("{:?}", commitment.commitment).to_lowercase(),
+        slot: status.slot,
+        fee: None,
+        compute_units_consumed: None,
+        compute_unit_limit: None,
+        priority_fee: None,
+        success: status.err.is_none(),
+        error: status
+            .err
+            .as_ref()
+            .map(|err| describe_transaction_error(err, &transaction.message)),
+        events: vec![],
+    };
+    // The status does not hold the fee, compute usage or inner instructions, which are read from
+    // the transaction once the RPC node serves it
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(if commitment.is_finalized() {
+            CommitmentConfig::finalized()
+        } else {
+            CommitmentConfig::confirmed()
+        }),
+        max_supported_transaction_version: Some(0),
+    };
+    for _ in 0..TRANSACTION_FETCH_ATTEMPTS {
+        if let Ok(confirmed) = client.get_transaction_with_config(&signature, config).await {
+            receipt.slot = confirmed.slot;
+            if let Some(meta) = confirmed.transaction.meta {
+                receipt.fee = Some(meta.fee);
+                receipt.compute_units_consumed = meta.compute_units_consumed.into();
+                let inner_instructions: Option<Vec<UiInnerInstructions>> =
+                    meta.inner_instructions.into();
+                receipt.events = get_phoenix_event_records(
+                    sdk,
+                    &signature,
+                    &transaction.message.account_keys,
+                    &inner_instructions.unwrap_or_default(),
+                )
+                .await?;
+            }
+            break;
+        }
+        tokio::time::sleep(STATUS_POLL_INTERVAL).await;
+    }
+    Ok(receipt)
+}
+
+// Names the error and shows the logs of a transaction the RPC node refused to send after simulating it
+fn describe_preflight_failure(
+    signature: &Signature,
+    error: ClientError,
+    message: &Message,
+) -> anyhow::Error {
+    if let ClientErrorKind::RpcError(RpcError::RpcResponseError {
+        data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+        ..
+    }) = error.kind()
+    {
+        if let Some(err) = &result.err {
+            return anyhow::anyhow!(
+                "Transaction {} failed simulation and was not sent: {}. Logs:\n{}",
+                signature,
+                describe_transaction_error(err, message),
+                result.logs.clone().unwrap_or_default().join("\n")
+            );
+        }
+    }
+    anyhow::anyhow!("Failed to send transaction {}: {}", signature, error)
+}
+
+/// Whether the transaction can no longer land: its blockhash is too old, or its durable nonce no
+/// longer holds the transaction's blockhash
+async fn transaction_expired(
+    client: &EllipsisClient,
+    transaction: &Transaction,
+) -> anyhow::Result<bool> {
+    let blockhash = transaction.message.recent_blockhash;
+    match uses_durable_nonce(transaction)
+        .and_then(|instruction| instruction.accounts.first())
+        .and_then(|index| transaction.message.account_keys.get(*index as usize))
+    {
+        Some(nonce_account) => {
+            let account = nonce_utils::nonblocking::get_account_with_commitment(
+                client,
+                nonce_account,
+                CommitmentConfig::confirmed(),
+            )
+            .await?;
+            Ok(nonce_utils::data_from_account(&account)?.blockhash() != blockhash)
+        }
+        None => Ok(!client
+            .is_blockhash_valid(&blockhash, CommitmentConfig::confirmed())
+            .await?),
+    }
+}
+
+/// The keypairs out of the payer and the given signers that the transaction needs signatures from
+fn get_local_signers<'a>(
+    transaction: &Transaction,
+    payer: &'a Keypair,
+    signers: Vec<&'a Keypair>,
+) -> Vec<&'a Keypair> {
+    let required_signers = transaction.message.signer_keys();
+    let mut local_signers: Vec<&Keypair> = vec![];
+    for signer in std::iter::once(payer).chain(signers) {
+        if required_signers.contains(&&signer.pubkey())
+            && !local_signers.iter().any(|s| s.pubkey() == signer.pubkey())
+        {
+            local_signers.push(signer);
+        }
+    }
+    local_signers
+}
+
+/// Describes a transaction error, naming the Phoenix error when a Phoenix instruction failed with
+/// a custom error code
+pub fn describe_transaction_error(error: &TransactionError, message: &Message) -> String {
+    if let TransactionError::InstructionError(index, InstructionError::Custom(code)) = error {
+        let program_id = message
+            .instructions
+            .get(*index as usize)
+            .and_then(|instruction| {
+                message
+                    .account_keys
+                    .get(instruction.program_id_index as usize)
+            });
+        if program_id == Some(&phoenix::id()) {
+            if let Some(phoenix_error) = PHOENIX_ERRORS
+                .iter()
+                .find(|phoenix_error| u32::from(**phoenix_error) == *code)
+            {
+                return format!(
+                    "Phoenix error {:?} ({}) in instruction {}",
+                    phoenix_error, phoenix_error, index
+                );
+            }
+        }
+    }
+    error.to_string()
+}
+
+/// Phoenix errors by their custom error code
+const PHOENIX_ERRORS: [PhoenixError; 26] = [
+    PhoenixError::InvalidMarketParameters,
+    PhoenixError::InvalidMarketAuthority,
+    PhoenixError::FailedToLoadMarketFromAccount,
+    PhoenixError::MarketAlreadyInitialized,
+    PhoenixError::MarketUninitialized,
+    PhoenixError::InvalidStateTransition,
+    PhoenixError::InvalidMarketSigner,
+    PhoenixError::InvalidLotSize,
+    PhoenixError::InvalidTickSize,
+    PhoenixError::InvalidMint,
+    PhoenixError::InvalidBaseVault,
+    PhoenixError::InvalidQuoteVault,
+    PhoenixError::InvalidBaseAccount,
+    PhoenixError::InvalidQuoteAccount,
+    PhoenixError::TooManyEvents,
+    PhoenixError::NewOrderError,
+    PhoenixError::ReduceOrderError,
+    PhoenixError::CancelMultipleOrdersError,
+    PhoenixError::WithdrawFundsError,
+    PhoenixError::RemoveEmptyOrdersError,
+    PhoenixError::TraderNotFound,
+    PhoenixError::InvalidSeatStatus,
+    PhoenixError::EvictionError,
+    PhoenixError::NonEmptyScratchBuffer,
+    PhoenixError::FailedToSerializeEvent,
+    PhoenixError::FailedToFlushBuffer,
+];
+
+/// Outcome of a transaction that reached the client's commitment
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionReceipt {
+    pub signature: String,
+    /// Commitment level the transaction was confirmed at
+    pub commitment: String,
+    pub slot: u64,
+    /// Fee paid in lamports, or `None` if the transaction could not be fetched
+    pub fee: Option<u64>,
+    pub compute_units_consumed: Option<u64>,
+    pub compute_unit_limit: Option<u32>,
+    /// Priority fee in micro-lamports per compute unit
+    pub priority_fee: Option<u64>,
+    pub success: bool,
+    pub error: Option<String>,
+    /// Phoenix events decoded from the confirmed transaction
+    pub events: Vec<MarketEventRecord>,
+}
+
+impl fmt::Display for TransactionReceipt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Transaction {} reached {} commitment in slot {}",
+            self.signature, self.commitment, self.slot
+        )?;
+        match &self.error {
+            None => writeln!(f, "  Result: success")?,
+            Some(error) => writeln!(f, "  Result: failed with {}", error)?,
+        }
+        writeln!(f, "  Fee: {} lamports", optional_or_unknown(&self.fee))?;
+        write!(
+            f,
+            "  Compute units: {} consumed",
+            optional_or_unknown(&self.compute_units_consumed)
+        )?;
+        if let Some(compute_unit_limit) = self.compute_unit_limit {
+            write!(f, " of a {} limit", compute_unit_limit)?;
+        }
+        if let Some(priority_fee) = self.priority_fee {
+            write!(
+                f,
+                "\n  Priority fee: {} micro-lamports per CU",
+                priority_fee
+            )?;
+        }
+        if !self.events.is_empty() {
+            write!(f, "\n  Phoenix events:")?;
+            for event in &self.events {
+                write!(f, "\n    {}", event.format_text())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CommandOutput for TransactionReceipt {
+    fn print_text(&self) {
+        println!("{}", self);
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "signature",
+            "commitment",
+            "slot",
+            "fee",
+            "compute_units_consumed",
+            "success",
+            "error",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.signature.clone(),
+            self.commitment.clone(),
+            self.slot.to_string(),
+            optional_field(&self.fee),
+            optional_field(&self.compute_units_consumed),
+            self.success.to_string(),
+            optional_field(&self.error),
+        ]]
+    }
+}
+
+fn optional_or_unknown<T: ToString>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Returns the compute unit limit to send the instructions with, simulating them first when the limit
+/// is set automatically
+async fn get_compute_unit_limit(
+    sdk: &SDKClient,
+    instructions: &[Instruction],
+    options: &TransactionOptions,
+) -> anyhow::Result<Option<u32>> {
+    if options.auto_compute {
+        let (compute_unit_limit, _) =
+            get_auto_compute_unit_limit(sdk, instructions, options.priority_fee).await?;
+        Ok(Some(compute_unit_limit))
+    } else {
+        Ok(options.compute_unit_limit)
+    }
+}
+
+/// Builds the transaction for signing elsewhere. With a nonce account, the nonce replaces the recent
+/// blockhash and the nonce is advanced first, so the transaction stays valid until it is used. With
+/// `sign_only`, the transaction is signed by the local keypairs it requires
+async fn build_offline_transaction(
+    client: &EllipsisClient,
+    mut instructions: Vec<Instruction>,
+    signers: Vec<&Keypair>,
+    options: &TransactionOptions,
+) -> anyhow::Result<EncodedTransaction> {
+    let fee_payer = options.fee_payer.unwrap_or_else(|| client.payer.pubkey());
+    let blockhash = match options.nonce_account {
+        Some(nonce_account) => {
+            let account = nonce_utils::nonblocking::get_account_with_commitment(
+                client,
+                &nonce_account,
+                client.commitment(),
+            )
+            .await?;
+            let nonce_data = nonce_utils::data_from_account(&account)?;
+            instructions.insert(
+                0,
+                system_instruction::advance_nonce_account(&nonce_account, &nonce_data.authority),
+            );
+            nonce_data.blockhash()
+        }
+        None => client.get_latest_blockhash().await?,
+    };
+
+    let mut transaction = Transaction::new_unsigned(Message::new_with_blockhash(
+        &instructions,
+        Some(&fee_payer),
+        &blockhash,
+    ));
+    if options.sign_only {
+        let local_signers = get_local_signers(&transaction, &client.payer, signers);
+        transaction.try_partial_sign(&local_signers, blockhash)?;
+    }
+    EncodedTransaction::new(&transaction, options.tx_encoding)
+}
+
+/// Simulates the instructions with the highest compute unit limit and returns the limit to send them with,
+/// the measured usage plus a margin, along with the measured usage
+pub async fn get_auto_compute_unit_limit(
+    sdk: &SDKClient,
+    instructions: &[Instruction],
+    priority_fee: Option<u64>,
+) -> anyhow::Result<(u32, u64)> {
+    let report = simulate_instructions(
+        sdk,
+        &with_compute_budget(instructions, priority_fee, Some(MAX_COMPUTE_UNIT_LIMIT)),
+    )
+    .await?;
+    if let Some(error) = report.error {
+        return Err(anyhow::anyhow!(
+            "Simulating the transaction to set its compute unit limit failed with {}. Logs:\n{}",
+            error,
+            report.logs.join("\n")
+        ));
+    }
+    let units_consumed = report
+        .units_consumed
+        .ok_or_else(|| anyhow::anyhow!("Simulation did not report the compute units consumed"))?;
+    let compute_unit_limit = (units_consumed
+        + units_consumed * AUTO_COMPUTE_UNIT_MARGIN_PERCENT / 100)
+        .min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32;
+    Ok((compute_unit_limit, units_consumed))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountChange {
+    pub pubkey: String,
+    /// Lamports before and after the transaction, or `None` if the account does not exist
+    pub lamports_before: Option<u64>,
+    pub lamports_after: Option<u64>,
+    pub data_len_before: Option<usize>,
+    pub data_len_after: Option<usize>,
+    /// Number of bytes of account data that differ, counting any change in length
+    pub bytes_changed: usize,
+    pub owner_after: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationReport {
+    pub success: bool,
+    pub error: Option<String>,
+    pub units_consumed: Option<u64>,
+    pub logs: Vec<String>,
+    /// Writable accounts of the transaction that the simulation would change
+    pub account_changes: Vec<AccountChange>,
+    /// Phoenix events decoded from the simulated transaction
+    pub events: Vec<MarketEventRecord>,
+}
+
+impl CommandOutput for SimulationReport {
+    fn print_text(&self) {
+        println!("Simulated transaction, nothing was sent");
+        match &self.error {
+            None => println!("Result: success"),
+            Some(error) => println!("Result: failed with {}", error),
+        }
+        if let Some(units_consumed) = self.units_consumed {
+            println!("Compute units consumed: {}", units_consumed);
+        }
+        println!();
+        println!("Program logs:");
+        self.logs.iter().for_each(|log| println!("  {}", log));
+        println!();
+        println!("Account changes:");
+        if self.account_changes.is_empty() {
+            println!("  None");
+        }
+        for change in &self.account_changes {
+            let lamports_delta = change.lamports_after.unwrap_or(0) as i128
+                - change.lamports_before.unwrap_or(0) as i128;
+            println!(
+                "  {}: lamports {} -> {} ({:+}), data {} -> {} bytes, {} bytes changed",
+                change.pubkey,
+                optional_or_none(&change.lamports_before),
+                optional_or_none(&change.lamports_after),
+                lamports_delta,
+                optional_or_none(&change.data_len_before),
+                optional_or_none(&change.data_len_after),
+                change.bytes_changed,
+            );
+        }
+        if !self.events.is_empty() {
+            println!();
+            println!("Phoenix events:");
+            self.events.iter().for_each(|event| event.print_text());
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "pubkey",
+            "lamports_before",
+            "lamports_after",
+            "data_len_before",
+            "data_len_after",
+            "bytes_changed",
+            "owner_after",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        self.account_changes
+            .iter()
+            .map(|change| {
+                vec![
+                    change.pubkey.clone(),
+                    optional_field(&change.lamports_before),
+                    optional_field(&change.lamports_after),
+                    optional_field(&change.data_len_before),
+                    optional_field(&change.data_len_after),
+                    change.bytes_changed.to_string(),
+                    optional_field(&change.owner_after),
+                ]
+            })
+            .collect()
+    }
+}
+
+fn optional_or_none<T: ToString>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "none".to_string())
+}
+
+/// A transaction built from a command's instructions that was not sent
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum UnsentTransaction {
+    /// Simulated instead of sent, with --simulate
+    Simulated(SimulationReport),
+    /// Encoded for signing or broadcasting elsewhere, with --export-tx or --sign-only
+    Exported(EncodedTransaction),
+}
+
+impl UnsentTransaction {
+    /// Error of a simulated transaction that would fail
+    pub fn simulation_error(&self) -> Option<&str> {
+        match self {
+            UnsentTransaction::Simulated(report) => report.error.as_deref(),
+            UnsentTransaction::Exported(_) => None,
+        }
+    }
+}
+
+impl CommandOutput for UnsentTransaction {
+    fn print_text(&self) {
+        match self {
+            UnsentTransaction::Simulated(report) => report.print_text(),
+            UnsentTransaction::Exported(transaction) => transaction.print_text(),
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        match self {
+            UnsentTransaction::Simulated(report) => report.csv_header(),
+            UnsentTransaction::Exported(transaction) => transaction.csv_header(),
+        }
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        match self {
+            UnsentTransaction::Simulated(report) => report.csv_records(),
+            UnsentTransaction::Exported(transaction) => transaction.csv_records(),
+        }
+    }
+}
+
+/// What became of the transaction of a command: the command's result once the transaction landed, or
+/// the transaction itself when it was not sent
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum TransactionOutcome<T> {
+    Sent(T),
+    Unsent(UnsentTransaction),
+}
+
+impl<T> TransactionOutcome<T> {
+    pub fn unsent(&self) -> Option<&UnsentTransaction> {
+        match self {
+            TransactionOutcome::Sent(_) => None,
+            TransactionOutcome::Unsent(unsent) => Some(unsent),
+        }
+    }
+}
+
+impl<T: CommandOutput> CommandOutput for TransactionOutcome<T> {
+    fn print_text(&self) {
+        match self {
+            TransactionOutcome::Sent(result) => result.print_text(),
+            TransactionOutcome::Unsent(unsent) => unsent.print_text(),
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        match self {
+            TransactionOutcome::Sent(result) => result.csv_header(),
+            TransactionOutcome::Unsent(unsent) => unsent.csv_header(),
+        }
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        match self {
+            TransactionOutcome::Sent(result) => result.csv_records(),
+            TransactionOutcome::Unsent(unsent) => unsent.csv_records(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionSigner {
+    pub pubkey: String,
+    /// Whether the transaction holds a valid signature from this signer
+    pub signed: bool,
+}
+
+/// A transaction encoded for signing or broadcasting elsewhere
+#[derive(Debug, Clone, Serialize)]
+pub struct EncodedTransaction {
+    pub transaction: String,
+    pub encoding: TransactionEncoding,
+    /// Recent blockhash of the transaction, or the durable nonce when `nonce_account` is set
+    pub blockhash: String,
+    pub nonce_account: Option<String>,
+    pub signers: Vec<TransactionSigner>,
+}
+
+impl EncodedTransaction {
+    pub fn new(transaction: &Transaction, encoding: TransactionEncoding) -> anyhow::Result<Self> {
+        let verified = transaction.verify_with_results();
+        let signers = transaction
+            .message
+            .signer_keys()
+            .iter()
+            .zip(verified)
+            .map(|(pubkey, signed)| TransactionSigner {
+                pubkey: pubkey.to_string(),
+                signed,
+            })
+            .collect();
+        let nonce_account = uses_durable_nonce(transaction)
+            .and_then(|instruction| instruction.accounts.first())
+            .and_then(|index| transaction.message.account_keys.get(*index as usize))
+            .map(|pubkey| pubkey.to_string());
+        Ok(EncodedTransaction {
+            transaction: encode_transaction(transaction, encoding)?,
+            encoding,
+            blockhash: transaction.message.recent_blockhash.to_string(),
+            nonce_account,
+            signers,
+        })
+    }
+
+    pub fn missing_signers(&self) -> Vec<&str> {
+        self.signers
+            .iter()
+            .filter(|signer| !signer.signed)
+            .map(|signer| signer.pubkey.as_str())
+            .collect()
+    }
+}
+
+impl CommandOutput for EncodedTransaction {
+    fn print_text(&self) {
+        println!("Transaction ({:?}):", self.encoding);
+        println!("{}", self.transaction);
+        println!();
+        match &self.nonce_account {
+            Some(nonce_account) => println!(
+                "Durable nonce: {} (nonce account {})",
+                self.blockhash, nonce_account
+            ),
+            None => println!("Recent blockhash: {}", self.blockhash),
+        }
+        println!("Signers:");
+        for signer in &self.signers {
+            println!(
+                "  {}: {}",
+                signer.pubkey,
+                if signer.signed { "signed" } else { "missing" }
+            );
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "transaction",
+            "encoding",
+            "blockhash",
+            "nonce_account",
+            "missing_signers",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.transaction.clone(),
+            format!("{:?}", self.encoding).to_lowercase(),
+            self.blockhash.clone(),
+            optional_field(&self.nonce_account),
+            self.missing_signers().join(" "),
+        ]]
+    }
+}
+
+pub fn encode_transaction(
+    transaction: &Transaction,
+    encoding: TransactionEncoding,
+) -> anyhow::Result<String> {
+    let bytes = bincode::serialize(transaction)?;
+    Ok(match encoding {
+        TransactionEncoding::Base58 => bs58::encode(bytes).into_string(),
+        TransactionEncoding::Base64 => base64::engine::general_purpose::STANDARD.encode(bytes),
+    })
+}
+
+/// Reads an encoded transaction given either directly or as the path of a file holding it
+pub fn read_encoded_transaction(transaction: &str) -> anyhow::Result<String> {
+    let path = Path::new(transaction);
+    if path.is_file() {
+        Ok(fs::read_to_string(path)?.trim().to_string())
+    } else {
+        Ok(transaction.trim().to_string())
+    }
+}
+
+/// Decodes a transaction encoded as base64 or base58, returning it with the encoding that was used
+pub fn decode_transaction(encoded: &str) -> anyhow::Result<(Transaction, TransactionEncoding)> {
+    let encoded = encoded.trim();
+    let candidates = [
+        (
+            base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .ok(),
+            TransactionEncoding::Base64,
+        ),
+        (
+            bs58::decode(encoded).into_vec().ok(),
+            TransactionEncoding::Base58,
+        ),
+    ];
+    // Base58 strings can also be valid base64, so only accept a decoding that yields a transaction
+    candidates
+        .into_iter()
+        .filter_map(|(bytes, encoding)| {
+            let transaction = bincode::deserialize::<Transaction>(&bytes?).ok()?;
+            transaction
+                .sanitize()
+                .is_ok()
+                .then_some((transaction, encoding))
+        })
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Not a base64 or base58 encoded transaction"))
+}
+
+/// Simulates the instructions as a transaction paid for by the client's payer. Signatures are not
+/// verified and the blockhash is replaced by the cluster, so no keypair is needed
+pub async fn simulate_instructions(
+    sdk: &SDKClient,
+    instructions: &[Instruction],
+) -> anyhow::Result<SimulationReport> {
+    let client = &sdk.client;
+    let message = Message::new(instructions, Some(&client.payer.pubkey()));
+    let writable_accounts = message
+        .account_keys
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| message.is_writable(*i))
+        .map(|(_, pubkey)| *pubkey)
+        .collect::<Vec<_>>();
+    let accounts_before = client.get_multiple_accounts(&writable_accounts).await?;
+
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(client.commitment()),
+        encoding: Some(UiTransactionEncoding::Base64),
+        accounts: Some(RpcSimulateTransactionAccountsConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            addresses: writable_accounts
+                .iter()
+                .map(|key| key.to_string())
+                .collect(),
+        }),
+        min_context_slot: None,
+        inner_instructions: true,
+    };
+    let result = client
+        .simulate_transaction_with_config(&Transaction::new_unsigned(message.clone()), config)
+        .await?
+        .value;
+
+    let accounts_after = result
+        .accounts
+        .unwrap_or_default()
+        .into_iter()
+        .map(|account| account.and_then(|account| account.decode::<Account>()))
+        .collect::<Vec<_>>();
+    let account_changes = writable_accounts
+        .iter()
+        .zip(accounts_before.iter())
+        .zip(accounts_after.iter())
+        .filter_map(|((pubkey, before), after)| get_account_change(pubkey, before, after))
+        .collect();
+
+    let events = get_phoenix_event_records(
+        sdk,
+        &Signature::default(),
+        &message.account_keys,
+        result.inner_instructions.as_deref().unwrap_or_default(),
+    )
+    .await?;
+
+    Ok(SimulationReport {
+        success: result.err.is_none(),
+        error: result
+            .err
+            .map(|err| describe_transaction_error(&err, &message)),
+        units_consumed: result.units_consumed,
+        logs: result.logs.unwrap_or_default(),
+        account_changes,
+        events,
+    })
+}
+
+/// Decodes the Phoenix events of a transaction from its inner instructions. Phoenix logs its events
+/// through self-invoked Log instructions
+async fn get_phoenix_event_records(
+    sdk: &SDKClient,
+    signature: &Signature,
+    account_keys: &[Pubkey],
+    inner_instructions: &[UiInnerInstructions],
+) -> anyhow::Result<Vec<MarketEventRecord>> {
+    let phoenix_program_id = phoenix::id().to_string();
+    let mut event_data = vec![];
+    for inner_instructions in inner_instructions {
+        for instruction in &inner_instructions.instructions {
+            let (program_id, data) = match instruction {
+                UiInstruction::Compiled(instruction) => (
+                    account_keys
+                        .get(instruction.program_id_index as usize)
+                        .map(|key| key.to_string()),
+                    &instruction.data,
+                ),
+                UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(instruction)) => {
+                    (Some(instruction.program_id.clone()), &instruction.data)
+                }
+                UiInstruction::Parsed(UiParsedInstruction::Parsed(_)) => continue,
+            };
+            if program_id.as_ref() != Some(&phoenix_program_id) {
+                continue;
+            }
+            let data = bs58::decode(data).into_vec()?;
+            if let Some((tag, data)) = data.split_first() {
+                if matches!(
+                    PhoenixInstruction::try_from(*tag),
+                    Ok(PhoenixInstruction::Log)
+                ) {
+                    event_data.push(data.to_vec());
+                }
+            }
+        }
+    }
+    if event_data.is_empty() {
+        return Ok(vec![]);
+    }
+    let raw_events = sdk
+        .core
+        .parse_raw_phoenix_events(signature, event_data)
+        .unwrap_or_default();
+    let events = sdk
+        .parse_raw_phoenix_events(raw_events)
+        .await
+        .unwrap_or_default();
+    get_market_event_records(sdk, events).await
+}
+
+fn get_account_change(
+    pubkey: &Pubkey,
+    before: &Option<Account>,
+    after: &Option<Account>,
+) -> Option<AccountChange> {
+    if before == after {
+        return None;
+    }
+    let data_before = before
+        .as_ref()
+        .map(|account| &account.data[..])
+        .unwrap_or(&[]);
+    let data_after = after
+        .as_ref()
+        .map(|account| &account.data[..])
+        .unwrap_or(&[]);
+    let bytes_changed = data_before
+        .iter()
+        .zip(data_after.iter())
+        .filter(|(a, b)| a != b)
+        .count()
+        + data_before.len().abs_diff(data_after.len());
+    Some(AccountChange {
+        pubkey: pubkey.to_string(),
+        lamports_before: before.as_ref().map(|account| account.lamports),
+        lamports_after: after.as_ref().map(|account| account.lamports),
+        data_len_before: before.as_ref().map(|account| account.data.len()),
+        data_len_after: after.as_ref().map(|account| account.data.len()),
+        bytes_changed,
+        owner_after: after.as_ref().map(|account| account.owner.to_string()),
+    })
+}
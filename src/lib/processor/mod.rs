@@ -1,7 +1,16 @@
+pub mod process_amend_order;
+pub mod process_broadcast_tx;
+pub mod process_cancel_orders;
+pub mod process_change_seat_status;
 pub mod process_claim_seat;
+pub mod process_create_market;
+pub mod process_deposit_funds;
 pub mod process_evict_seat;
+pub mod process_export_trades;
 pub mod process_get_all_markets;
 pub mod process_get_book_levels;
+pub mod process_get_candles;
+pub mod process_get_expiring_orders;
 pub mod process_get_full_book;
 pub mod process_get_market;
 pub mod process_get_market_status;
@@ -11,6 +20,18 @@ pub mod process_get_seat_manager_info;
 pub mod process_get_top_of_book;
 pub mod process_get_traders_for_market;
 pub mod process_get_transaction;
+pub mod process_list_pending_seats;
+pub mod process_market_admin;
 pub mod process_mint_tokens;
 pub mod process_mint_tokens_for_market;
+pub mod process_place_limit_order;
+pub mod process_place_orders;
+pub mod process_registry;
 pub mod process_request_seat;
+pub mod process_sandbox;
+pub mod process_seat_manager_admin;
+pub mod process_sign_tx;
+pub mod process_stream_events;
+pub mod process_swap;
+pub mod process_watch_book;
+pub mod process_withdraw_funds;
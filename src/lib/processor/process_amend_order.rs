@@ -0,0 +1,223 @@
+use crate::helpers::market_helpers::{get_open_orders_for_trader, TraderOpenOrders};
+use crate::helpers::output_helpers::CommandOutput;
+use crate::helpers::print_helpers::{get_market_event_records, MarketEventRecord};
+use crate::helpers::transaction_helpers::{send_instructions, TransactionOutcome};
+use phoenix::program::create_new_order_instruction;
+use phoenix::quantities::{BaseLots, Ticks, WrapperU64};
+use phoenix::state::markets::RestingOrder;
+use phoenix::state::{OrderPacket, SelfTradeBehavior, Side};
+use phoenix_sdk::sdk_client::*;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AmendOrderResult {
+    /// Order ID of the cancelled order, as printed by get-open-orders
+    pub original_order_id: i64,
+    pub side: String,
+    pub original_price: f64,
+    pub original_size: String,
+    pub new_price: f64,
+    pub new_size: String,
+    pub signature: String,
+    /// Order ID of the replacement, or `None` if it was filled in full without resting on the book
+    pub new_order_id: Option<i64>,
+    pub events: Vec<MarketEventRecord>,
+}
+
+impl CommandOutput for AmendOrderResult {
+    fn print_text(&self) {
+        println!(
+            "Amending {} order {}: price {} -> {}, size {} -> {}",
+            self.side,
+            self.original_order_id,
+            self.original_price,
+            self.new_price,
+            self.original_size,
+            self.new_size
+        );
+        println!("Amend order transaction: {}", self.signature);
+        self.events.iter().for_each(|event| event.print_text());
+        match self.new_order_id {
+            Some(order_id) => println!("Replacement order ID: {}", order_id),
+            None => println!("Replacement order was filled without resting on the book"),
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        MarketEventRecord::CSV_HEADER.to_vec()
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        self.events.iter().map(|event| event.csv_record()).collect()
+    }
+}
+
+/// Cancels one of the trader's resting orders and places its replacement in the same transaction.
+/// The replacement keeps the side and expiry of the original, and its price and size default to the original's
+#[allow(clippy::too_many_arguments)]
+pub async fn process_amend_order(
+    market_pubkey: &Pubkey,
+    sdk: &mut SDKClient,
+    order_sequence_number: u64,
+    price: Option<f64>,
+    size: Option<f64>,
+    client_order_id: u128,
+    post_only: bool,
+    self_trade_behavior: SelfTradeBehavior,
+) -> anyhow::Result<TransactionOutcome<AmendOrderResult>> {
+    let meta = *sdk.get_market_metadata_from_cache(market_pubkey)?;
+    let trader = sdk.get_trader();
+
+    let TraderOpenOrders { bids, asks, .. } =
+        get_open_orders_for_trader(&sdk.client, market_pubkey, &trader).await?;
+    let (order_id, order) = bids
+        .iter()
+        .chain(asks.iter())
+        .find(|(order_id, _)| order_id.order_sequence_number == order_sequence_number)
+        .copied()
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Order {} not found in open orders. It may have been filled, cancelled or expired",
+                order_sequence_number as i64
+            )
+        })?;
+    let side = Side::from_order_sequence_number(order_sequence_number);
+
+    // Round the price away from the spread so the order is never more aggressive than requested
+    let price_in_ticks = match (price, side) {
+        (None, _) => order_id.price_in_ticks.as_u64(),
+        (Some(price), Side::Bid) => sdk.float_price_to_ticks_rounded_down(market_pubkey, price)?,
+        (Some(price), Side::Ask) => sdk.float_price_to_ticks_rounded_up(market_pubkey, price)?,
+    };
+    let num_base_lots = match size {
+        None => order.num_base_lots.as_u64(),
+        Some(size) => sdk.raw_base_units_to_base_lots_rounded_down(market_pubkey, size)?,
+    };
+
+    if price_in_ticks == 0 {
+        return Err(anyhow::anyhow!(
+            "Price {} is below the minimum tick size of the market",
+            price.unwrap_or_default()
+        ));
+    }
+    if num_base_lots == 0 {
+        return Err(anyhow::anyhow!(
+            "Size {} is below the minimum base lot size of the market",
+            size.unwrap_or_default()
+        ));
+    }
+    if price_in_ticks == order_id.price_in_ticks.as_u64()
+        && num_base_lots == order.num_base_lots.as_u64()
+    {
+        return Err(anyhow::anyhow!(
+            "Order {} already has this price and size",
+            order_sequence_number as i64
+        ));
+    }
+
+    // An order placed with a time in force keeps expiring at the same slot and time once amended
+    let last_valid_slot = order.last_valid_slot();
+    let last_valid_unix_timestamp_in_seconds = order.last_valid_unix_timestamp_in_seconds();
+    let order_packet = if post_only {
+        OrderPacket::PostOnly {
+            side,
+            price_in_ticks: Ticks::new(price_in_ticks),
+            num_base_lots: BaseLots::new(num_base_lots),
+            client_order_id,
+            reject_post_only: true,
+            use_only_deposited_funds: false,
+            last_valid_slot,
+            last_valid_unix_timestamp_in_seconds,
+            fail_silently_on_insufficient_funds: false,
+        }
+    } else {
+        OrderPacket::Limit {
+            side,
+            price_in_ticks: Ticks::new(price_in_ticks),
+            num_base_lots: BaseLots::new(num_base_lots),
+            self_trade_behavior,
+            match_limit: None,
+            client_order_id,
+            use_only_deposited_funds: false,
+            last_valid_slot,
+            last_valid_unix_timestamp_in_seconds,
+            fail_silently_on_insufficient_funds: false,
+        }
+    };
+
+    // The cancel releases the funds of the original order before the replacement is placed
+    let instructions = vec![
+        sdk.get_cancel_ids_ix(market_pubkey, vec![order_id])?,
+        create_new_order_instruction(
+            market_pubkey,
+            &trader,
+            &meta.base_mint,
+            &meta.quote_mint,
+            &order_packet,
+        ),
+    ];
+    let signature = match send_instructions(sdk, instructions, vec![]).await? {
+        TransactionOutcome::Sent(signature) => signature,
+        TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+    };
+
+    let events = sdk
+        .parse_events_from_transaction(&signature)
+        .await
+        .ok_or_else(|| anyhow::anyhow!("Failed to parse events from transaction"))?;
+
+    // Confirm that the original order was removed and find the replacement
+    let original_reduced = events.iter().any(|event| {
+        matches!(
+            event.details,
+            MarketEventDetails::Reduce(reduce)
+                if reduce.order_sequence_number == order_sequence_number
+        )
+    });
+    if !original_reduced {
+        return Err(anyhow::anyhow!(
+            "Transaction {} landed, but order {} was not cancelled",
+            signature,
+            order_sequence_number as i64
+        ));
+    }
+    let new_order_id = events.iter().find_map(|event| match event.details {
+        MarketEventDetails::Place(place) if place.maker == trader => {
+            Some(place.order_sequence_number as i64)
+        }
+        _ => None,
+    });
+    let replacement_filled = events.iter().any(|event| {
+        matches!(
+            event.details,
+            MarketEventDetails::Fill(fill) if fill.taker == trader
+        )
+    });
+    if new_order_id.is_none() && !replacement_filled {
+        return Err(anyhow::anyhow!(
+            "Transaction {} cancelled order {}, but the replacement was neither placed nor filled",
+            signature,
+            order_sequence_number as i64
+        ));
+    }
+
+    Ok(TransactionOutcome::Sent(AmendOrderResult {
+        original_order_id: order_sequence_number as i64,
+        side: format!("{:?}", side),
+        original_price: sdk
+            .ticks_to_float_price(market_pubkey, order_id.price_in_ticks.as_u64())?,
+        original_size: get_decimal_string(
+            sdk.base_lots_to_base_atoms(market_pubkey, order.num_base_lots.as_u64())?,
+            meta.base_decimals,
+        ),
+        new_price: sdk.ticks_to_float_price(market_pubkey, price_in_ticks)?,
+        new_size: get_decimal_string(
+            sdk.base_lots_to_base_atoms(market_pubkey, num_base_lots)?,
+            meta.base_decimals,
+        ),
+        signature: signature.to_string(),
+        new_order_id,
+        events: get_market_event_records(sdk, events).await?,
+    }))
+}
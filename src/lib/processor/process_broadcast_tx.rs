@@ -0,0 +1,23 @@
+use crate::helpers::transaction_helpers::{
+    decode_transaction, read_encoded_transaction, send_and_confirm_transaction, EncodedTransaction,
+    TransactionReceipt,
+};
+use phoenix_sdk::sdk_client::SDKClient;
+
+/// Submits a fully signed transaction, resending it until it reaches the client's commitment
+pub async fn process_broadcast_tx(
+    sdk: &SDKClient,
+    transaction: &str,
+) -> anyhow::Result<TransactionReceipt> {
+    let (transaction, encoding) = decode_transaction(&read_encoded_transaction(transaction)?)?;
+    let missing_signers = EncodedTransaction::new(&transaction, encoding)?
+        .missing_signers()
+        .join(", ");
+    if !missing_signers.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Transaction is missing signatures from {}",
+            missing_signers
+        ));
+    }
+    send_and_confirm_transaction(sdk, &transaction).await
+}
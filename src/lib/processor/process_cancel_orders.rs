@@ -0,0 +1,207 @@
+use crate::helpers::market_helpers::{get_open_orders_for_trader, TraderOpenOrders};
+use crate::helpers::output_helpers::CommandOutput;
+use crate::helpers::print_helpers::{get_market_event_records, MarketEventRecord};
+use crate::helpers::transaction_helpers::{
+    check_nonce_batches, pack_instructions, send_instructions, TransactionOutcome,
+    UnsentTransaction,
+};
+use phoenix::quantities::WrapperU64;
+use phoenix::state::markets::FIFOOrderId;
+use phoenix::state::Side;
+use phoenix_sdk::sdk_client::*;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::Signer;
+
+#[derive(Debug, Clone, Copy)]
+pub enum PriceThreshold {
+    Ticks(u64),
+    Price(f64),
+}
+
+#[derive(Debug, Clone)]
+pub enum CancelSelection {
+    /// Cancel the orders with the given sequence numbers
+    Ids(Vec<u64>),
+    /// Cancel every open order, optionally restricted to one side of the book
+    All(Option<Side>),
+    /// Cancel every order on one side priced at or better than the threshold
+    UpTo {
+        side: Side,
+        threshold: PriceThreshold,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CancelTransaction {
+    pub signature: String,
+    pub events: Vec<MarketEventRecord>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CancelOrdersResult {
+    /// Requested order ids that are not among the trader's open orders
+    pub orders_not_found: Vec<i64>,
+    pub num_orders_cancelled: usize,
+    pub transactions: Vec<CancelTransaction>,
+    /// Transactions that were not sent, one per batch of orders
+    pub unsent_transactions: Vec<UnsentTransaction>,
+}
+
+impl CommandOutput for CancelOrdersResult {
+    fn print_text(&self) {
+        for order_id in self.orders_not_found.iter() {
+            println!("Order {} not found in open orders", order_id);
+        }
+        if self.num_orders_cancelled == 0 {
+            println!("No matching open orders to cancel");
+            return;
+        }
+        println!("Cancelling {} order(s)", self.num_orders_cancelled);
+        for transaction in self.transactions.iter() {
+            println!("Cancel orders transaction: {}", transaction.signature);
+            transaction
+                .events
+                .iter()
+                .for_each(|event| event.print_text());
+        }
+        for (i, unsent) in self.unsent_transactions.iter().enumerate() {
+            println!();
+            println!(
+                "Transaction {} of {}:",
+                i + 1,
+                self.unsent_transactions.len()
+            );
+            unsent.print_text();
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        match self.unsent_transactions.first() {
+            Some(unsent) => unsent.csv_header(),
+            None => MarketEventRecord::CSV_HEADER.to_vec(),
+        }
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        if !self.unsent_transactions.is_empty() {
+            return self
+                .unsent_transactions
+                .iter()
+                .flat_map(|unsent| unsent.csv_records())
+                .collect();
+        }
+        self.transactions
+            .iter()
+            .flat_map(|transaction| transaction.events.iter())
+            .map(|event| event.csv_record())
+            .collect()
+    }
+}
+
+pub async fn process_cancel_orders(
+    market_pubkey: &Pubkey,
+    sdk: &mut SDKClient,
+    selection: CancelSelection,
+) -> anyhow::Result<CancelOrdersResult> {
+    let payer = sdk.client.payer.pubkey();
+    let TraderOpenOrders { bids, asks, .. } =
+        get_open_orders_for_trader(&sdk.client, market_pubkey, &sdk.get_trader()).await?;
+    let open_order_ids = bids
+        .iter()
+        .chain(asks.iter())
+        .map(|(order_id, _)| *order_id);
+
+    let mut orders_not_found = vec![];
+    let order_ids: Vec<FIFOOrderId> = match selection {
+        CancelSelection::Ids(sequence_numbers) => {
+            let order_ids = open_order_ids
+                .filter(|order_id| sequence_numbers.contains(&order_id.order_sequence_number))
+                .collect::<Vec<_>>();
+            for sequence_number in sequence_numbers.iter().filter(|&&sequence_number| {
+                !order_ids
+                    .iter()
+                    .any(|order_id| order_id.order_sequence_number == sequence_number)
+            }) {
+                orders_not_found.push(*sequence_number as i64);
+            }
+            order_ids
+        }
+        CancelSelection::All(side) => open_order_ids
+            .filter(|order_id| {
+                side.is_none_or(|side| {
+                    Side::from_order_sequence_number(order_id.order_sequence_number) == side
+                })
+            })
+            .collect(),
+        CancelSelection::UpTo { side, threshold } => {
+            // Round the threshold towards the top of the book so no order beyond it is cancelled
+            let tick_limit = match (threshold, side) {
+                (PriceThreshold::Ticks(ticks), _) => ticks,
+                (PriceThreshold::Price(price), Side::Bid) => {
+                    sdk.float_price_to_ticks_rounded_up(market_pubkey, price)?
+                }
+                (PriceThreshold::Price(price), Side::Ask) => {
+                    sdk.float_price_to_ticks_rounded_down(market_pubkey, price)?
+                }
+            };
+            open_order_ids
+                .filter(|order_id| {
+                    Side::from_order_sequence_number(order_id.order_sequence_number) == side
+                        && match side {
+                            Side::Bid => order_id.price_in_ticks.as_u64() >= tick_limit,
+                            Side::Ask => order_id.price_in_ticks.as_u64() <= tick_limit,
+                        }
+                })
+                .collect()
+        }
+    };
+
+    let num_orders_cancelled = order_ids.len();
+    let mut transactions = vec![];
+    let mut unsent_transactions = vec![];
+    if order_ids.is_empty() {
+        return Ok(CancelOrdersResult {
+            orders_not_found,
+            num_orders_cancelled,
+            transactions,
+            unsent_transactions,
+        });
+    }
+
+    // Each order gets its own cancel instruction, packed into as few transactions as possible
+    let cancel_instructions = order_ids
+        .into_iter()
+        .map(|order_id| sdk.get_cancel_ids_ix(market_pubkey, vec![order_id]))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let batches = pack_instructions(
+        vec![],
+        cancel_instructions.into_iter().enumerate().collect(),
+        &payer,
+    );
+    check_nonce_batches(batches.len())?;
+
+    for (instructions, _) in batches {
+        let signature = match send_instructions(sdk, instructions, vec![]).await? {
+            TransactionOutcome::Sent(signature) => signature,
+            // Every batch is simulated or exported on its own, so none is left out of the result
+            TransactionOutcome::Unsent(unsent) => {
+                unsent_transactions.push(unsent);
+                continue;
+            }
+        };
+
+        let cancels = sdk.parse_cancels(&signature).await;
+        transactions.push(CancelTransaction {
+            signature: signature.to_string(),
+            events: get_market_event_records(sdk, cancels).await?,
+        });
+    }
+
+    Ok(CancelOrdersResult {
+        orders_not_found,
+        num_orders_cancelled,
+        transactions,
+        unsent_transactions,
+    })
+}
@@ -0,0 +1,248 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::helpers::market_helpers::get_seat_statuses;
+use crate::helpers::output_helpers::{optional_field, CommandOutput};
+use crate::helpers::transaction_helpers::{
+    check_nonce_batches, get_signing_authority, pack_instructions, send_instructions,
+    TransactionOutcome, UnsentTransaction,
+};
+use crate::processor::process_market_admin::{assert_market_authority, get_market_state};
+use phoenix::program::create_change_seat_status_instruction;
+use phoenix::program::status::SeatApprovalStatus;
+use phoenix_sdk::sdk_client::SDKClient;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::Signer;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SeatStatusChange {
+    pub trader: String,
+    /// Status before the change, or `None` if the trader never requested a seat
+    pub previous_status: Option<String>,
+    /// changed, unchanged, skipped, failed, simulated or exported
+    pub result: String,
+    pub message: Option<String>,
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeSeatStatusResult {
+    pub market: String,
+    pub status: String,
+    pub num_transactions: usize,
+    pub seats: Vec<SeatStatusChange>,
+    /// Transactions that were not sent, in the order they were built
+    pub unsent_transactions: Vec<UnsentTransaction>,
+}
+
+impl CommandOutput for ChangeSeatStatusResult {
+    fn print_text(&self) {
+        println!(
+            "Setting {} seat(s) on market {} to {} in {} transaction(s)",
+            self.seats.len(),
+            self.market,
+            self.status,
+            self.num_transactions
+        );
+        for seat in &self.seats {
+            let previous_status = seat
+                .previous_status
+                .clone()
+                .unwrap_or_else(|| "no seat".to_string());
+            match &seat.message {
+                Some(message) => println!(
+                    "{} ({}): {}, {}",
+                    seat.trader, previous_status, seat.result, message
+                ),
+                None => println!("{} ({}): {}", seat.trader, previous_status, seat.result),
+            }
+        }
+        let mut signatures = self
+            .seats
+            .iter()
+            .filter_map(|seat| seat.signature.as_deref())
+            .collect::<Vec<_>>();
+        signatures.dedup();
+        for signature in signatures {
+            println!("Signature: {}", signature);
+        }
+        for (i, unsent) in self.unsent_transactions.iter().enumerate() {
+            println!();
+            println!("Transaction {}:", i + 1);
+            unsent.print_text();
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "trader",
+            "previous_status",
+            "result",
+            "message",
+            "signature",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        self.seats
+            .iter()
+            .map(|seat| {
+                vec![
+                    seat.trader.clone(),
+                    optional_field(&seat.previous_status),
+                    seat.result.clone(),
+                    optional_field(&seat.message),
+                    optional_field(&seat.signature),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Reads traders from a file with one trader pubkey per line. Blank lines and lines starting with `#`
+/// are ignored, and the CSV output of list-pending-seats is read from its `trader` column
+pub fn read_traders_file(path: &Path) -> anyhow::Result<Vec<Pubkey>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let mut trader_column = 0;
+    let mut traders = vec![];
+    for (line_index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+        if let Some(column) = fields.iter().position(|field| *field == "trader") {
+            trader_column = column;
+            continue;
+        }
+        let field = fields.get(trader_column).copied().unwrap_or_default();
+        let trader = Pubkey::from_str(field).map_err(|_| {
+            anyhow::anyhow!(
+                "{}:{}: {} is not a pubkey",
+                path.display(),
+                line_index + 1,
+                field
+            )
+        })?;
+        traders.push(trader);
+    }
+    Ok(traders)
+}
+
+// Mirrors the transitions the program accepts. Setting the current status again is a no-op
+fn valid_seat_transition(current: SeatApprovalStatus, new: SeatApprovalStatus) -> bool {
+    match new {
+        SeatApprovalStatus::Approved => current == SeatApprovalStatus::NotApproved,
+        SeatApprovalStatus::NotApproved => current == SeatApprovalStatus::Approved,
+        SeatApprovalStatus::Retired => current != SeatApprovalStatus::Retired,
+    }
+}
+
+/// Changes the approval status of the seats of the given traders. Every seat is checked before anything
+/// is sent: traders without a seat request, invalid transitions and approvals beyond the market's free
+/// trader slots are skipped. The remaining changes are packed into as few transactions as fit
+pub async fn process_change_seat_status(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+    traders: &[Pubkey],
+    status: SeatApprovalStatus,
+) -> anyhow::Result<ChangeSeatStatusResult> {
+    let authority = get_signing_authority(&sdk.client);
+    let state = get_market_state(sdk, market_pubkey).await?;
+    assert_market_authority(&state.header, market_pubkey, &authority)?;
+
+    let mut traders = traders.to_vec();
+    let mut seen = HashSet::new();
+    traders.retain(|trader| seen.insert(*trader));
+    if traders.is_empty() {
+        return Err(anyhow::anyhow!("No traders given"));
+    }
+
+    let seat_statuses = get_seat_statuses(&sdk.client, market_pubkey, &traders).await?;
+
+    let mut free_trader_slots = state
+        .header
+        .market_size_params
+        .num_seats
+        .saturating_sub(state.registered_traders.len() as u64);
+    let mut changes = vec![];
+    let mut instructions = vec![];
+    for (trader, &current_status) in traders.iter().zip(seat_statuses.iter()) {
+        let mut change = SeatStatusChange {
+            trader: trader.to_string(),
+            previous_status: current_status.map(|status| status.to_string()),
+            result: "skipped".to_string(),
+            message: None,
+            signature: None,
+        };
+        match current_status {
+            None => change.message = Some("the trader has not requested a seat".to_string()),
+            Some(current_status) if current_status == status => {
+                change.result = "unchanged".to_string();
+            }
+            Some(current_status) if !valid_seat_transition(current_status, status) => {
+                change.message = Some(format!(
+                    "a seat cannot change from {} to {}",
+                    current_status, status
+                ));
+            }
+            Some(_) if status == SeatApprovalStatus::Approved && free_trader_slots == 0 => {
+                change.message = Some("the market has no free trader slots".to_string());
+            }
+            Some(_) => {
+                if status == SeatApprovalStatus::Approved {
+                    free_trader_slots -= 1;
+                }
+                instructions.push((
+                    changes.len(),
+                    create_change_seat_status_instruction(
+                        &authority,
+                        market_pubkey,
+                        trader,
+                        status,
+                    ),
+                ));
+            }
+        }
+        changes.push(change);
+    }
+
+    let batches = pack_instructions(vec![], instructions, &sdk.client.payer.pubkey());
+    check_nonce_batches(batches.len())?;
+    let mut unsent_transactions = vec![];
+    for (instructions, change_indices) in &batches {
+        let result = match send_instructions(sdk, instructions.clone(), vec![]).await {
+            Ok(TransactionOutcome::Sent(signature)) => {
+                ("changed", None, Some(signature.to_string()))
+            }
+            Ok(TransactionOutcome::Unsent(unsent)) => {
+                let result = match unsent {
+                    UnsentTransaction::Simulated(_) => "simulated",
+                    UnsentTransaction::Exported(_) => "exported",
+                };
+                let message = unsent.simulation_error().map(str::to_string);
+                unsent_transactions.push(unsent);
+                (result, message, None)
+            }
+            Err(e) => ("failed", Some(e.to_string()), None),
+        };
+        for &change_index in change_indices {
+            let change = &mut changes[change_index];
+            change.result = result.0.to_string();
+            change.message = result.1.clone();
+            change.signature = result.2.clone();
+        }
+    }
+
+    Ok(ChangeSeatStatusResult {
+        market: market_pubkey.to_string(),
+        status: status.to_string(),
+        num_transactions: batches.len(),
+        seats: changes,
+        unsent_transactions,
+    })
+}
@@ -1,21 +1,55 @@
-use ellipsis_client::EllipsisClient;
+use crate::helpers::output_helpers::{optional_field, CommandOutput};
+use crate::helpers::transaction_helpers::{send_instructions, TransactionOutcome};
+use phoenix_sdk::sdk_client::SDKClient;
 use phoenix_sdk::utils::create_claim_seat_ix_if_needed;
+use serde::Serialize;
 use solana_sdk::{pubkey::Pubkey, signer::Signer};
 
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaimSeatResult {
+    pub trader: String,
+    /// Claim seat transaction, or `None` if the seat already existed
+    pub signature: Option<String>,
+}
+
+impl CommandOutput for ClaimSeatResult {
+    fn print_text(&self) {
+        println!("Claiming seat for pubkey: {}", self.trader);
+        match &self.signature {
+            Some(signature) => println!("Claim seat transaction: {}", signature),
+            None => println!("Seat already created for pubkey: {}", self.trader),
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec!["trader", "signature"]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![self.trader.clone(), optional_field(&self.signature)]]
+    }
+}
+
 pub async fn process_claim_seat(
-    client: &EllipsisClient,
+    sdk: &SDKClient,
     market_pubkey: &Pubkey,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<TransactionOutcome<ClaimSeatResult>> {
     let claim_seat_ix =
-        create_claim_seat_ix_if_needed(client, market_pubkey, &client.payer.pubkey()).await?;
-    println!("Claiming seat for pubkey: {}", client.payer.pubkey());
+        create_claim_seat_ix_if_needed(&sdk.client, market_pubkey, &sdk.client.payer.pubkey())
+            .await?;
 
-    if !claim_seat_ix.is_empty() {
-        let tx = client.sign_send_instructions(claim_seat_ix, vec![]).await?;
-        println!("Claim seat transaction: {}", tx);
+    let signature = if !claim_seat_ix.is_empty() {
+        let tx = match send_instructions(sdk, claim_seat_ix, vec![]).await? {
+            TransactionOutcome::Sent(signature) => signature,
+            TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+        };
+        Some(tx.to_string())
     } else {
-        println!("Seat already created for pubkey: {}", client.payer.pubkey());
-    }
+        None
+    };
 
-    Ok(())
+    Ok(TransactionOutcome::Sent(ClaimSeatResult {
+        trader: sdk.client.payer.pubkey().to_string(),
+        signature,
+    }))
 }
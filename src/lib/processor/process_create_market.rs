@@ -0,0 +1,541 @@
+use crate::helpers::output_helpers::CommandOutput;
+use crate::helpers::transaction_helpers::{
+    get_signing_authority, get_transaction_options, send_instructions, TransactionOutcome,
+};
+use ellipsis_client::EllipsisClient;
+use phoenix::program::instruction_builders::{
+    create_initialize_market_instructions, create_name_successor_instruction,
+};
+use phoenix::program::MarketSizeParams;
+use phoenix_sdk::sdk_client::SDKClient;
+use phoenix_seat_manager::get_seat_manager_address;
+use phoenix_seat_manager::instruction_builders::create_claim_market_authority_instruction;
+use serde::Serialize;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use spl_token::state::Mint;
+
+/// Market sizes the Phoenix program accepts, as (bids, asks, seats). Mirrors `get_market_size`
+const SUPPORTED_MARKET_SIZES: [(u64, u64, u64); 12] = [
+    (512, 512, 128),
+    (512, 512, 1025),
+    (512, 512, 1153),
+    (1024, 1024, 128),
+    (1024, 1024, 2049),
+    (1024, 1024, 2177),
+    (2048, 2048, 128),
+    (2048, 2048, 4097),
+    (2048, 2048, 4225),
+    (4096, 4096, 128),
+    (4096, 4096, 8193),
+    (4096, 4096, 8321),
+];
+
+/// Maximum number of designated market makers a seat manager holds. A seat manager market reserves
+/// a seat for each of them on top of one per order
+const MAX_DMMS: u64 = 128;
+
+pub struct CreateMarketParams {
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    /// Tick size in quote units per base unit, as a decimal string
+    pub tick_size: String,
+    /// Base lot size in base units, as a decimal string
+    pub base_lot_size: String,
+    /// Quote lot size in quote units, as a decimal string. Defaults to one quote atom
+    pub quote_lot_size: Option<String>,
+    pub bids_size: u64,
+    pub asks_size: u64,
+    /// Number of trader seats. Defaults to 128, or to the size a seat manager requires
+    pub num_seats: Option<u64>,
+    pub taker_fee_bps: u16,
+    /// Defaults to the payer
+    pub fee_recipient: Option<Pubkey>,
+    pub raw_base_units_per_base_unit: Option<u32>,
+    pub seat_manager: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateMarketResult {
+    pub market: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub base_atoms_per_base_lot: u64,
+    pub num_base_lots_per_base_unit: u64,
+    pub quote_atoms_per_quote_lot: u64,
+    pub num_quote_lots_per_quote_unit: u64,
+    pub tick_size_in_quote_lots_per_base_unit: u64,
+    pub tick_size_in_quote_atoms_per_base_unit: u64,
+    pub bids_size: u64,
+    pub asks_size: u64,
+    pub num_seats: u64,
+    pub taker_fee_bps: u16,
+    pub fee_recipient: String,
+    pub authority: String,
+    pub signature: String,
+}
+
+impl CommandOutput for CreateMarketResult {
+    fn print_text(&self) {
+        println!("Created market {}", self.market);
+        println!("Base mint: {}", self.base_mint);
+        println!("Quote mint: {}", self.quote_mint);
+        println!(
+            "Base lot size: {} atoms ({} lots per base unit)",
+            self.base_atoms_per_base_lot, self.num_base_lots_per_base_unit
+        );
+        println!(
+            "Quote lot size: {} atoms ({} lots per quote unit)",
+            self.quote_atoms_per_quote_lot, self.num_quote_lots_per_quote_unit
+        );
+        println!(
+            "Tick size: {} quote lots per base unit ({} quote atoms per base unit)",
+            self.tick_size_in_quote_lots_per_base_unit, self.tick_size_in_quote_atoms_per_base_unit
+        );
+        println!(
+            "Size: {} bids, {} asks, {} seats",
+            self.bids_size, self.asks_size, self.num_seats
+        );
+        println!("Taker fee: {} bps", self.taker_fee_bps);
+        println!("Fee recipient: {}", self.fee_recipient);
+        println!("Authority: {}", self.authority);
+        println!("Signature: {}", self.signature);
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "market",
+            "base_mint",
+            "quote_mint",
+            "base_atoms_per_base_lot",
+            "num_base_lots_per_base_unit",
+            "quote_atoms_per_quote_lot",
+            "num_quote_lots_per_quote_unit",
+            "tick_size_in_quote_lots_per_base_unit",
+            "tick_size_in_quote_atoms_per_base_unit",
+            "bids_size",
+            "asks_size",
+            "num_seats",
+            "taker_fee_bps",
+            "fee_recipient",
+            "authority",
+            "signature",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.market.clone(),
+            self.base_mint.clone(),
+            self.quote_mint.clone(),
+            self.base_atoms_per_base_lot.to_string(),
+            self.num_base_lots_per_base_unit.to_string(),
+            self.quote_atoms_per_quote_lot.to_string(),
+            self.num_quote_lots_per_quote_unit.to_string(),
+            self.tick_size_in_quote_lots_per_base_unit.to_string(),
+            self.tick_size_in_quote_atoms_per_base_unit.to_string(),
+            self.bids_size.to_string(),
+            self.asks_size.to_string(),
+            self.num_seats.to_string(),
+            self.taker_fee_bps.to_string(),
+            self.fee_recipient.clone(),
+            self.authority.clone(),
+            self.signature.clone(),
+        ]]
+    }
+}
+
+// Converts a decimal amount of units into atoms, where a unit is `atoms_per_unit` atoms. Fails unless the
+// result is a whole, nonzero number of atoms, so that nothing is silently rounded
+fn units_to_atoms(name: &str, amount: &str, atoms_per_unit: u64) -> anyhow::Result<u64> {
+    let invalid = || anyhow::anyhow!("Invalid {} {}, expected a positive decimal", name, amount);
+    let (whole, fraction) = amount.trim().split_once('.').unwrap_or((amount.trim(), ""));
+    if whole.is_empty() && fraction.is_empty()
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    let fraction = fraction.trim_end_matches('0');
+    let scale = 10u128
+        .checked_pow(fraction.len() as u32)
+        .ok_or_else(invalid)?;
+    let mantissa = format!("{}{}", whole, fraction)
+        .parse::<u128>()
+        .map_err(|_| invalid())?;
+    let scaled = mantissa
+        .checked_mul(atoms_per_unit as u128)
+        .ok_or_else(invalid)?;
+    if !scaled.is_multiple_of(scale) {
+        return Err(anyhow::anyhow!(
+            "The {} {} is not a whole number of atoms, the token has {} atoms per unit",
+            name,
+            amount,
+            atoms_per_unit
+        ));
+    }
+    let atoms = u64::try_from(scaled / scale).map_err(|_| invalid())?;
+    if atoms == 0 {
+        return Err(anyhow::anyhow!("The {} must be greater than zero", name));
+    }
+    Ok(atoms)
+}
+
+/// Lot and tick sizes of a new market, in the atoms of its mints
+#[derive(Debug, PartialEq, Eq)]
+struct LotSizes {
+    base_atoms_per_base_lot: u64,
+    num_base_lots_per_base_unit: u64,
+    quote_atoms_per_quote_lot: u64,
+    num_quote_lots_per_quote_unit: u64,
+    tick_size_in_quote_lots_per_base_unit: u64,
+    tick_size_in_quote_atoms_per_base_unit: u64,
+}
+
+// Converts the lot and tick sizes of the parameters into atoms and lots, and checks that they fit
+// together: lots must divide units, and one base lot at one tick must be a whole number of quote lots
+fn get_lot_sizes(
+    params: &CreateMarketParams,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> anyhow::Result<LotSizes> {
+    let base_atoms_per_base_unit = 10u64
+        .checked_pow(base_decimals as u32)
+        .and_then(|atoms| {
+            atoms.checked_mul(params.raw_base_units_per_base_unit.unwrap_or(1) as u64)
+        })
+        .ok_or_else(|| anyhow::anyhow!("The base unit is too large"))?;
+    let quote_atoms_per_quote_unit = 10u64
+        .checked_pow(quote_decimals as u32)
+        .ok_or_else(|| anyhow::anyhow!("The quote unit is too large"))?;
+
+    let base_atoms_per_base_lot = units_to_atoms(
+        "base lot size",
+        &params.base_lot_size,
+        base_atoms_per_base_unit,
+    )?;
+    if !base_atoms_per_base_unit.is_multiple_of(base_atoms_per_base_lot) {
+        return Err(anyhow::anyhow!(
+            "The base lot size of {} atoms must divide the base unit of {} atoms",
+            base_atoms_per_base_lot,
+            base_atoms_per_base_unit
+        ));
+    }
+    let num_base_lots_per_base_unit = base_atoms_per_base_unit / base_atoms_per_base_lot;
+
+    let quote_atoms_per_quote_lot = match &params.quote_lot_size {
+        Some(quote_lot_size) => {
+            units_to_atoms("quote lot size", quote_lot_size, quote_atoms_per_quote_unit)?
+        }
+        None => 1,
+    };
+    if !quote_atoms_per_quote_unit.is_multiple_of(quote_atoms_per_quote_lot) {
+        return Err(anyhow::anyhow!(
+            "The quote lot size of {} atoms must divide the quote unit of {} atoms",
+            quote_atoms_per_quote_lot,
+            quote_atoms_per_quote_unit
+        ));
+    }
+    let num_quote_lots_per_quote_unit = quote_atoms_per_quote_unit / quote_atoms_per_quote_lot;
+
+    let tick_size_in_quote_atoms_per_base_unit =
+        units_to_atoms("tick size", &params.tick_size, quote_atoms_per_quote_unit)?;
+    if !tick_size_in_quote_atoms_per_base_unit.is_multiple_of(quote_atoms_per_quote_lot) {
+        return Err(anyhow::anyhow!(
+            "The tick size of {} quote atoms must be a whole number of quote lots of {} atoms",
+            tick_size_in_quote_atoms_per_base_unit,
+            quote_atoms_per_quote_lot
+        ));
+    }
+    let tick_size_in_quote_lots_per_base_unit =
+        tick_size_in_quote_atoms_per_base_unit / quote_atoms_per_quote_lot;
+    // A trade of one base lot at one tick must come to a whole number of quote lots
+    if !tick_size_in_quote_lots_per_base_unit.is_multiple_of(num_base_lots_per_base_unit) {
+        let minimum_tick_atoms = num_base_lots_per_base_unit * quote_atoms_per_quote_lot;
+        return Err(anyhow::anyhow!(
+            "The tick size of {} quote lots must be a multiple of the {} base lots per base unit, so that one base lot \
+            at one tick is a whole quote lot. Use a multiple of {} quote atoms ({} quote units), or a larger base lot size",
+            tick_size_in_quote_lots_per_base_unit,
+            num_base_lots_per_base_unit,
+            minimum_tick_atoms,
+            minimum_tick_atoms as f64 / quote_atoms_per_quote_unit as f64
+        ));
+    }
+
+    Ok(LotSizes {
+        base_atoms_per_base_lot,
+        num_base_lots_per_base_unit,
+        quote_atoms_per_quote_lot,
+        num_quote_lots_per_quote_unit,
+        tick_size_in_quote_lots_per_base_unit,
+        tick_size_in_quote_atoms_per_base_unit,
+    })
+}
+
+async fn get_mint(client: &EllipsisClient, mint: &Pubkey, name: &str) -> anyhow::Result<Mint> {
+    let account = client
+        .get_account(mint)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch {} mint {}: {}", name, mint, e))?;
+    if account.owner != spl_token::id() {
+        return Err(anyhow::anyhow!(
+            "The {} mint {} is not an SPL token mint, its owner is {}",
+            name,
+            mint,
+            account.owner
+        ));
+    }
+    Mint::unpack(&account.data)
+        .map_err(|e| anyhow::anyhow!("Failed to read {} mint {}: {}", name, mint, e))
+}
+
+/// Creates and initializes a market along with its base and quote vaults. Every parameter is checked
+/// against the rules of the program first. The market starts out PostOnly, with the signing authority
+/// as its authority unless a seat manager is installed
+pub async fn process_create_market(
+    sdk: &SDKClient,
+    params: &CreateMarketParams,
+    market_keypair: Option<Keypair>,
+) -> anyhow::Result<TransactionOutcome<CreateMarketResult>> {
+    let client = &sdk.client;
+    let payer = get_signing_authority(client);
+    if params.base_mint == params.quote_mint {
+        return Err(anyhow::anyhow!("The base and quote mints must differ"));
+    }
+    if params.taker_fee_bps > 10_000 {
+        return Err(anyhow::anyhow!(
+            "The taker fee must be at most 10000 bps (100%), got {}",
+            params.taker_fee_bps
+        ));
+    }
+    // The market account signs its creation, and a generated keypair would be lost once the
+    // transaction is printed
+    if market_keypair.is_none() && get_transaction_options().is_offline() {
+        return Err(anyhow::anyhow!(
+            "The new market account must sign the transaction, so pass its keypair with --market-keypair when exporting"
+        ));
+    }
+
+    let num_seats = params.num_seats.unwrap_or(if params.seat_manager {
+        params.bids_size + params.asks_size + 1 + MAX_DMMS
+    } else {
+        128
+    });
+    let market_size_params = MarketSizeParams {
+        bids_size: params.bids_size,
+        asks_size: params.asks_size,
+        num_seats,
+    };
+    if !SUPPORTED_MARKET_SIZES.contains(&(params.bids_size, params.asks_size, num_seats)) {
+        let supported = SUPPORTED_MARKET_SIZES
+            .iter()
+            .map(|(bids, asks, seats)| format!("{}/{}/{}", bids, asks, seats))
+            .collect::<Vec<_>>();
+        return Err(anyhow::anyhow!(
+            "Unsupported market size of {} bids, {} asks and {} seats. Supported bids/asks/seats: {}",
+            params.bids_size,
+            params.asks_size,
+            num_seats,
+            supported.join(", ")
+        ));
+    }
+    if params.seat_manager && num_seats != params.bids_size + params.asks_size + 1 + MAX_DMMS {
+        return Err(anyhow::anyhow!(
+            "A seat manager market needs exactly {} seats (bids + asks + {}), got {}",
+            params.bids_size + params.asks_size + 1 + MAX_DMMS,
+            MAX_DMMS + 1,
+            num_seats
+        ));
+    }
+
+    let base_mint = get_mint(client, &params.base_mint, "base").await?;
+    let quote_mint = get_mint(client, &params.quote_mint, "quote").await?;
+    let LotSizes {
+        base_atoms_per_base_lot,
+        num_base_lots_per_base_unit,
+        quote_atoms_per_quote_lot,
+        num_quote_lots_per_quote_unit,
+        tick_size_in_quote_lots_per_base_unit,
+        tick_size_in_quote_atoms_per_base_unit,
+    } = get_lot_sizes(params, base_mint.decimals, quote_mint.decimals)?;
+
+    let market_keypair = market_keypair.unwrap_or_else(Keypair::new);
+    let market = market_keypair.pubkey();
+    let fee_recipient = params.fee_recipient.unwrap_or(payer);
+    let mut instructions = create_initialize_market_instructions(
+        &market,
+        &params.base_mint,
+        &params.quote_mint,
+        &payer,
+        market_size_params,
+        num_quote_lots_per_quote_unit,
+        num_base_lots_per_base_unit,
+        tick_size_in_quote_lots_per_base_unit,
+        params.taker_fee_bps,
+        &fee_recipient,
+        params.raw_base_units_per_base_unit,
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to build the market instructions: {}", e))?;
+    let authority = if params.seat_manager {
+        // The seat manager takes over a market by claiming the authority as its named successor
+        let seat_manager = get_seat_manager_address(&market).0;
+        instructions.push(create_name_successor_instruction(
+            &payer,
+            &market,
+            &seat_manager,
+        ));
+        instructions.push(create_claim_market_authority_instruction(&market, &payer));
+        seat_manager
+    } else {
+        payer
+    };
+
+    let signature = match send_instructions(sdk, instructions, vec![&market_keypair]).await? {
+        TransactionOutcome::Sent(signature) => signature,
+        TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+    };
+    Ok(TransactionOutcome::Sent(CreateMarketResult {
+        market: market.to_string(),
+        base_mint: params.base_mint.to_string(),
+        quote_mint: params.quote_mint.to_string(),
+        base_atoms_per_base_lot,
+        num_base_lots_per_base_unit,
+        quote_atoms_per_quote_lot,
+        num_quote_lots_per_quote_unit,
+        tick_size_in_quote_lots_per_base_unit,
+        tick_size_in_quote_atoms_per_base_unit,
+        bids_size: params.bids_size,
+        asks_size: params.asks_size,
+        num_seats,
+        taker_fee_bps: params.taker_fee_bps,
+        fee_recipient: fee_recipient.to_string(),
+        authority: authority.to_string(),
+        signature: signature.to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A SOL/USDC style market: 9 base decimals, 6 quote decimals
+    const BASE_DECIMALS: u8 = 9;
+    const QUOTE_DECIMALS: u8 = 6;
+
+    fn lot_sizes(
+        tick_size: &str,
+        base_lot_size: &str,
+        quote_lot_size: Option<&str>,
+    ) -> anyhow::Result<LotSizes> {
+        let params = CreateMarketParams {
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            tick_size: tick_size.to_string(),
+            base_lot_size: base_lot_size.to_string(),
+            quote_lot_size: quote_lot_size.map(str::to_string),
+            bids_size: 512,
+            asks_size: 512,
+            num_seats: None,
+            taker_fee_bps: 2,
+            fee_recipient: None,
+            raw_base_units_per_base_unit: None,
+            seat_manager: false,
+        };
+        get_lot_sizes(&params, BASE_DECIMALS, QUOTE_DECIMALS)
+    }
+
+    #[test]
+    fn units_to_atoms_accepts_exact_decimals() {
+        let cases = [
+            ("0.001", 1_000_000, 1_000),
+            ("1", 1_000_000, 1_000_000),
+            (".5", 10, 5),
+            ("2.50", 10, 25),
+            (" 3 ", 10, 30),
+        ];
+        for (amount, atoms_per_unit, expected) in cases {
+            assert_eq!(
+                units_to_atoms("tick size", amount, atoms_per_unit).unwrap(),
+                expected,
+                "{:?}",
+                amount
+            );
+        }
+    }
+
+    #[test]
+    fn units_to_atoms_rejects_inexact_or_malformed_amounts() {
+        let fractional = units_to_atoms("tick size", "0.0000001", 1_000_000).unwrap_err();
+        assert!(fractional
+            .to_string()
+            .contains("not a whole number of atoms"));
+
+        let rejected = ["", ".", "0", "0.000", "-1", "1e3", "abc", "1.2.3"]
+            .into_iter()
+            .filter(|amount| units_to_atoms("tick size", amount, 1_000_000).is_err())
+            .count();
+        assert_eq!(rejected, 8);
+        // More atoms than fit in a u64
+        assert!(units_to_atoms("base lot size", "100000000000", 1_000_000_000).is_err());
+    }
+
+    #[test]
+    fn default_quote_lot_is_one_atom() {
+        let sizes = lot_sizes("0.001", "0.001", None).unwrap();
+        assert_eq!(
+            sizes,
+            LotSizes {
+                base_atoms_per_base_lot: 1_000_000,
+                num_base_lots_per_base_unit: 1_000,
+                quote_atoms_per_quote_lot: 1,
+                num_quote_lots_per_quote_unit: 1_000_000,
+                tick_size_in_quote_lots_per_base_unit: 1_000,
+                tick_size_in_quote_atoms_per_base_unit: 1_000,
+            }
+        );
+    }
+
+    #[test]
+    fn explicit_quote_lot_scales_the_tick() {
+        let sizes = lot_sizes("0.01", "0.1", Some("0.001")).unwrap();
+        assert_eq!(sizes.num_base_lots_per_base_unit, 10);
+        assert_eq!(sizes.quote_atoms_per_quote_lot, 1_000);
+        assert_eq!(sizes.tick_size_in_quote_lots_per_base_unit, 10);
+        assert_eq!(sizes.tick_size_in_quote_atoms_per_base_unit, 10_000);
+    }
+
+    #[test]
+    fn inconsistent_lot_and_tick_sizes_are_rejected() {
+        let cases = [
+            (("0.001", "0.3", None), "must divide the base unit"),
+            (
+                ("0.003", "0.001", Some("0.003")),
+                "must divide the quote unit",
+            ),
+            (
+                ("0.001", "0.1", Some("0.01")),
+                "must be a whole number of quote lots",
+            ),
+            // 1000 base lots per unit, but a tick of only 100 quote lots
+            (
+                ("0.0001", "0.001", None),
+                "must be a multiple of the 1000 base lots per base unit",
+            ),
+        ];
+        for ((tick_size, base_lot_size, quote_lot_size), expected) in cases {
+            match lot_sizes(tick_size, base_lot_size, quote_lot_size) {
+                Err(error) => assert!(
+                    error.to_string().contains(expected),
+                    "{}: {}",
+                    tick_size,
+                    error
+                ),
+                Ok(sizes) => panic!("{} was accepted as {:?}", tick_size, sizes),
+            }
+        }
+    }
+}
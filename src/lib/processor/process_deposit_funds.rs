@@ -0,0 +1,93 @@
+use crate::helpers::market_helpers::get_token_account_setup_instructions;
+use crate::helpers::output_helpers::CommandOutput;
+use crate::helpers::print_helpers::{
+    get_trader_state_summary_for_market, print_trader_state_or_missing_seat, TraderStateSummary,
+};
+use crate::helpers::transaction_helpers::{send_instructions, TransactionOutcome};
+use phoenix::program::create_deposit_funds_instruction;
+use phoenix::program::deposit::DepositParams;
+use phoenix_sdk::sdk_client::*;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DepositFundsResult {
+    pub trader: String,
+    pub signature: String,
+    /// Trader state before the deposit, or `None` if the trader had no seat
+    pub state_before: Option<TraderStateSummary>,
+    /// Trader state after the deposit, or `None` if the trader has no seat
+    pub state_after: Option<TraderStateSummary>,
+}
+
+impl CommandOutput for DepositFundsResult {
+    fn print_text(&self) {
+        println!("Trader state before deposit:");
+        print_trader_state_or_missing_seat(&self.trader, &self.state_before);
+        println!("Deposit funds transaction: {}", self.signature);
+        println!("Trader state after deposit:");
+        print_trader_state_or_missing_seat(&self.trader, &self.state_after);
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        let mut header = vec!["stage", "signature"];
+        header.extend(TraderStateSummary::CSV_HEADER);
+        header
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        [("before", &self.state_before), ("after", &self.state_after)]
+            .into_iter()
+            .filter_map(|(stage, state)| {
+                let mut record = vec![stage.to_string(), self.signature.clone()];
+                record.extend(state.as_ref()?.csv_record());
+                Some(record)
+            })
+            .collect()
+    }
+}
+
+pub async fn process_deposit_funds(
+    market_pubkey: &Pubkey,
+    sdk: &SDKClient,
+    base_amount: f64,
+    quote_amount: f64,
+) -> anyhow::Result<TransactionOutcome<DepositFundsResult>> {
+    let meta = sdk.get_market_metadata(market_pubkey).await?;
+    let trader = sdk.get_trader();
+
+    let base_lots_to_deposit =
+        sdk.raw_base_units_to_base_lots_rounded_down(market_pubkey, base_amount)?;
+    let quote_lots_to_deposit = sdk.quote_units_to_quote_lots(market_pubkey, quote_amount)?;
+    if base_lots_to_deposit == 0 && quote_lots_to_deposit == 0 {
+        return Err(anyhow::anyhow!(
+            "Deposit amounts are below the minimum lot sizes of the market"
+        ));
+    }
+
+    let state_before = get_trader_state_summary_for_market(sdk, market_pubkey, &trader).await?;
+
+    let mut instructions = get_token_account_setup_instructions(sdk, market_pubkey).await?;
+    instructions.push(create_deposit_funds_instruction(
+        market_pubkey,
+        &trader,
+        &meta.base_mint,
+        &meta.quote_mint,
+        &DepositParams {
+            quote_lots_to_deposit,
+            base_lots_to_deposit,
+        },
+    ));
+
+    let signature = match send_instructions(sdk, instructions, vec![]).await? {
+        TransactionOutcome::Sent(signature) => signature,
+        TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+    };
+
+    Ok(TransactionOutcome::Sent(DepositFundsResult {
+        trader: trader.to_string(),
+        signature: signature.to_string(),
+        state_before,
+        state_after: get_trader_state_summary_for_market(sdk, market_pubkey, &trader).await?,
+    }))
+}
@@ -1,22 +1,51 @@
-use std::mem::size_of;
+use crate::helpers::market_helpers::read_market_header;
+use crate::helpers::transaction_helpers::{send_instructions, TransactionOutcome};
 
-use ellipsis_client::EllipsisClient;
-use phoenix::program::MarketHeader;
+use crate::helpers::output_helpers::{optional_field, CommandOutput};
+use phoenix_sdk::sdk_client::SDKClient;
 use phoenix_sdk::utils::get_evictable_trader_ix;
 use phoenix_seat_manager::instruction_builders::{
     create_evict_seat_instruction, EvictTraderAccountBackup,
 };
+use serde::Serialize;
 use solana_sdk::pubkey::Pubkey;
 
+#[derive(Debug, Clone, Serialize)]
+pub struct EvictSeatResult {
+    /// Evicted trader, or `None` if no trader could be evicted
+    pub evicted_trader: Option<String>,
+    pub signature: Option<String>,
+}
+
+impl CommandOutput for EvictSeatResult {
+    fn print_text(&self) {
+        match (&self.evicted_trader, &self.signature) {
+            (Some(trader), Some(signature)) => {
+                println!("Evicting trader: {}", trader);
+                println!("Evict trader tx: {}", signature);
+            }
+            _ => println!("Cannot evict a trader when the market's trader state is not full."),
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec!["evicted_trader", "signature"]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            optional_field(&self.evicted_trader),
+            optional_field(&self.signature),
+        ]]
+    }
+}
+
 pub async fn process_evict_seat(
-    client: &EllipsisClient,
+    sdk: &SDKClient,
     market_pubkey: &Pubkey,
     trader_to_evict: &Option<Pubkey>,
-) -> anyhow::Result<()> {
-    let market_bytes = client.get_account_data(market_pubkey).await?;
-    let (header_bytes, _market_bytes) = market_bytes.split_at(size_of::<MarketHeader>());
-    let market_header = bytemuck::try_from_bytes::<MarketHeader>(header_bytes)
-        .map_err(|e| anyhow::anyhow!("Error deserializing market header. Error: {:?}", e))?;
+) -> anyhow::Result<TransactionOutcome<EvictSeatResult>> {
+    let market_header = read_market_header(&sdk.client.get_account_data(market_pubkey).await?)?;
 
     let maybe_evict_trader_ix = if let Some(trader_pubkey) = trader_to_evict {
         let evict_trader_state = EvictTraderAccountBackup {
@@ -32,19 +61,23 @@ pub async fn process_evict_seat(
             vec![evict_trader_state],
         ))
     } else {
-        get_evictable_trader_ix(client, market_pubkey).await?
+        get_evictable_trader_ix(&sdk.client, market_pubkey).await?
     };
 
     if let Some(evict_trader_ix) = maybe_evict_trader_ix {
-        println!("Evicting trader: {}", evict_trader_ix.accounts[13].pubkey);
-        let tx = client
-            .sign_send_instructions(vec![evict_trader_ix], vec![])
-            .await?;
-        println!("Evict trader tx: {}", tx);
+        let evicted_trader = evict_trader_ix.accounts[13].pubkey;
+        let tx = match send_instructions(sdk, vec![evict_trader_ix], vec![]).await? {
+            TransactionOutcome::Sent(signature) => signature,
+            TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+        };
+        Ok(TransactionOutcome::Sent(EvictSeatResult {
+            evicted_trader: Some(evicted_trader.to_string()),
+            signature: Some(tx.to_string()),
+        }))
     } else {
-        println!("Cannot evict a trader when the market's trader state is not full.");
-        return Ok(());
+        Ok(TransactionOutcome::Sent(EvictSeatResult {
+            evicted_trader: None,
+            signature: None,
+        }))
     }
-
-    Ok(())
 }
@@ -0,0 +1,594 @@
+use crate::helpers::output_helpers::{
+    format_csv_record, optional_field, CommandOutput, OutputFormat,
+};
+use crate::processor::process_stream_events::get_signatures_for_market;
+use clap::ValueEnum;
+use phoenix_sdk::sdk_client::*;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+// Number of attempts to fetch a transaction before giving up on the export
+const TRANSACTION_FETCH_ATTEMPTS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeExportFormat {
+    /// Comma separated values with a header row
+    Csv,
+    /// One JSON object per line
+    Ndjson,
+}
+
+/// Bounds of the export. Signatures are exclusive, slots and timestamps are inclusive
+#[derive(Debug, Clone, Default)]
+pub struct TradeExportRange {
+    /// Start walking backwards from the transaction before this signature
+    pub before: Option<Signature>,
+    /// Stop walking backwards at this signature
+    pub until: Option<Signature>,
+    pub start_slot: Option<u64>,
+    pub end_slot: Option<u64>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeRecord {
+    pub market: String,
+    pub signature: String,
+    pub slot: u64,
+    pub timestamp: i64,
+    pub sequence_number: u64,
+    pub event_index: u64,
+    pub price: f64,
+    pub side: String,
+    pub size: String,
+    pub maker: String,
+    pub taker: String,
+    /// Share of the taker's quote fees for this fill, in quote units
+    pub quote_fees: Option<String>,
+}
+
+impl TradeRecord {
+    pub const CSV_HEADER: [&'static str; 12] = [
+        "market",
+        "signature",
+        "slot",
+        "timestamp",
+        "sequence_number",
+        "event_index",
+        "price",
+        "side",
+        "size",
+        "maker",
+        "taker",
+        "quote_fees",
+    ];
+
+    pub fn csv_record(&self) -> Vec<String> {
+        vec![
+            self.market.clone(),
+            self.signature.clone(),
+            self.slot.to_string(),
+            self.timestamp.to_string(),
+            self.sequence_number.to_string(),
+            self.event_index.to_string(),
+            self.price.to_string(),
+            self.side.clone(),
+            self.size.clone(),
+            self.maker.clone(),
+            self.taker.clone(),
+            optional_field(&self.quote_fees),
+        ]
+    }
+}
+
+/// Range options of an export as saved in its checkpoint, so the export is only resumed with the same range
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointRange {
+    pub before: Option<String>,
+    pub until: Option<String>,
+    pub start_slot: Option<u64>,
+    pub end_slot: Option<u64>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+}
+
+impl From<&TradeExportRange> for CheckpointRange {
+    fn from(range: &TradeExportRange) -> Self {
+        CheckpointRange {
+            before: range.before.map(|signature| signature.to_string()),
+            until: range.until.map(|signature| signature.to_string()),
+            start_slot: range.start_slot,
+            end_slot: range.end_slot,
+            start_time: range.start_time,
+            end_time: range.end_time,
+        }
+    }
+}
+
+impl std::fmt::Display for CheckpointRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = [
+            ("--before", self.before.clone()),
+            ("--until", self.until.clone()),
+            ("--start-slot", self.start_slot.map(|slot| slot.to_string())),
+            ("--end-slot", self.end_slot.map(|slot| slot.to_string())),
+            ("--start-time", self.start_time.map(|time| time.to_string())),
+            ("--end-time", self.end_time.map(|time| time.to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| format!("{} {}", name, value)))
+        .collect::<Vec<_>>();
+        if options.is_empty() {
+            write!(f, "no range options")
+        } else {
+            write!(f, "{}", options.join(" "))
+        }
+    }
+}
+
+/// Progress of an export, saved after every transaction so an interrupted export can be resumed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportCheckpoint {
+    pub market: String,
+    pub format: TradeExportFormat,
+    pub range: CheckpointRange,
+    /// Oldest transaction written to the output file. The export resumes from the transaction before it
+    pub last_signature: Option<String>,
+    pub last_slot: Option<u64>,
+    /// Length of the output file once the trades up to `last_signature` were written. Anything after it
+    /// comes from a transaction the checkpoint does not cover yet, and is dropped when resuming
+    pub out_file_len: u64,
+    pub transactions_scanned: u64,
+    pub trades_exported: u64,
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportTradesResult {
+    pub market: String,
+    pub out_file: String,
+    pub resumed: bool,
+    pub transactions_scanned: u64,
+    pub trades_exported: u64,
+    pub last_signature: Option<String>,
+    pub last_slot: Option<u64>,
+    pub complete: bool,
+}
+
+impl CommandOutput for ExportTradesResult {
+    fn print_text(&self) {
+        println!(
+            "Exported {} trades from {} transactions to {}",
+            self.trades_exported, self.transactions_scanned, self.out_file
+        );
+        if let (Some(signature), Some(slot)) = (&self.last_signature, self.last_slot) {
+            println!("Oldest transaction exported: {} (slot {})", signature, slot);
+        }
+        if !self.complete {
+            println!("Export incomplete, run the same command again to resume");
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "market",
+            "out_file",
+            "resumed",
+            "transactions_scanned",
+            "trades_exported",
+            "last_signature",
+            "last_slot",
+            "complete",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.market.clone(),
+            self.out_file.clone(),
+            self.resumed.to_string(),
+            self.transactions_scanned.to_string(),
+            self.trades_exported.to_string(),
+            optional_field(&self.last_signature),
+            optional_field(&self.last_slot),
+            self.complete.to_string(),
+        ]]
+    }
+}
+
+/// Walks the transactions of a market from newest to oldest and writes every Fill in the range to
+/// `out_file`. Trades are therefore written newest first
+pub async fn process_export_trades(
+    market_pubkey: &Pubkey,
+    sdk: &SDKClient,
+    range: TradeExportRange,
+    format: TradeExportFormat,
+    out_file: &Path,
+    checkpoint_file: Option<&Path>,
+    output: OutputFormat,
+) -> anyhow::Result<ExportTradesResult> {
+    let existing_checkpoint = match checkpoint_file {
+        Some(path) if path.exists() => Some(read_checkpoint(path)?),
+        _ => None,
+    };
+    let resumed = existing_checkpoint.is_some();
+    let mut checkpoint = match existing_checkpoint {
+        Some(checkpoint) => {
+            if checkpoint.market != market_pubkey.to_string() {
+                return Err(anyhow::anyhow!(
+                    "Checkpoint is for market {}, not {}",
+                    checkpoint.market,
+                    market_pubkey
+                ));
+            }
+            if checkpoint.format != format {
+                return Err(anyhow::anyhow!(
+                    "Checkpoint was written for the {:?} format, not {:?}",
+                    checkpoint.format,
+                    format
+                ));
+            }
+            if checkpoint.range != CheckpointRange::from(&range) {
+                return Err(anyhow::anyhow!(
+                    "Checkpoint was written for the range ({}), but this export has ({}). Pass the same range options or another checkpoint file",
+                    checkpoint.range,
+                    CheckpointRange::from(&range)
+                ));
+            }
+            checkpoint
+        }
+        None => ExportCheckpoint {
+            market: market_pubkey.to_string(),
+            format,
+            range: CheckpointRange::from(&range),
+            last_signature: None,
+            last_slot: None,
+            out_file_len: 0,
+            transactions_scanned: 0,
+            trades_exported: 0,
+            complete: false,
+        },
+    };
+
+    let mut result = ExportTradesResult {
+        market: market_pubkey.to_string(),
+        out_file: out_file.display().to_string(),
+        resumed,
+        transactions_scanned: checkpoint.transactions_scanned,
+        trades_exported: checkpoint.trades_exported,
+        last_signature: checkpoint.last_signature.clone(),
+        last_slot: checkpoint.last_slot,
+        complete: checkpoint.complete,
+    };
+    if checkpoint.complete {
+        return Ok(result);
+    }
+
+    // A resumed export appends to the trades written up to the checkpoint. Trades of the transaction
+    // that was being exported when the export stopped are cut off, since it is exported again
+    let mut before = match &checkpoint.last_signature {
+        Some(signature) => Some(Signature::from_str(signature)?),
+        None => range.before,
+    };
+    let mut file = if resumed {
+        OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(out_file)?
+    } else {
+        File::create(out_file)?
+    };
+    if resumed {
+        let file_len = file.metadata()?.len();
+        if file_len < checkpoint.out_file_len {
+            return Err(anyhow::anyhow!(
+                "{} holds {} bytes, but the checkpoint recorded {}. The output file was changed since, \
+                so start over with another checkpoint file",
+                out_file.display(),
+                file_len,
+                checkpoint.out_file_len
+            ));
+        }
+        file.set_len(checkpoint.out_file_len)?;
+        file.seek(SeekFrom::End(0))?;
+    }
+    let write_header = format == TradeExportFormat::Csv && file.metadata()?.len() == 0;
+    let mut writer = BufWriter::new(file);
+    if write_header {
+        writeln!(writer, "{}", format_csv_record(TradeRecord::CSV_HEADER))?;
+        writer.flush()?;
+    }
+
+    let metadata = *sdk.get_market_metadata_from_cache(market_pubkey)?;
+    'pages: loop {
+        let page = get_signatures_for_market(&sdk.client, market_pubkey, before, range.until, None)
+            .await?;
+        let Some(oldest) = page.last() else {
+            break;
+        };
+        before = Some(Signature::from_str(&oldest.signature)?);
+
+        for status in page.iter() {
+            if is_before_range(status, &range) {
+                break 'pages;
+            }
+            let signature = Signature::from_str(&status.signature)?;
+            if status.err.is_none() && !is_after_range(status, &range) {
+                let events =
+                    get_transaction_events(sdk, &signature, checkpoint_file.is_some()).await?;
+                for trade in get_trade_records(sdk, market_pubkey, &metadata, events)? {
+                    match format {
+                        TradeExportFormat::Csv => {
+                            writeln!(writer, "{}", format_csv_record(trade.csv_record()))?
+                        }
+                        TradeExportFormat::Ndjson => {
+                            writeln!(writer, "{}", serde_json::to_string(&trade)?)?
+                        }
+                    }
+                    checkpoint.trades_exported += 1;
+                }
+            }
+            checkpoint.transactions_scanned += 1;
+            checkpoint.last_signature = Some(status.signature.clone());
+            checkpoint.last_slot = Some(status.slot);
+
+            // Flush the trades before recording progress so a resumed export never skips a trade
+            writer.flush()?;
+            checkpoint.out_file_len = writer.get_ref().metadata()?.len();
+            if let Some(path) = checkpoint_file {
+                write_checkpoint(path, &checkpoint)?;
+            }
+        }
+
+        if output == OutputFormat::Text {
+            println!(
+                "Scanned {} transactions, exported {} trades, reached slot {}",
+                checkpoint.transactions_scanned, checkpoint.trades_exported, oldest.slot
+            );
+        }
+    }
+
+    checkpoint.complete = true;
+    if let Some(path) = checkpoint_file {
+        write_checkpoint(path, &checkpoint)?;
+    }
+
+    result.transactions_scanned = checkpoint.transactions_scanned;
+    result.trades_exported = checkpoint.trades_exported;
+    result.last_signature = checkpoint.last_signature;
+    result.last_slot = checkpoint.last_slot;
+    result.complete = true;
+    Ok(result)
+}
+
+/// Whether the transaction is older than the range. Since signatures are walked newest first,
+/// nothing after it can be in the range either
+pub fn is_before_range(
+    status: &RpcConfirmedTransactionStatusWithSignature,
+    range: &TradeExportRange,
+) -> bool {
+    let before_start_slot = range
+        .start_slot
+        .is_some_and(|start_slot| status.slot < start_slot);
+    let before_start_time = range.start_time.is_some_and(|start_time| {
+        status
+            .block_time
+            .is_some_and(|block_time| block_time < start_time)
+    });
+    before_start_slot || before_start_time
+}
+
+pub fn is_after_range(
+    status: &RpcConfirmedTransactionStatusWithSignature,
+    range: &TradeExportRange,
+) -> bool {
+    let after_end_slot = range
+        .end_slot
+        .is_some_and(|end_slot| status.slot > end_slot);
+    let after_end_time = range.end_time.is_some_and(|end_time| {
+        status
+            .block_time
+            .is_some_and(|block_time| block_time > end_time)
+    });
+    after_end_slot || after_end_time
+}
+
+pub async fn get_transaction_events(
+    sdk: &SDKClient,
+    signature: &Signature,
+    resumable: bool,
+) -> anyhow::Result<Vec<PhoenixEvent>> {
+    for attempt in 1..=TRANSACTION_FETCH_ATTEMPTS {
+        if let Some(events) = sdk.parse_events_from_transaction(signature).await {
+            return Ok(events);
+        }
+        if attempt < TRANSACTION_FETCH_ATTEMPTS {
+            tokio::time::sleep(Duration::from_secs(attempt as u64)).await;
+        }
+    }
+    let hint = if resumable {
+        ". Run the same command again to resume from the checkpoint"
+    } else {
+        ""
+    };
+    Err(anyhow::anyhow!(
+        "Failed to parse events from transaction {}{}",
+        signature,
+        hint
+    ))
+}
+
+/// Converts the Fill events of a transaction into trade records. A FillSummary event follows the fills
+/// of each order and holds the total quote fees paid by the taker, which are split across those fills
+/// in proportion to their quote amount
+pub fn get_trade_records(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+    metadata: &MarketMetadata,
+    events: Vec<PhoenixEvent>,
+) -> anyhow::Result<Vec<TradeRecord>> {
+    let mut trades = vec![];
+    // Indices into `trades` and quote amounts (in ticks times base lots) of the fills awaiting a summary
+    let mut pending_fills: Vec<(usize, u128)> = vec![];
+    for event in events.into_iter().filter(|e| e.market == *market_pubkey) {
+        match event.details {
+            MarketEventDetails::Fill(fill) => {
+                pending_fills.push((
+                    trades.len(),
+                    fill.price_in_ticks as u128 * fill.base_lots_filled as u128,
+                ));
+                trades.push(TradeRecord {
+                    market: market_pubkey.to_string(),
+                    signature: event.signature.to_string(),
+                    slot: event.slot,
+                    timestamp: event.timestamp,
+                    sequence_number: event.sequence_number,
+                    event_index: event.event_index,
+                    price: sdk.ticks_to_float_price(market_pubkey, fill.price_in_ticks)?,
+                    side: format!("{:?}", fill.side_filled),
+                    size: get_decimal_string(
+                        sdk.base_lots_to_base_atoms(market_pubkey, fill.base_lots_filled)?,
+                        metadata.base_decimals,
+                    ),
+                    maker: fill.maker.to_string(),
+                    taker: fill.taker.to_string(),
+                    quote_fees: None,
+                });
+            }
+            MarketEventDetails::FillSummary(summary) => {
+                let weights = pending_fills.iter().map(|(_, w)| *w).collect::<Vec<_>>();
+                let shares = split_quote_fees(summary.total_quote_fees, &weights);
+                for ((trade_index, _), fee_atoms) in pending_fills.iter().zip(shares) {
+                    trades[*trade_index].quote_fees =
+                        Some(get_decimal_string(fee_atoms, metadata.quote_decimals));
+                }
+                pending_fills.clear();
+            }
+            _ => {}
+        }
+    }
+    Ok(trades)
+}
+
+// Splits the quote fees of an order across its fills in proportion to their weights. The last fill takes
+// the rounding remainder so the shares add up to the total
+fn split_quote_fees(total_quote_fees: u64, weights: &[u128]) -> Vec<u64> {
+    let total_weight = weights.iter().sum::<u128>();
+    let mut remaining_fees = total_quote_fees;
+    weights
+        .iter()
+        .enumerate()
+        .map(|(i, weight)| {
+            let fee_atoms = if i == weights.len() - 1 {
+                remaining_fees
+            } else {
+                (total_quote_fees as u128 * weight)
+                    .checked_div(total_weight)
+                    .unwrap_or(0) as u64
+            };
+            remaining_fees -= fee_atoms;
+            fee_atoms
+        })
+        .collect()
+}
+
+fn read_checkpoint(path: &Path) -> anyhow::Result<ExportCheckpoint> {
+    let contents = std::fs::read_to_string(path)?;
+    serde_json::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("Invalid checkpoint file {}: {}", path.display(), e))
+}
+
+fn write_checkpoint(path: &Path, checkpoint: &ExportCheckpoint) -> anyhow::Result<()> {
+    // Write to a temporary file first so an interruption never leaves a truncated checkpoint
+    let tmp_path = PathBuf::from(format!("{}.tmp", path.display()));
+    std::fs::write(&tmp_path, serde_json::to_string_pretty(checkpoint)?)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slot_and_time_bounds_are_inclusive() {
+        let range = TradeExportRange {
+            start_slot: Some(100),
+            end_slot: Some(200),
+            start_time: Some(1_000),
+            end_time: Some(2_000),
+            ..Default::default()
+        };
+        // (slot, block time, before the range, after the range)
+        let cases = [
+            (99, Some(1_500), true, false),
+            (100, Some(1_500), false, false),
+            (200, Some(1_500), false, false),
+            (201, Some(1_500), false, true),
+            (150, Some(999), true, false),
+            (150, Some(2_001), false, true),
+            // Without a block time, only the slot bounds apply
+            (150, None, false, false),
+        ];
+        for (slot, block_time, before, after) in cases {
+            let status = RpcConfirmedTransactionStatusWithSignature {
+                signature: Signature::default().to_string(),
+                slot,
+                err: None,
+                memo: None,
+                block_time,
+                confirmation_status: None,
+            };
+            assert_eq!(is_before_range(&status, &range), before, "slot {}", slot);
+            assert_eq!(is_after_range(&status, &range), after, "slot {}", slot);
+        }
+    }
+
+    #[test]
+    fn quote_fees_split_by_weight_and_add_up() {
+        assert_eq!(split_quote_fees(100, &[1, 3]), [25, 75]);
+        // The last fill takes the rounding remainder, or everything when no fill has a weight
+        assert_eq!(split_quote_fees(10, &[1, 1, 1]), [3, 3, 4]);
+        assert_eq!(split_quote_fees(9, &[0, 0]), [0, 9]);
+        assert!(split_quote_fees(7, &[]).is_empty());
+    }
+
+    #[test]
+    fn checkpoint_survives_a_round_trip_under_a_tmp_name() {
+        let path = std::env::temp_dir().join(format!("export-{}.tmp", std::process::id()));
+        let checkpoint = ExportCheckpoint {
+            market: Pubkey::new_unique().to_string(),
+            format: TradeExportFormat::Csv,
+            range: CheckpointRange::from(&TradeExportRange {
+                start_slot: Some(100),
+                end_time: Some(2_000),
+                ..Default::default()
+            }),
+            last_signature: None,
+            last_slot: Some(150),
+            out_file_len: 4_096,
+            transactions_scanned: 12,
+            trades_exported: 30,
+            complete: false,
+        };
+        write_checkpoint(&path, &checkpoint).unwrap();
+        let read = read_checkpoint(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let read = read.unwrap();
+        assert_eq!(read.range, checkpoint.range);
+        assert_eq!(read.out_file_len, 4_096);
+        assert_eq!(read.range.to_string(), "--start-slot 100 --end-time 2000");
+        assert_eq!(CheckpointRange::default().to_string(), "no range options");
+    }
+}
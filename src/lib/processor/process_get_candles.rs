@@ -0,0 +1,324 @@
+use crate::helpers::output_helpers::CommandOutput;
+use crate::helpers::print_helpers::get_precision;
+use crate::processor::process_export_trades::{
+    get_trade_records, get_transaction_events, is_after_range, is_before_range, TradeExportRange,
+    TradeRecord,
+};
+use crate::processor::process_stream_events::get_signatures_for_market;
+use chrono::{DateTime, SecondsFormat};
+use clap::ValueEnum;
+use phoenix_sdk::sdk_client::*;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ValueEnum)]
+pub enum CandleResolution {
+    #[value(name = "1m")]
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[value(name = "5m")]
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[value(name = "1h")]
+    #[serde(rename = "1h")]
+    OneHour,
+    #[value(name = "1d")]
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl CandleResolution {
+    pub fn seconds(&self) -> i64 {
+        match self {
+            CandleResolution::OneMinute => 60,
+            CandleResolution::FiveMinutes => 5 * 60,
+            CandleResolution::OneHour => 60 * 60,
+            CandleResolution::OneDay => 24 * 60 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Candle {
+    /// Unix timestamp of the start of the bar
+    pub start_time: i64,
+    /// Start of the bar in RFC 3339 format, in UTC
+    pub time: String,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Traded size in base units
+    pub base_volume: f64,
+    /// Traded notional in quote units, excluding fees
+    pub quote_volume: f64,
+    pub trade_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Candles {
+    pub market: String,
+    pub resolution: CandleResolution,
+    pub start_time: i64,
+    pub end_time: i64,
+    /// Bars with at least one trade, oldest first
+    pub candles: Vec<Candle>,
+    #[serde(skip)]
+    pub price_precision: usize,
+    #[serde(skip)]
+    pub size_precision: usize,
+}
+
+impl CommandOutput for Candles {
+    fn print_text(&self) {
+        println!(
+            "{0: <20} | {1: <12} | {2: <12} | {3: <12} | {4: <12} | {5: <15} | {6: <15} | {7: <8}",
+            "Time (UTC)", "Open", "High", "Low", "Close", "Base Volume", "Quote Volume", "Trades"
+        );
+        for candle in &self.candles {
+            println!(
+                "{0: <20} | {1: <12} | {2: <12} | {3: <12} | {4: <12} | {5: <15} | {6: <15} | {7: <8}",
+                format_bar_time(candle.start_time),
+                format!("{:.1$}", candle.open, self.price_precision),
+                format!("{:.1$}", candle.high, self.price_precision),
+                format!("{:.1$}", candle.low, self.price_precision),
+                format!("{:.1$}", candle.close, self.price_precision),
+                format!("{:.1$}", candle.base_volume, self.size_precision),
+                format!("{:.1$}", candle.quote_volume, self.price_precision),
+                candle.trade_count,
+            );
+        }
+        if self.candles.is_empty() {
+            println!(
+                "No trades between {} and {}",
+                self.start_time, self.end_time
+            );
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "start_time",
+            "time",
+            "open",
+            "high",
+            "low",
+            "close",
+            "base_volume",
+            "quote_volume",
+            "trade_count",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        self.candles
+            .iter()
+            .map(|candle| {
+                vec![
+                    candle.start_time.to_string(),
+                    candle.time.clone(),
+                    candle.open.to_string(),
+                    candle.high.to_string(),
+                    candle.low.to_string(),
+                    candle.close.to_string(),
+                    candle.base_volume.to_string(),
+                    candle.quote_volume.to_string(),
+                    candle.trade_count.to_string(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Builds OHLCV bars from the fills of a market between `start_time` and `end_time`. The window
+/// defaults to the last `bars` bars up to now
+pub async fn process_get_candles(
+    market_pubkey: &Pubkey,
+    sdk: &SDKClient,
+    resolution: CandleResolution,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    bars: u64,
+) -> anyhow::Result<Candles> {
+    let end_time = match end_time {
+        Some(end_time) => end_time,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64,
+    };
+    let start_time = start_time.unwrap_or_else(|| {
+        get_bar_start(end_time, resolution) - (bars.saturating_sub(1) as i64) * resolution.seconds()
+    });
+    if start_time > end_time {
+        return Err(anyhow::anyhow!(
+            "Start time {} is after end time {}",
+            start_time,
+            end_time
+        ));
+    }
+
+    let metadata = *sdk.get_market_metadata_from_cache(market_pubkey)?;
+    let range = TradeExportRange {
+        start_time: Some(start_time),
+        end_time: Some(end_time),
+        ..TradeExportRange::default()
+    };
+
+    let mut trades = vec![];
+    let mut before = None;
+    'pages: loop {
+        let page =
+            get_signatures_for_market(&sdk.client, market_pubkey, before, None, None).await?;
+        let Some(oldest) = page.last() else {
+            break;
+        };
+        before = Some(Signature::from_str(&oldest.signature)?);
+
+        for status in page.iter() {
+            if is_before_range(status, &range) {
+                break 'pages;
+            }
+            if status.err.is_some() || is_after_range(status, &range) {
+                continue;
+            }
+            let signature = Signature::from_str(&status.signature)?;
+            let events = get_transaction_events(sdk, &signature, false).await?;
+            trades.extend(get_trade_records(sdk, market_pubkey, &metadata, events)?);
+        }
+    }
+
+    let price_precision = get_precision(
+        10_u64.pow(metadata.quote_decimals) * metadata.raw_base_units_per_base_unit as u64
+            / metadata.tick_size_in_quote_atoms_per_base_unit,
+    );
+    let size_precision = get_precision(
+        metadata.num_base_lots_per_base_unit / metadata.raw_base_units_per_base_unit as u64,
+    );
+
+    Ok(Candles {
+        market: market_pubkey.to_string(),
+        resolution,
+        start_time,
+        end_time,
+        candles: build_candles(trades, resolution, start_time, end_time)?,
+        price_precision,
+        size_precision,
+    })
+}
+
+/// Aggregates trades into bars, skipping bars without trades
+pub fn build_candles(
+    mut trades: Vec<TradeRecord>,
+    resolution: CandleResolution,
+    start_time: i64,
+    end_time: i64,
+) -> anyhow::Result<Vec<Candle>> {
+    trades.retain(|trade| trade.timestamp >= start_time && trade.timestamp <= end_time);
+    // Fills are walked newest first, so order them by execution before picking opens and closes
+    trades.sort_by_key(|trade| (trade.slot, trade.sequence_number, trade.event_index));
+
+    let mut candles = BTreeMap::new();
+    for trade in trades {
+        let size = trade.size.parse::<f64>()?;
+        let bar_start = get_bar_start(trade.timestamp, resolution);
+        let candle = candles.entry(bar_start).or_insert_with(|| Candle {
+            start_time: bar_start,
+            time: DateTime::from_timestamp(bar_start, 0)
+                .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+                .unwrap_or_default(),
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            base_volume: 0.0,
+            quote_volume: 0.0,
+            trade_count: 0,
+        });
+        candle.high = candle.high.max(trade.price);
+        candle.low = candle.low.min(trade.price);
+        candle.close = trade.price;
+        candle.base_volume += size;
+        candle.quote_volume += size * trade.price;
+        candle.trade_count += 1;
+    }
+    Ok(candles.into_values().collect())
+}
+
+fn get_bar_start(timestamp: i64, resolution: CandleResolution) -> i64 {
+    timestamp - timestamp.rem_euclid(resolution.seconds())
+}
+
+fn format_bar_time(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fills given as (timestamp, price, size), newest first as the market's transactions are walked
+    fn fills(fills: &[(i64, f64, &str)]) -> Vec<TradeRecord> {
+        let count = fills.len() as u64;
+        fills
+            .iter()
+            .enumerate()
+            .map(|(i, &(timestamp, price, size))| TradeRecord {
+                market: Pubkey::default().to_string(),
+                signature: Signature::default().to_string(),
+                slot: count - i as u64,
+                timestamp,
+                sequence_number: count - i as u64,
+                event_index: 0,
+                price,
+                side: "Bid".to_string(),
+                size: size.to_string(),
+                maker: Pubkey::default().to_string(),
+                taker: Pubkey::default().to_string(),
+                quote_fees: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bar_follows_execution_order() {
+        let trades = fills(&[(150, 12.0, "1"), (130, 8.0, "2"), (120, 10.0, "0.5")]);
+        let candles = build_candles(trades, CandleResolution::OneMinute, 0, 1_000).unwrap();
+
+        assert_eq!(candles.len(), 1);
+        let bar = &candles[0];
+        assert_eq!(
+            (bar.start_time, bar.time.as_str()),
+            (120, "1970-01-01T00:02:00Z")
+        );
+        assert_eq!(
+            (bar.open, bar.high, bar.low, bar.close),
+            (10.0, 12.0, 8.0, 12.0)
+        );
+        assert_eq!((bar.base_volume, bar.quote_volume), (3.5, 33.0));
+        assert_eq!(bar.trade_count, 3);
+    }
+
+    #[test]
+    fn only_bars_with_trades_in_range_are_built() {
+        let trades = fills(&[
+            (3_601, 4.0, "1"),
+            (3_600, 3.0, "1"),
+            (100, 2.0, "1"),
+            (99, 1.0, "1"),
+        ]);
+        let candles = build_candles(trades, CandleResolution::OneMinute, 100, 3_600).unwrap();
+        let bars = candles
+            .iter()
+            .map(|bar| (bar.start_time, bar.close))
+            .collect::<Vec<_>>();
+        // The empty minutes between the two trades in range get no bar
+        assert_eq!(bars, [(60, 2.0), (3_600, 3.0)]);
+
+        let invalid = fills(&[(10, 1.0, "not a size")]);
+        assert!(build_candles(invalid, CandleResolution::OneDay, 0, 100).is_err());
+    }
+}
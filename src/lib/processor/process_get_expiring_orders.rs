@@ -0,0 +1,205 @@
+use phoenix::quantities::WrapperU64;
+use phoenix::state::markets::{FIFOOrderId, FIFORestingOrder};
+use phoenix::state::Side;
+use phoenix_sdk::sdk_client::*;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::helpers::market_helpers::{get_open_orders_for_trader, TraderOpenOrders};
+use crate::helpers::output_helpers::{optional_field, CommandOutput};
+use crate::helpers::print_helpers::get_precision;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpiringOrder {
+    pub side: String,
+    /// Order sequence number, as a signed integer so bids are negative
+    pub order_id: i64,
+    pub price: f64,
+    pub size: f64,
+    /// Last slot at which the order is valid, or `None` if it has no slot expiry
+    pub last_valid_slot: Option<u64>,
+    /// Last unix timestamp at which the order is valid, or `None` if it has no timestamp expiry
+    pub last_valid_unix_timestamp: Option<u64>,
+    pub slots_remaining: Option<u64>,
+    pub seconds_remaining: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpiringOrders {
+    pub market: String,
+    pub trader: String,
+    /// Slot and unix timestamp of the on-chain clock the orders were read at
+    pub slot: u64,
+    pub unix_timestamp: i64,
+    /// Orders with an expiry inside the window, soonest to expire first
+    pub orders: Vec<ExpiringOrder>,
+    #[serde(skip)]
+    pub price_precision: usize,
+    #[serde(skip)]
+    pub size_precision: usize,
+}
+
+impl CommandOutput for ExpiringOrders {
+    fn print_text(&self) {
+        println!(
+            "Current slot: {}, unix timestamp: {}",
+            self.slot, self.unix_timestamp
+        );
+        if self.orders.is_empty() {
+            println!("No expiring orders");
+            return;
+        }
+        println!(
+            "{0: <5} | {1: <20} | {2: <10} | {3: <10} | {4: <15} | {5: <15} ",
+            "Side", "ID", "Price", "Quantity", "Slots Remaining", "Seconds Remaining"
+        );
+        for order in &self.orders {
+            println!(
+                "{0: <5} | {1: <20} | {2: <10} | {3: <10} | {4: <15} | {5: <15} ",
+                order.side,
+                order.order_id,
+                format!("{:.1$}", order.price, self.price_precision),
+                format!("{:.1$}", order.size, self.size_precision),
+                order
+                    .slots_remaining
+                    .map(|slots| slots.to_string())
+                    .unwrap_or_else(|| "∞".to_string()),
+                order
+                    .seconds_remaining
+                    .map(|seconds| seconds.to_string())
+                    .unwrap_or_else(|| "∞".to_string()),
+            );
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "side",
+            "order_id",
+            "price",
+            "size",
+            "last_valid_slot",
+            "last_valid_unix_timestamp",
+            "slots_remaining",
+            "seconds_remaining",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        self.orders
+            .iter()
+            .map(|order| {
+                vec![
+                    order.side.clone(),
+                    order.order_id.to_string(),
+                    order.price.to_string(),
+                    order.size.to_string(),
+                    optional_field(&order.last_valid_slot),
+                    optional_field(&order.last_valid_unix_timestamp),
+                    optional_field(&order.slots_remaining),
+                    optional_field(&order.seconds_remaining),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Gets the open orders of a trader that expire within the given number of slots or seconds. Without
+/// a window, every order with an expiry is listed
+pub async fn process_get_expiring_orders(
+    market_pubkey: &Pubkey,
+    trader_pubkey: &Pubkey,
+    sdk: &SDKClient,
+    within_slots: Option<u64>,
+    within_seconds: Option<u64>,
+) -> anyhow::Result<ExpiringOrders> {
+    let meta = sdk.get_market_metadata(market_pubkey).await?;
+    let TraderOpenOrders { clock, bids, asks } =
+        get_open_orders_for_trader(&sdk.client, market_pubkey, trader_pubkey).await?;
+
+    let raw_base_units_per_base_lot =
+        meta.base_atoms_per_base_lot as f64 / meta.base_atoms_per_raw_base_unit as f64;
+    let price_precision: usize = get_precision(
+        10_u64.pow(meta.quote_decimals) / meta.tick_size_in_quote_atoms_per_base_unit,
+    );
+    let size_precision: usize = get_precision(meta.num_base_lots_per_base_unit);
+
+    let now = clock.unix_timestamp as u64;
+    let mut orders = vec![];
+    for (order_id, order) in bids.iter().chain(asks.iter()) {
+        let expiring_order = get_expiring_order(
+            sdk,
+            market_pubkey,
+            order_id,
+            order,
+            clock.slot,
+            now,
+            raw_base_units_per_base_lot,
+        )?;
+        if expiring_order.slots_remaining.is_none() && expiring_order.seconds_remaining.is_none() {
+            continue;
+        }
+        let in_window = match (within_slots, within_seconds) {
+            (None, None) => true,
+            (within_slots, within_seconds) => {
+                within_slots.is_some_and(|within| {
+                    expiring_order
+                        .slots_remaining
+                        .is_some_and(|slots| slots <= within)
+                }) || within_seconds.is_some_and(|within| {
+                    expiring_order
+                        .seconds_remaining
+                        .is_some_and(|seconds| seconds <= within)
+                })
+            }
+        };
+        if in_window {
+            orders.push(expiring_order);
+        }
+    }
+    orders.sort_by_key(|order| {
+        (
+            order.seconds_remaining.unwrap_or(u64::MAX),
+            order.slots_remaining.unwrap_or(u64::MAX),
+        )
+    });
+
+    Ok(ExpiringOrders {
+        market: market_pubkey.to_string(),
+        trader: trader_pubkey.to_string(),
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+        orders,
+        price_precision,
+        size_precision,
+    })
+}
+
+fn get_expiring_order(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+    order_id: &FIFOOrderId,
+    order: &FIFORestingOrder,
+    slot: u64,
+    unix_timestamp: u64,
+    raw_base_units_per_base_lot: f64,
+) -> anyhow::Result<ExpiringOrder> {
+    // A zero slot or timestamp means the order never expires on that dimension
+    let last_valid_slot = Some(order.last_valid_slot).filter(|slot| *slot != 0);
+    let last_valid_unix_timestamp =
+        Some(order.last_valid_unix_timestamp_in_seconds).filter(|timestamp| *timestamp != 0);
+    Ok(ExpiringOrder {
+        side: format!(
+            "{:?}",
+            Side::from_order_sequence_number(order_id.order_sequence_number)
+        ),
+        order_id: order_id.order_sequence_number as i64,
+        price: sdk.ticks_to_float_price(market_pubkey, order_id.price_in_ticks.as_u64())?,
+        size: order.num_base_lots.as_u64() as f64 * raw_base_units_per_base_lot,
+        last_valid_slot,
+        last_valid_unix_timestamp,
+        slots_remaining: last_valid_slot.map(|last_valid_slot| 1 + last_valid_slot - slot),
+        seconds_remaining: last_valid_unix_timestamp
+            .map(|last_valid_unix_timestamp| 1 + last_valid_unix_timestamp - unix_timestamp),
+    })
+}
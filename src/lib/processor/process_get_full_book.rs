@@ -1,14 +1,18 @@
 use crate::helpers::market_helpers::*;
-use crate::helpers::print_helpers::print_book;
+use crate::helpers::print_helpers::{get_order_book, OrderBook};
 use phoenix_sdk::sdk_client::*;
 use solana_sdk::pubkey::Pubkey;
 
-pub async fn process_get_full_book(market_pubkey: &Pubkey, sdk: &SDKClient) -> anyhow::Result<()> {
-    let book = get_book_levels(market_pubkey, &sdk.client, u64::MAX).await?;
-    if book.bids.is_empty() && book.asks.is_empty() {
-        println!("Book is empty");
-    } else {
-        print_book(sdk, market_pubkey, &book)?;
-    }
-    Ok(())
+/// Gets the full book of a market. When the market account data is given, it is decoded without any
+/// network access and expired orders are not filtered out, since the clock is unknown
+pub async fn process_get_full_book(
+    market_pubkey: &Pubkey,
+    sdk: &SDKClient,
+    market_account_data: Option<Vec<u8>>,
+) -> anyhow::Result<OrderBook> {
+    let book = match market_account_data {
+        Some(market_account_data) => get_ladder(&market_account_data, None, u64::MAX)?,
+        None => get_book_levels(market_pubkey, &sdk.client, u64::MAX).await?,
+    };
+    get_order_book(sdk, market_pubkey, &book)
 }
@@ -1,40 +1,47 @@
+use crate::helpers::market_helpers::read_market_header;
 use crate::helpers::print_helpers::*;
-use phoenix::program::{load_with_dispatch, MarketHeader};
+use phoenix::program::get_vault_address;
 use phoenix_sdk::sdk_client::*;
+use solana_sdk::program_pack::Pack;
 use solana_sdk::pubkey::Pubkey;
-use std::mem::size_of;
 
-use super::process_get_all_markets::{get_base_and_quote_symbols, get_phoenix_config};
+use crate::helpers::registry_helpers::{get_base_and_quote_symbols, get_phoenix_config};
+
+/// Gets the details of a market. When the market account data is given, it is decoded without any
+/// network access, so the vault balances and token symbols are left out
+pub async fn process_get_market(
+    market_pubkey: &Pubkey,
+    sdk: &SDKClient,
+    market_account_data: Option<Vec<u8>>,
+) -> anyhow::Result<MarketDetails> {
+    if let Some(market_account_data) = market_account_data {
+        return get_market_details(sdk, market_pubkey, &market_account_data, None, None, None);
+    }
 
-pub async fn process_get_market(market_pubkey: &Pubkey, sdk: &SDKClient) -> anyhow::Result<()> {
     let market_metadata = sdk.get_market_metadata(market_pubkey).await?;
     let market_account_data = sdk.client.get_account_data(market_pubkey).await?;
-    let (header_bytes, market_bytes) = market_account_data.split_at(size_of::<MarketHeader>());
-    let header: &MarketHeader = bytemuck::try_from_bytes(header_bytes)
-        .map_err(|e| anyhow::anyhow!("Error getting market header. Error: {:?}", e))?;
-
-    // Derserialize data and load into correct type
-    let market = load_with_dispatch(&header.market_size_params, market_bytes)
-        .map_err(|e| anyhow::anyhow!("Failed to load market. Error {:?}", e))?
-        .inner;
 
-    let taker_fees = market.get_taker_fee_bps();
+    let base_vault = get_vault_address(market_pubkey, &market_metadata.base_mint).0;
+    let quote_vault = get_vault_address(market_pubkey, &market_metadata.quote_mint).0;
+    let base_vault_acct =
+        spl_token::state::Account::unpack(&sdk.client.get_account(&base_vault).await?.data)?;
+    let quote_vault_acct =
+        spl_token::state::Account::unpack(&sdk.client.get_account(&quote_vault).await?.data)?;
 
+    let header = read_market_header(&market_account_data)?;
     let (base_mint_symbol, quote_mint_symbol) =
         if let Ok(config) = get_phoenix_config(&sdk.client).await {
-            get_base_and_quote_symbols(&config, header)
+            get_base_and_quote_symbols(&config, &header)
         } else {
             (None, None)
         };
 
-    print_market_details(
+    get_market_details(
         sdk,
         market_pubkey,
-        &market_metadata,
-        header,
-        taker_fees,
+        &market_account_data,
+        Some((base_vault_acct.amount, quote_vault_acct.amount)),
         base_mint_symbol,
         quote_mint_symbol,
     )
-    .await
 }
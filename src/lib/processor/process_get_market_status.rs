@@ -1,19 +1,41 @@
-use phoenix::program::{status::MarketStatus, MarketHeader};
+use crate::helpers::market_helpers::read_market_header;
+use crate::helpers::output_helpers::CommandOutput;
+use phoenix::program::status::MarketStatus;
 use phoenix_sdk::sdk_client::*;
+use serde::Serialize;
 use solana_sdk::pubkey::Pubkey;
-use std::mem::size_of;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketStatusInfo {
+    pub market: String,
+    pub status: String,
+}
+
+impl CommandOutput for MarketStatusInfo {
+    fn print_text(&self) {
+        println!("Market status: {}", self.status);
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec!["market", "status"]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![self.market.clone(), self.status.clone()]]
+    }
+}
 
 pub async fn process_get_market_status(
     market_pubkey: &Pubkey,
     sdk: &SDKClient,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<MarketStatusInfo> {
     // Get market account
-    let mut market_account_data = sdk.client.get_account_data(market_pubkey).await?;
-    let (header_bytes, _) = market_account_data.split_at_mut(size_of::<MarketHeader>());
-    let header: &MarketHeader = bytemuck::try_from_bytes(header_bytes)
-        .map_err(|e| anyhow::anyhow!("Error getting market header. Error: {:?}", e))?;
+    let market_account_data = sdk.client.get_account_data(market_pubkey).await?;
+    let header = read_market_header(&market_account_data)?;
 
     let status = MarketStatus::from(header.status);
-    println!("Market status: {}", status);
-    Ok(())
+    Ok(MarketStatusInfo {
+        market: market_pubkey.to_string(),
+        status: status.to_string(),
+    })
 }
@@ -1,154 +1,198 @@
-use phoenix::program::{load_with_dispatch, MarketHeader};
 use phoenix::quantities::WrapperU64;
-use phoenix::state::markets::{FIFOOrderId, FIFORestingOrder, RestingOrder};
-use phoenix::state::Side;
+use phoenix::state::markets::{FIFOOrderId, FIFORestingOrder};
 use phoenix_sdk::sdk_client::*;
+use serde::Serialize;
 use solana_sdk::clock::Clock;
-use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::sysvar;
-use std::mem::size_of;
 
+use crate::helpers::market_helpers::{
+    get_open_orders_for_trader, get_trader_orders, TraderOpenOrders,
+};
+use crate::helpers::output_helpers::{optional_field, CommandOutput};
 use crate::helpers::print_helpers::get_precision;
 
-pub async fn process_get_open_orders(
-    market_pubkey: &Pubkey,
-    trader_pubkey: &Pubkey,
-    sdk: &SDKClient,
-) -> anyhow::Result<()> {
-    let meta = sdk.get_market_metadata(market_pubkey).await?;
-    // Get market account
-    let mut market_and_clock = sdk
-        .client
-        .get_multiple_accounts_with_commitment(
-            &[*market_pubkey, sysvar::clock::id()],
-            CommitmentConfig::confirmed(),
-        )
-        .await?
-        .value;
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenOrder {
+    /// Order sequence number, as a signed integer so bids are negative
+    pub order_id: i64,
+    pub price_in_ticks: u64,
+    pub price: f64,
+    pub size: f64,
+    /// Slots until the order expires, or `None` if it has no slot expiry
+    pub slots_remaining: Option<u64>,
+    /// Seconds until the order expires, or `None` if it has no timestamp expiry
+    pub seconds_remaining: Option<u64>,
+}
 
-    let market_account_data = market_and_clock
-        .remove(0)
-        .ok_or_else(|| anyhow::Error::msg("Market account not found"))?
-        .data;
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenOrders {
+    pub market: String,
+    pub trader: String,
+    pub bids: Vec<OpenOrder>,
+    pub asks: Vec<OpenOrder>,
+    #[serde(skip)]
+    pub price_precision: usize,
+    #[serde(skip)]
+    pub size_precision: usize,
+    /// Whether the orders were read at a known clock. Expiry is not evaluated otherwise
+    #[serde(skip)]
+    pub clock_known: bool,
+}
 
-    let clock_account_data = market_and_clock
-        .remove(0)
-        .ok_or_else(|| anyhow::Error::msg("Clock account not found"))?
-        .data;
+impl CommandOutput for OpenOrders {
+    fn print_text(&self) {
+        if !self.clock_known {
+            println!("The market was read from an account file, so order expiry is not evaluated");
+            println!();
+        }
+        println!("Open Bids");
+        self.print_orders(&self.bids);
+        println!();
+        println!("Open Asks");
+        self.print_orders(&self.asks);
+    }
 
-    let clock: Clock = bincode::deserialize(&clock_account_data)
-        .map_err(|_| anyhow::Error::msg("Error deserializing clock"))?;
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "side",
+            "order_id",
+            "price_in_ticks",
+            "price",
+            "size",
+            "slots_remaining",
+            "seconds_remaining",
+        ]
+    }
 
-    let (header_bytes, market_bytes) = market_account_data.split_at(size_of::<MarketHeader>());
-    let header: &MarketHeader = bytemuck::try_from_bytes(header_bytes)
-        .map_err(|e| anyhow::anyhow!("Error getting market header. Error: {:?}", e))?;
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        let bids = self.bids.iter().map(|order| ("bid", order));
+        let asks = self.asks.iter().map(|order| ("ask", order));
+        bids.chain(asks)
+            .map(|(side, order)| {
+                vec![
+                    side.to_string(),
+                    order.order_id.to_string(),
+                    order.price_in_ticks.to_string(),
+                    order.price.to_string(),
+                    order.size.to_string(),
+                    optional_field(&order.slots_remaining),
+                    optional_field(&order.seconds_remaining),
+                ]
+            })
+            .collect()
+    }
+}
 
-    // Derserialize data and load into correct type
-    let market = load_with_dispatch(&header.market_size_params, market_bytes)?.inner;
+impl OpenOrders {
+    fn print_orders(&self, orders: &[OpenOrder]) {
+        let no_expiry = if self.clock_known { "∞" } else { "?" };
+        println!(
+            "{0: <20} | {1: <20} | {2: <10} | {3: <10} | {4: <15} | {5: <15} ",
+            "ID", "Price (ticks)", "Price", "Quantity", "Slots Remaining", "Seconds Remaining"
+        );
+        for order in orders {
+            println!(
+                "{0: <20} | {1: <20} | {2: <10} | {3: <10} | {4: <15} | {5: <15} ",
+                order.order_id,
+                order.price_in_ticks,
+                format!("{:.1$}", order.price, self.price_precision),
+                format!("{:.1$}", order.size, self.size_precision),
+                order
+                    .slots_remaining
+                    .map(|slots| slots.to_string())
+                    .unwrap_or_else(|| no_expiry.to_string()),
+                order
+                    .seconds_remaining
+                    .map(|seconds| seconds.to_string())
+                    .unwrap_or_else(|| no_expiry.to_string()),
+            );
+        }
+    }
+}
+
+/// Gets the open orders of a trader. When the market account data is given, it is decoded without any
+/// network access and order expiry is not evaluated, since the clock is unknown
+pub async fn process_get_open_orders(
+    market_pubkey: &Pubkey,
+    trader_pubkey: &Pubkey,
+    sdk: &SDKClient,
+    market_account_data: Option<Vec<u8>>,
+) -> anyhow::Result<OpenOrders> {
+    let meta = sdk.get_market_metadata(market_pubkey).await?;
+    let (clock, bids, asks) = match market_account_data {
+        Some(market_account_data) => {
+            let (bids, asks) = get_trader_orders(&market_account_data, trader_pubkey, None)?;
+            (None, bids, asks)
+        }
+        None => {
+            let TraderOpenOrders { clock, bids, asks } =
+                get_open_orders_for_trader(&sdk.client, market_pubkey, trader_pubkey).await?;
+            (Some(clock), bids, asks)
+        }
+    };
 
     let raw_base_units_per_base_lot =
         meta.base_atoms_per_base_lot as f64 / meta.base_atoms_per_raw_base_unit as f64;
 
-    let trader_index = market
-        .get_trader_index(trader_pubkey)
-        .ok_or_else(|| anyhow::anyhow!("Trader not found"))?;
-    let book_bids = market.get_book(Side::Bid);
-    let book_asks = market.get_book(Side::Ask);
     let price_precision: usize = get_precision(
         10_u64.pow(meta.quote_decimals) / meta.tick_size_in_quote_atoms_per_base_unit,
     );
     let size_precision: usize = get_precision(meta.num_base_lots_per_base_unit);
 
-    println!("Open Bids");
-    let mut open_bids = vec![];
-    open_bids.push(format!(
-        "{0: <20} | {1: <20} | {2: <10} | {3: <10} | {4: <15} | {5: <15} ",
-        "ID", "Price (ticks)", "Price", "Quantity", "Slots Remaining", "Seconds Remaining"
-    ));
-    for (order_id, order) in book_bids.iter() {
-        if order.trader_index as u32 == trader_index {
-            if order.is_expired(clock.slot, clock.unix_timestamp as u64) {
-                continue;
-            }
-            open_bids.push(format_open_orders(
-                sdk,
-                market_pubkey,
-                order_id,
-                order,
-                price_precision,
-                size_precision,
-                &clock,
-                raw_base_units_per_base_lot,
-            )?);
-        }
-    }
-    open_bids.iter().for_each(|line| println!("{}", line));
-
-    println!();
-    println!("Open Asks");
-    let mut open_asks = vec![];
-    open_asks.push(format!(
-        "{0: <20} | {1: <20} | {2: <10} | {3: <10} | {4: <15} | {5: <15} ",
-        "ID", "Price (ticks)", "Price", "Quantity", "Slots Remaining", "Seconds Remaining"
-    ));
-    for (order_id, order) in book_asks.iter() {
-        if order.trader_index as u32 == trader_index {
-            if order.is_expired(clock.slot, clock.unix_timestamp as u64) {
-                continue;
-            }
-            open_asks.push(format_open_orders(
-                sdk,
-                market_pubkey,
-                order_id,
-                order,
-                price_precision,
-                size_precision,
-                &clock,
-                raw_base_units_per_base_lot,
-            )?);
-        }
-    }
-    open_asks.iter().for_each(|line| println!("{}", line));
+    let to_open_orders = |orders: &[(FIFOOrderId, FIFORestingOrder)]| {
+        orders
+            .iter()
+            .map(|(order_id, order)| {
+                get_open_order(
+                    sdk,
+                    market_pubkey,
+                    order_id,
+                    order,
+                    clock.as_ref(),
+                    raw_base_units_per_base_lot,
+                )
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+    };
 
-    Ok(())
+    Ok(OpenOrders {
+        market: market_pubkey.to_string(),
+        trader: trader_pubkey.to_string(),
+        bids: to_open_orders(&bids)?,
+        asks: to_open_orders(&asks)?,
+        price_precision,
+        size_precision,
+        clock_known: clock.is_some(),
+    })
 }
 
-#[allow(clippy::too_many_arguments)]
-fn format_open_orders(
+fn get_open_order(
     sdk: &SDKClient,
     market_pubkey: &Pubkey,
     order_id: &FIFOOrderId,
     order: &FIFORestingOrder,
-    price_precision: usize,
-    size_precision: usize,
-    clock: &Clock,
+    clock: Option<&Clock>,
     raw_base_units_per_base_lot: f64,
-) -> anyhow::Result<String> {
-    Ok(format!(
-        "{0: <20} | {1: <20} | {2: <10} | {3: <10} | {4: <15} | {5: <15} ",
-        order_id.order_sequence_number as i64,
-        order_id.price_in_ticks,
-        format!(
-            "{:.1$}",
-            sdk.ticks_to_float_price(market_pubkey, order_id.price_in_ticks.as_u64())?,
-            price_precision
-        ),
-        format!(
-            "{:.1$}",
-            order.num_base_lots.as_u64() as f64 * raw_base_units_per_base_lot,
-            size_precision,
-        ),
-        if order.last_valid_slot >= clock.slot {
-            (1 + order.last_valid_slot - clock.slot).to_string()
-        } else {
-            "∞".to_string()
-        },
-        if order.last_valid_unix_timestamp_in_seconds >= clock.unix_timestamp as u64 {
-            (1 + order.last_valid_unix_timestamp_in_seconds - clock.unix_timestamp as u64)
-                .to_string()
-        } else {
-            "∞".to_string()
-        }
-    ))
+) -> anyhow::Result<OpenOrder> {
+    // Without a clock, the time remaining is unknown
+    Ok(OpenOrder {
+        order_id: order_id.order_sequence_number as i64,
+        price_in_ticks: order_id.price_in_ticks.as_u64(),
+        price: sdk.ticks_to_float_price(market_pubkey, order_id.price_in_ticks.as_u64())?,
+        size: order.num_base_lots.as_u64() as f64 * raw_base_units_per_base_lot,
+        slots_remaining: clock.and_then(|clock| {
+            if order.last_valid_slot >= clock.slot {
+                Some(1 + order.last_valid_slot - clock.slot)
+            } else {
+                None
+            }
+        }),
+        seconds_remaining: clock.and_then(|clock| {
+            if order.last_valid_unix_timestamp_in_seconds >= clock.unix_timestamp as u64 {
+                Some(1 + order.last_valid_unix_timestamp_in_seconds - clock.unix_timestamp as u64)
+            } else {
+                None
+            }
+        }),
+    })
 }
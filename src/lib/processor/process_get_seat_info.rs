@@ -1,21 +1,55 @@
 use crate::helpers::market_helpers::*;
+use crate::helpers::output_helpers::{optional_field, CommandOutput};
 use phoenix_sdk::sdk_client::*;
+use serde::Serialize;
 use solana_sdk::pubkey::Pubkey;
 
+#[derive(Debug, Clone, Serialize)]
+pub struct SeatInfo {
+    pub market: String,
+    pub trader: String,
+    pub seat_address: String,
+    /// Approval status of the seat, or `None` if the seat does not exist
+    pub status: Option<String>,
+}
+
+impl CommandOutput for SeatInfo {
+    fn print_text(&self) {
+        println!("Seat address: {}", self.seat_address);
+        match &self.status {
+            Some(status) => println!("Seat status: {}", status),
+            None => println!("Seat status not found"),
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec!["market", "trader", "seat_address", "status"]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.market.clone(),
+            self.trader.clone(),
+            self.seat_address.clone(),
+            optional_field(&self.status),
+        ]]
+    }
+}
+
 pub async fn process_get_seat_info(
     market_pubkey: &Pubkey,
     trader_pubkey: &Pubkey,
     sdk: &SDKClient,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<SeatInfo> {
     let (seat_address, _) = Pubkey::find_program_address(
         &[b"seat", market_pubkey.as_ref(), trader_pubkey.as_ref()],
         &phoenix::ID,
     );
-    println!("Seat address: {}", seat_address);
     let status = get_seat_status(sdk, &seat_address).await;
-    match status {
-        Ok(status) => println!("Seat status: {}", status),
-        _ => println!("Seat status not found"),
-    }
-    Ok(())
+    Ok(SeatInfo {
+        market: market_pubkey.to_string(),
+        trader: trader_pubkey.to_string(),
+        seat_address: seat_address.to_string(),
+        status: status.ok().map(|status| status.to_string()),
+    })
 }
@@ -1,50 +1,125 @@
-use std::mem::size_of;
-
 use ellipsis_client::EllipsisClient;
-use phoenix::program::MarketHeader;
 use phoenix_seat_manager::{get_seat_manager_address, seat_manager::SeatManager};
+use serde::Serialize;
 use solana_sdk::pubkey::Pubkey;
 
-use crate::helpers::market_helpers::get_seat_manager_data_with_market;
+use crate::helpers::market_helpers::{get_seat_manager_data_with_market, read_market_header};
+use crate::helpers::output_helpers::CommandOutput;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SeatManagerDetails {
+    pub address: String,
+    pub market: String,
+    pub authority: String,
+    pub successor: String,
+    pub num_makers: u64,
+    pub designated_market_makers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SeatManagerInfo {
+    pub market: String,
+    pub market_authority: String,
+    pub seat_manager_address: String,
+    /// Seat manager state, or `None` if the seat manager is not the market authority
+    pub seat_manager: Option<SeatManagerDetails>,
+}
+
+impl CommandOutput for SeatManagerInfo {
+    fn print_text(&self) {
+        match &self.seat_manager {
+            Some(seat_manager) => seat_manager.print_text(),
+            None => {
+                println!(
+                    "Authority for Market {} is not the seat manager.",
+                    self.market
+                );
+                println!("Market authority: {}", self.market_authority);
+                println!("Seat manager address: {}", self.seat_manager_address);
+            }
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "market",
+            "market_authority",
+            "seat_manager_address",
+            "seat_manager_authority",
+            "seat_manager_successor",
+            "num_makers",
+            "designated_market_makers",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        let mut record = vec![
+            self.market.clone(),
+            self.market_authority.clone(),
+            self.seat_manager_address.clone(),
+        ];
+        match &self.seat_manager {
+            Some(seat_manager) => record.extend([
+                seat_manager.authority.clone(),
+                seat_manager.successor.clone(),
+                seat_manager.num_makers.to_string(),
+                seat_manager.designated_market_makers.join(" "),
+            ]),
+            None => record.extend(vec![String::new(); 4]),
+        }
+        vec![record]
+    }
+}
+
+impl SeatManagerDetails {
+    pub fn new(seat_manager: &SeatManager, seat_manager_pubkey: &Pubkey) -> Self {
+        SeatManagerDetails {
+            address: seat_manager_pubkey.to_string(),
+            market: seat_manager.market.to_string(),
+            authority: seat_manager.authority.to_string(),
+            successor: seat_manager.successor.to_string(),
+            num_makers: seat_manager.num_makers,
+            designated_market_makers: seat_manager
+                .designated_market_makers
+                .iter()
+                .filter(|&&dmm| dmm != Pubkey::default())
+                .map(|dmm| dmm.to_string())
+                .collect(),
+        }
+    }
+
+    pub fn print_text(&self) {
+        println!("Seat Manager Address: {}", self.address);
+        println!("SM Market: {}", self.market);
+        println!("SM Authority: {}", self.authority);
+        println!("SM Successor: {}", self.successor);
+        println!("Number of designated market makers: {}", self.num_makers);
+        if !self.designated_market_makers.is_empty() {
+            println!("DMMs: [{}]", self.designated_market_makers.join(", "));
+        }
+    }
+}
 
 pub async fn process_get_seat_manager_info(
     client: &EllipsisClient,
     market_pubkey: &Pubkey,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<SeatManagerInfo> {
     let seat_manager_address = get_seat_manager_address(market_pubkey).0;
     let market_data = client.get_account_data(market_pubkey).await?;
-    let market_header =
-        bytemuck::from_bytes::<MarketHeader>(market_data.split_at(size_of::<MarketHeader>()).0);
-    if market_header.authority != seat_manager_address {
-        println!(
-            "Authority for Market {} is not the seat manager.",
-            market_pubkey
-        );
-        println!("Market authority: {}", market_header.authority);
-        println!("Seat manager address: {}", seat_manager_address);
-        return Ok(());
-    }
-    let seat_manager_info = get_seat_manager_data_with_market(client, market_pubkey).await?;
-    print_seat_manager_struct(&seat_manager_info, &seat_manager_address);
-    Ok(())
-}
-
-pub fn print_seat_manager_struct(seat_manager: &SeatManager, seat_manager_pubkey: &Pubkey) {
-    println!("Seat Manager Address: {}", seat_manager_pubkey);
-    println!("SM Market: {}", seat_manager.market);
-    println!("SM Authority: {}", seat_manager.authority);
-    println!("SM Successor: {}", seat_manager.successor);
-    println!(
-        "Number of designated market makers: {}",
-        seat_manager.num_makers
-    );
-
-    let dmms: Vec<&Pubkey> = seat_manager
-        .designated_market_makers
-        .iter()
-        .filter(|&&dmm| dmm != Pubkey::default())
-        .collect();
-    if !dmms.is_empty() {
-        println!("DMMs: {:?}", dmms);
-    }
+    let market_header = read_market_header(&market_data)?;
+    let seat_manager = if market_header.authority != seat_manager_address {
+        None
+    } else {
+        let seat_manager_info = get_seat_manager_data_with_market(client, market_pubkey).await?;
+        Some(SeatManagerDetails::new(
+            &seat_manager_info,
+            &seat_manager_address,
+        ))
+    };
+    Ok(SeatManagerInfo {
+        market: market_pubkey.to_string(),
+        market_authority: market_header.authority.to_string(),
+        seat_manager_address: seat_manager_address.to_string(),
+        seat_manager,
+    })
 }
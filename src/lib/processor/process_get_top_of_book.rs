@@ -1,18 +1,12 @@
 use crate::helpers::market_helpers::*;
-use crate::helpers::print_helpers::print_book;
+use crate::helpers::print_helpers::{get_order_book, OrderBook};
 use phoenix_sdk::sdk_client::*;
 use solana_sdk::pubkey::Pubkey;
 
 pub async fn process_get_top_of_book(
     market_pubkey: &Pubkey,
     sdk: &SDKClient,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<OrderBook> {
     let book = get_book_levels(market_pubkey, &sdk.client, 1).await?;
-    if book.bids.is_empty() && book.asks.is_empty() {
-        println!("Book is empty");
-    } else {
-        print_book(sdk, market_pubkey, &book)?;
-    }
-
-    Ok(())
+    get_order_book(sdk, market_pubkey, &book)
 }
@@ -1,33 +1,66 @@
+use crate::helpers::market_helpers::load_market;
+use crate::helpers::output_helpers::CommandOutput;
 use crate::helpers::print_helpers::*;
-use phoenix::program::{load_with_dispatch, MarketHeader};
 use phoenix_sdk::sdk_client::*;
+use serde::Serialize;
 use solana_sdk::pubkey::Pubkey;
-use std::mem::size_of;
 
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketTraders {
+    pub num_registered_traders: usize,
+    /// Registered traders with locked or free lots
+    pub traders: Vec<TraderStateSummary>,
+}
+
+impl CommandOutput for MarketTraders {
+    fn print_text(&self) {
+        println!(
+            "Found {} trader(s). Printing traders with locked or free lots",
+            self.num_registered_traders
+        );
+        self.traders.iter().for_each(|trader| trader.print_text());
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        TraderStateSummary::CSV_HEADER.to_vec()
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        self.traders
+            .iter()
+            .map(|trader| trader.csv_record())
+            .collect()
+    }
+}
+
+/// Lists the traders of a market, reading the market account from the RPC unless its data is given
 pub async fn process_get_traders_for_market(
     market_pubkey: &Pubkey,
     sdk: &SDKClient,
-) -> anyhow::Result<()> {
+    market_account_data: Option<Vec<u8>>,
+) -> anyhow::Result<MarketTraders> {
     // Get market account
-    let mut market_account_data = sdk.client.get_account_data(market_pubkey).await?;
-    let (header_bytes, market_bytes) = market_account_data.split_at_mut(size_of::<MarketHeader>());
-    let header: &MarketHeader = bytemuck::try_from_bytes(header_bytes)
-        .map_err(|e| anyhow::anyhow!("Error getting market header. Error: {:?}", e))?;
-
-    // Derserialize data and load into correct type
-    let market = load_with_dispatch(&header.market_size_params, market_bytes)
-        .map_err(|e| anyhow::anyhow!("Failed to load market. Error {:?}", e))?
-        .inner;
-
-    println!(
-        "Found {} trader(s). Printing traders with locked or free lots",
-        market.get_registered_traders().len()
-    );
+    let market_account_data = match market_account_data {
+        Some(market_account_data) => market_account_data,
+        None => sdk.client.get_account_data(market_pubkey).await?,
+    };
+    let (_, market) = load_market(&market_account_data)?;
 
-    // Print trader information
+    // Collect trader information
+    let mut traders = vec![];
     for (pubkey, state) in market.get_registered_traders().iter() {
-        print_trader_state(sdk, market_pubkey, pubkey, state)?;
+        if state.base_lots_locked == 0
+            && state.base_lots_free == 0
+            && state.quote_lots_locked == 0
+            && state.quote_lots_free == 0
+        {
+            continue;
+        }
+        traders.push(get_trader_state_summary(sdk, market_pubkey, pubkey, state)?);
     }
 
-    Ok(())
+    Ok(MarketTraders {
+        num_registered_traders: market.get_registered_traders().len(),
+        traders,
+    })
 }
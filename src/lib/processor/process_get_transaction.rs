@@ -5,11 +5,12 @@ use solana_sdk::signature::Signature;
 pub async fn process_get_transaction(
     signature: &Signature,
     sdk: &mut SDKClient,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<MarketEvents> {
     let events = sdk
         .parse_events_from_transaction(signature)
         .await
         .ok_or_else(|| anyhow::anyhow!("Failed to parse events from transaction"))?;
-    log_market_events(sdk, events).await?;
-    Ok(())
+    Ok(MarketEvents {
+        events: get_market_event_records(sdk, events).await?,
+    })
 }
@@ -0,0 +1,88 @@
+use std::mem::size_of;
+
+use crate::helpers::market_helpers::get_seats_for_market_with_status;
+use crate::helpers::output_helpers::CommandOutput;
+use crate::processor::process_market_admin::get_market_state;
+use phoenix::program::status::SeatApprovalStatus;
+use phoenix::program::Seat;
+use phoenix_sdk::sdk_client::SDKClient;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingSeat {
+    pub seat: String,
+    pub trader: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingSeats {
+    pub market: String,
+    /// Traders the market can still register. Approving a seat registers its trader
+    pub free_trader_slots: u64,
+    pub seats: Vec<PendingSeat>,
+}
+
+impl CommandOutput for PendingSeats {
+    fn print_text(&self) {
+        println!("Market: {}", self.market);
+        println!("Free trader slots: {}", self.free_trader_slots);
+        if self.seats.is_empty() {
+            println!("No pending seat requests");
+            return;
+        }
+        println!("Pending seat requests: {}", self.seats.len());
+        println!("{0: <44} | {1: <44}", "Trader", "Seat");
+        for seat in &self.seats {
+            println!("{0: <44} | {1: <44}", seat.trader, seat.seat);
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec!["trader", "seat"]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        self.seats
+            .iter()
+            .map(|seat| vec![seat.trader.clone(), seat.seat.clone()])
+            .collect()
+    }
+}
+
+/// Lists the seats requested on a market that the market authority has not approved yet
+pub async fn process_list_pending_seats(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+) -> anyhow::Result<PendingSeats> {
+    let state = get_market_state(sdk, market_pubkey).await?;
+    let accounts = get_seats_for_market_with_status(
+        &sdk.client,
+        market_pubkey,
+        SeatApprovalStatus::NotApproved,
+    )
+    .await?;
+
+    let mut seats = accounts
+        .iter()
+        .filter_map(|(seat_address, account)| {
+            let seat: Seat =
+                bytemuck::try_pod_read_unaligned(account.data.get(..size_of::<Seat>())?).ok()?;
+            Some(PendingSeat {
+                seat: seat_address.to_string(),
+                trader: seat.trader.to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+    seats.sort_by(|a, b| a.trader.cmp(&b.trader));
+
+    Ok(PendingSeats {
+        market: market_pubkey.to_string(),
+        free_trader_slots: state
+            .header
+            .market_size_params
+            .num_seats
+            .saturating_sub(state.registered_traders.len() as u64),
+        seats,
+    })
+}
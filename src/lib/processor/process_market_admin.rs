@@ -0,0 +1,314 @@
+use std::collections::BTreeMap;
+
+use crate::helpers::market_helpers::load_market;
+use crate::helpers::output_helpers::CommandOutput;
+use crate::helpers::transaction_helpers::{
+    get_signing_authority, get_transaction_options, send_instructions, TransactionOutcome,
+};
+use phoenix::program::instruction_builders::{
+    create_change_fee_recipient_instruction,
+    create_change_fee_recipient_with_unclaimed_fees_instruction,
+    create_change_market_status_instruction, create_claim_authority_instruction,
+    create_name_successor_instruction,
+};
+use phoenix::program::status::MarketStatus;
+use phoenix::program::MarketHeader;
+use phoenix::quantities::WrapperU64;
+use phoenix::state::{Side, TraderState};
+use phoenix_sdk::sdk_client::SDKClient;
+use phoenix_seat_manager::get_seat_manager_address;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+
+const MARKET_STATUSES: [MarketStatus; 5] = [
+    MarketStatus::Active,
+    MarketStatus::PostOnly,
+    MarketStatus::Paused,
+    MarketStatus::Closed,
+    MarketStatus::Tombstoned,
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketAdminResult {
+    pub market: String,
+    /// Market setting that was changed, such as `status` or `fee_recipient`
+    pub field: String,
+    pub previous: String,
+    pub new: String,
+    pub signature: String,
+}
+
+impl CommandOutput for MarketAdminResult {
+    fn print_text(&self) {
+        println!(
+            "Changed {} of market {} from {} to {}",
+            self.field.replace('_', " "),
+            self.market,
+            self.previous,
+            self.new
+        );
+        println!("Signature: {}", self.signature);
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec!["market", "field", "previous", "new", "signature"]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.market.clone(),
+            self.field.clone(),
+            self.previous.clone(),
+            self.new.clone(),
+            self.signature.clone(),
+        ]]
+    }
+}
+
+pub struct MarketState {
+    pub header: MarketHeader,
+    pub uncollected_fees_in_quote_lots: u64,
+    pub open_orders: usize,
+    pub registered_traders: BTreeMap<Pubkey, TraderState>,
+}
+
+pub async fn get_market_state(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+) -> anyhow::Result<MarketState> {
+    let market_account_data = sdk.client.get_account_data(market_pubkey).await?;
+    let (header, market) = load_market(&market_account_data)
+        .map_err(|e| anyhow::anyhow!("{} is not a Phoenix market account: {}", market_pubkey, e))?;
+
+    Ok(MarketState {
+        header: *header,
+        uncollected_fees_in_quote_lots: market.get_uncollected_fee_amount().as_u64(),
+        open_orders: market.get_book(Side::Bid).len() + market.get_book(Side::Ask).len(),
+        registered_traders: market
+            .get_registered_traders()
+            .iter()
+            .map(|(trader, state)| (*trader, *state))
+            .collect(),
+    })
+}
+
+// The program rejects these instructions from anyone but the market authority, so check before paying for the transaction.
+// An exported transaction signed once the authority has changed can skip the check with --skip-authority-check
+pub fn assert_market_authority(
+    header: &MarketHeader,
+    market_pubkey: &Pubkey,
+    signer: &Pubkey,
+) -> anyhow::Result<()> {
+    if header.authority == *signer || get_transaction_options().skip_authority_check {
+        return Ok(());
+    }
+    let seat_manager_hint = if header.authority == get_seat_manager_address(market_pubkey).0 {
+        ". The market is governed by the seat manager program, so its authority acts through the seat manager"
+    } else {
+        ""
+    };
+    Err(anyhow::anyhow!(
+        "{} is not the authority of market {}, the authority is {}{}",
+        signer,
+        market_pubkey,
+        header.authority,
+        seat_manager_hint
+    ))
+}
+
+/// Changes the status of a market, after checking the transition is allowed from its current status.
+/// Tombstoning a market removes it, so the book, traders and uncollected fees must be empty first
+pub async fn process_change_market_status(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+    status: MarketStatus,
+) -> anyhow::Result<TransactionOutcome<MarketAdminResult>> {
+    let authority = get_signing_authority(&sdk.client);
+    let state = get_market_state(sdk, market_pubkey).await?;
+    assert_market_authority(&state.header, market_pubkey, &authority)?;
+
+    let current_status = MarketStatus::from(state.header.status);
+    if !current_status.valid_state_transition(&status) {
+        let allowed = MARKET_STATUSES
+            .iter()
+            .filter(|next| current_status.valid_state_transition(next))
+            .map(|next| next.to_string())
+            .collect::<Vec<_>>();
+        return Err(anyhow::anyhow!(
+            "Cannot change the market status from {} to {}. From {}, the status can change to: {}",
+            current_status,
+            status,
+            current_status,
+            allowed.join(", ")
+        ));
+    }
+    if status == MarketStatus::Tombstoned {
+        if state.open_orders > 0 {
+            return Err(anyhow::anyhow!(
+                "Cannot tombstone a market with open orders, found {}",
+                state.open_orders
+            ));
+        }
+        if !state.registered_traders.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Cannot tombstone a market with traders, found {}. Evict them first",
+                state.registered_traders.len()
+            ));
+        }
+        if state.uncollected_fees_in_quote_lots > 0 {
+            return Err(anyhow::anyhow!(
+                "Cannot tombstone a market with uncollected fees, found {} quote lots",
+                state.uncollected_fees_in_quote_lots
+            ));
+        }
+    }
+
+    let ix = create_change_market_status_instruction(&authority, market_pubkey, status);
+    let signature = match send_instructions(sdk, vec![ix], vec![]).await? {
+        TransactionOutcome::Sent(signature) => signature,
+        TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+    };
+    Ok(TransactionOutcome::Sent(MarketAdminResult {
+        market: market_pubkey.to_string(),
+        field: "status".to_string(),
+        previous: current_status.to_string(),
+        new: status.to_string(),
+        signature: signature.to_string(),
+    }))
+}
+
+/// Changes the account that collected fees are sent to. While the market has uncollected fees, the
+/// current fee recipient must also sign
+pub async fn process_change_fee_recipient(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+    new_fee_recipient: &Pubkey,
+    current_fee_recipient: Option<&Keypair>,
+) -> anyhow::Result<TransactionOutcome<MarketAdminResult>> {
+    let authority = get_signing_authority(&sdk.client);
+    let state = get_market_state(sdk, market_pubkey).await?;
+    assert_market_authority(&state.header, market_pubkey, &authority)?;
+    let previous_fee_recipient = state.header.fee_recipient;
+    if previous_fee_recipient == *new_fee_recipient {
+        return Err(anyhow::anyhow!(
+            "{} is already the fee recipient of market {}",
+            new_fee_recipient,
+            market_pubkey
+        ));
+    }
+    if let Some(keypair) = current_fee_recipient {
+        if keypair.pubkey() != previous_fee_recipient {
+            return Err(anyhow::anyhow!(
+                "The given fee recipient keypair is for {}, but the fee recipient of market {} is {}",
+                keypair.pubkey(),
+                market_pubkey,
+                previous_fee_recipient
+            ));
+        }
+    }
+
+    let ix = if state.uncollected_fees_in_quote_lots > 0 {
+        if current_fee_recipient.is_none()
+            && previous_fee_recipient != authority
+            && !get_transaction_options().is_offline()
+        {
+            return Err(anyhow::anyhow!(
+                "Market {} has {} quote lots of uncollected fees, so the current fee recipient {} must sign. \
+                Pass its keypair with --fee-recipient-keypair, or collect the fees first",
+                market_pubkey,
+                state.uncollected_fees_in_quote_lots,
+                previous_fee_recipient
+            ));
+        }
+        create_change_fee_recipient_with_unclaimed_fees_instruction(
+            &authority,
+            market_pubkey,
+            new_fee_recipient,
+            &previous_fee_recipient,
+        )
+    } else {
+        create_change_fee_recipient_instruction(&authority, market_pubkey, new_fee_recipient)
+    };
+    let signature = match send_instructions(
+        sdk,
+        vec![ix],
+        current_fee_recipient.into_iter().collect(),
+    )
+    .await?
+    {
+        TransactionOutcome::Sent(signature) => signature,
+        TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+    };
+    Ok(TransactionOutcome::Sent(MarketAdminResult {
+        market: market_pubkey.to_string(),
+        field: "fee_recipient".to_string(),
+        previous: previous_fee_recipient.to_string(),
+        new: new_fee_recipient.to_string(),
+        signature: signature.to_string(),
+    }))
+}
+
+/// Names the account that can claim the market authority with claim-authority
+pub async fn process_name_successor(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+    successor: &Pubkey,
+) -> anyhow::Result<TransactionOutcome<MarketAdminResult>> {
+    let authority = get_signing_authority(&sdk.client);
+    let state = get_market_state(sdk, market_pubkey).await?;
+    assert_market_authority(&state.header, market_pubkey, &authority)?;
+
+    let ix = create_name_successor_instruction(&authority, market_pubkey, successor);
+    let signature = match send_instructions(sdk, vec![ix], vec![]).await? {
+        TransactionOutcome::Sent(signature) => signature,
+        TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+    };
+    Ok(TransactionOutcome::Sent(MarketAdminResult {
+        market: market_pubkey.to_string(),
+        field: "successor".to_string(),
+        previous: state.header.successor.to_string(),
+        new: successor.to_string(),
+        signature: signature.to_string(),
+    }))
+}
+
+/// Takes over the market authority. Unlike the other admin commands, this is signed by the successor
+/// named by the current authority, which --authority names when exporting
+pub async fn process_claim_authority(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+) -> anyhow::Result<TransactionOutcome<MarketAdminResult>> {
+    let successor = get_signing_authority(&sdk.client);
+    let state = get_market_state(sdk, market_pubkey).await?;
+    let skip_authority_check = get_transaction_options().skip_authority_check;
+    if state.header.authority == successor && !skip_authority_check {
+        return Err(anyhow::anyhow!(
+            "{} is already the authority of market {}",
+            successor,
+            market_pubkey
+        ));
+    }
+    if state.header.successor != successor && !skip_authority_check {
+        return Err(anyhow::anyhow!(
+            "{} is not the successor of market {}, the successor is {}. The authority names one with `admin name-successor`",
+            successor,
+            market_pubkey,
+            state.header.successor
+        ));
+    }
+
+    let ix = create_claim_authority_instruction(&successor, market_pubkey);
+    let signature = match send_instructions(sdk, vec![ix], vec![]).await? {
+        TransactionOutcome::Sent(signature) => signature,
+        TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+    };
+    Ok(TransactionOutcome::Sent(MarketAdminResult {
+        market: market_pubkey.to_string(),
+        field: "authority".to_string(),
+        previous: state.header.authority.to_string(),
+        new: successor.to_string(),
+        signature: signature.to_string(),
+    }))
+}
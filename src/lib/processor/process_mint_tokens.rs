@@ -1,21 +1,65 @@
 use crate::helpers::devnet_helpers::*;
-use ellipsis_client::EllipsisClient;
+use crate::helpers::output_helpers::CommandOutput;
+use crate::helpers::transaction_helpers::{send_instructions, TransactionOutcome};
+use phoenix_sdk::sdk_client::SDKClient;
+use serde::Serialize;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Keypair;
 use solana_sdk::signature::Signer;
 
+#[derive(Debug, Clone, Serialize)]
+pub struct MintTokensResult {
+    pub amount: u64,
+    pub mint: String,
+    pub recipient: String,
+    pub created_token_account: bool,
+    pub signature: String,
+}
+
+impl CommandOutput for MintTokensResult {
+    fn print_text(&self) {
+        if self.created_token_account {
+            println!("Creating ATA");
+        }
+        println!(
+            "{} Tokens minted! Mint pubkey: {},  Recipient address: {}",
+            self.amount, self.mint, self.recipient
+        );
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "amount",
+            "mint",
+            "recipient",
+            "created_token_account",
+            "signature",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.amount.to_string(),
+            self.mint.clone(),
+            self.recipient.clone(),
+            self.created_token_account.to_string(),
+            self.signature.clone(),
+        ]]
+    }
+}
+
 // Only valid for sandbox devnet markets
 pub async fn process_mint_tokens(
-    client: &EllipsisClient,
+    sdk: &SDKClient,
     payer: &Keypair,
     recipient_pubkey: &Pubkey,
     mint_ticker: String,
     amount: u64,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<TransactionOutcome<MintTokensResult>> {
     let mut instructions = vec![];
 
     let mint_pda = find_or_create_devnet_mint(
-        client,
+        sdk,
         &mint_ticker,
         9, //Decimals only used in creating mint. No effect if mint already exists
     )
@@ -25,8 +69,8 @@ pub async fn process_mint_tokens(
     let recipient_ata =
         spl_associated_token_account::get_associated_token_address(recipient_pubkey, &mint_pda);
 
-    if client.get_account(&recipient_ata).await.is_err() {
-        println!("Creating ATA");
+    let created_token_account = sdk.client.get_account(&recipient_ata).await.is_err();
+    if created_token_account {
         instructions.push(
             spl_associated_token_account::instruction::create_associated_token_account(
                 &payer.pubkey(),
@@ -45,14 +89,16 @@ pub async fn process_mint_tokens(
         amount,
     ));
 
-    client
-        .sign_send_instructions(instructions, vec![payer])
-        .await?;
-
-    println!(
-        "{} Tokens minted! Mint pubkey: {},  Recipient address: {}",
-        amount, mint_pda, recipient_pubkey
-    );
+    let signature = match send_instructions(sdk, instructions, vec![payer]).await? {
+        TransactionOutcome::Sent(signature) => signature,
+        TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+    };
 
-    Ok(())
+    Ok(TransactionOutcome::Sent(MintTokensResult {
+        amount,
+        mint: mint_pda.to_string(),
+        recipient: recipient_pubkey.to_string(),
+        created_token_account,
+        signature: signature.to_string(),
+    }))
 }
@@ -1,10 +1,57 @@
+use crate::helpers::transaction_helpers::{send_instructions, TransactionOutcome};
 use phoenix_sdk::sdk_client::*;
+use serde::Serialize;
 use solana_sdk::program_pack::Pack;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signer;
 use spl_token::state::Mint;
 
 use crate::helpers::devnet_helpers::devnet_token_faucet;
+use crate::helpers::output_helpers::CommandOutput;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MintTokensForMarketResult {
+    pub recipient: String,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub created_base_token_account: bool,
+    pub created_quote_token_account: bool,
+    pub signature: String,
+}
+
+impl CommandOutput for MintTokensForMarketResult {
+    fn print_text(&self) {
+        if self.created_base_token_account {
+            println!("Creating ATA for base token");
+        }
+        if self.created_quote_token_account {
+            println!("Creating ATA for quote token");
+        }
+        println!("Tokens minted! Signature: {}", self.signature);
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "recipient",
+            "base_amount",
+            "quote_amount",
+            "created_base_token_account",
+            "created_quote_token_account",
+            "signature",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.recipient.clone(),
+            self.base_amount.to_string(),
+            self.quote_amount.to_string(),
+            self.created_base_token_account.to_string(),
+            self.created_quote_token_account.to_string(),
+            self.signature.clone(),
+        ]]
+    }
+}
 
 // Only valid for sandbox devnet markets
 pub async fn process_mint_tokens_for_market(
@@ -13,7 +60,7 @@ pub async fn process_mint_tokens_for_market(
     recipient_pubkey: &Pubkey,
     base_amount: u64,
     quote_amount: u64,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<TransactionOutcome<MintTokensForMarketResult>> {
     // Get base and quote mints from market metadata
     let market_metadata = sdk.get_market_metadata(market_pubkey).await?;
     let base_mint = market_metadata.base_mint;
@@ -47,8 +94,8 @@ pub async fn process_mint_tokens_for_market(
     let recipient_ata_base =
         spl_associated_token_account::get_associated_token_address(recipient_pubkey, &base_mint);
 
-    if sdk.client.get_account(&recipient_ata_base).await.is_err() {
-        println!("Creating ATA for base token");
+    let created_base_token_account = sdk.client.get_account(&recipient_ata_base).await.is_err();
+    if created_base_token_account {
         instructions.push(
             spl_associated_token_account::instruction::create_associated_token_account(
                 &sdk.client.payer.pubkey(),
@@ -62,8 +109,8 @@ pub async fn process_mint_tokens_for_market(
     let recipient_ata_quote =
         spl_associated_token_account::get_associated_token_address(recipient_pubkey, &quote_mint);
 
-    if sdk.client.get_account(&recipient_ata_quote).await.is_err() {
-        println!("Creating ATA for quote token");
+    let created_quote_token_account = sdk.client.get_account(&recipient_ata_quote).await.is_err();
+    if created_quote_token_account {
         instructions.push(
             spl_associated_token_account::instruction::create_associated_token_account(
                 &sdk.client.payer.pubkey(),
@@ -89,11 +136,17 @@ pub async fn process_mint_tokens_for_market(
         recipient_pubkey,
         quote_amount,
     ));
-    let signature = sdk
-        .client
-        .sign_send_instructions(instructions, vec![])
-        .await?;
-    println!("Tokens minted! Signature: {}", signature);
+    let signature = match send_instructions(sdk, instructions, vec![]).await? {
+        TransactionOutcome::Sent(signature) => signature,
+        TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+    };
 
-    Ok(())
+    Ok(TransactionOutcome::Sent(MintTokensForMarketResult {
+        recipient: recipient_pubkey.to_string(),
+        base_amount,
+        quote_amount,
+        created_base_token_account,
+        created_quote_token_account,
+        signature: signature.to_string(),
+    }))
 }
@@ -0,0 +1,218 @@
+use crate::helpers::market_helpers::{get_clock, get_token_account_setup_instructions};
+use crate::helpers::output_helpers::CommandOutput;
+use crate::helpers::print_helpers::{get_market_event_records, MarketEventRecord};
+use crate::helpers::transaction_helpers::{send_instructions, TransactionOutcome};
+use phoenix::program::create_new_order_instruction;
+use phoenix::quantities::{BaseLots, QuoteLots, Ticks, WrapperU64};
+use phoenix::state::{OrderPacket, SelfTradeBehavior, Side};
+use phoenix_sdk::sdk_client::*;
+use serde::Serialize;
+use solana_sdk::clock::Clock;
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderType {
+    /// Crosses the spread if possible and rests the remainder on the book
+    Limit,
+    /// Rests on the book, or is rejected if it would cross the spread
+    PostOnly,
+    /// Matches against the book and cancels whatever is left unfilled
+    ImmediateOrCancel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderExpiry {
+    /// Valid for this many slots after the current slot
+    InSlots(u64),
+    /// Valid for this many seconds after the current on-chain time
+    InSeconds(u64),
+    /// Valid until this unix timestamp, in seconds
+    At(u64),
+}
+
+impl OrderExpiry {
+    /// Converts the expiry into the absolute `last_valid_slot` and `last_valid_unix_timestamp_in_seconds`
+    /// of an order packet, relative to the on-chain clock
+    pub fn to_last_valid(&self, clock: &Clock) -> anyhow::Result<(Option<u64>, Option<u64>)> {
+        let now = clock.unix_timestamp as u64;
+        match *self {
+            OrderExpiry::InSlots(0) | OrderExpiry::InSeconds(0) => {
+                Err(anyhow::anyhow!("Order expiry must be in the future"))
+            }
+            OrderExpiry::InSlots(slots) => Ok((Some(clock.slot + slots), None)),
+            OrderExpiry::InSeconds(seconds) => Ok((None, Some(now + seconds))),
+            OrderExpiry::At(timestamp) if timestamp <= now => Err(anyhow::anyhow!(
+                "Expiry time {} is not after the current on-chain time {}",
+                timestamp,
+                now
+            )),
+            OrderExpiry::At(timestamp) => Ok((None, Some(timestamp))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaceOrderResult {
+    pub order_type: String,
+    pub side: String,
+    pub size: String,
+    pub price: f64,
+    /// Last slot at which the order is valid, or `None` if it has no slot expiry
+    pub last_valid_slot: Option<u64>,
+    /// Last unix timestamp at which the order is valid, or `None` if it has no timestamp expiry
+    pub last_valid_unix_timestamp: Option<u64>,
+    pub signature: String,
+    pub events: Vec<MarketEventRecord>,
+}
+
+impl CommandOutput for PlaceOrderResult {
+    fn print_text(&self) {
+        println!(
+            "Placing {} {} order for {} at price {}",
+            self.order_type, self.side, self.size, self.price
+        );
+        if let Some(slot) = self.last_valid_slot {
+            println!("Order is valid until slot {}", slot);
+        }
+        if let Some(timestamp) = self.last_valid_unix_timestamp {
+            println!("Order is valid until unix timestamp {}", timestamp);
+        }
+        println!("Place order transaction: {}", self.signature);
+        self.events.iter().for_each(|event| event.print_text());
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        MarketEventRecord::CSV_HEADER.to_vec()
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        self.events.iter().map(|event| event.csv_record()).collect()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn process_place_limit_order(
+    market_pubkey: &Pubkey,
+    sdk: &mut SDKClient,
+    side: Side,
+    price: f64,
+    size: f64,
+    client_order_id: u128,
+    order_type: OrderType,
+    self_trade_behavior: SelfTradeBehavior,
+    expiry: Option<OrderExpiry>,
+) -> anyhow::Result<TransactionOutcome<PlaceOrderResult>> {
+    let meta = *sdk.get_market_metadata_from_cache(market_pubkey)?;
+
+    // Round the price away from the spread so the order is never more aggressive than requested
+    let price_in_ticks = match side {
+        Side::Bid => sdk.float_price_to_ticks_rounded_down(market_pubkey, price)?,
+        Side::Ask => sdk.float_price_to_ticks_rounded_up(market_pubkey, price)?,
+    };
+    let num_base_lots = sdk.raw_base_units_to_base_lots_rounded_down(market_pubkey, size)?;
+
+    if price_in_ticks == 0 {
+        return Err(anyhow::anyhow!(
+            "Price {} is below the minimum tick size of the market",
+            price
+        ));
+    }
+    if num_base_lots == 0 {
+        return Err(anyhow::anyhow!(
+            "Size {} is below the minimum base lot size of the market",
+            size
+        ));
+    }
+
+    // Expiry is relative to the on-chain clock rather than the local one
+    let (last_valid_slot, last_valid_unix_timestamp_in_seconds) = match expiry {
+        Some(expiry) => expiry.to_last_valid(&get_clock(&sdk.client).await?)?,
+        None => (None, None),
+    };
+
+    let order_packet = match order_type {
+        OrderType::Limit => OrderPacket::Limit {
+            side,
+            price_in_ticks: Ticks::new(price_in_ticks),
+            num_base_lots: BaseLots::new(num_base_lots),
+            self_trade_behavior,
+            match_limit: None,
+            client_order_id,
+            use_only_deposited_funds: false,
+            last_valid_slot,
+            last_valid_unix_timestamp_in_seconds,
+            fail_silently_on_insufficient_funds: false,
+        },
+        OrderType::PostOnly => OrderPacket::PostOnly {
+            side,
+            price_in_ticks: Ticks::new(price_in_ticks),
+            num_base_lots: BaseLots::new(num_base_lots),
+            client_order_id,
+            reject_post_only: true,
+            use_only_deposited_funds: false,
+            last_valid_slot,
+            last_valid_unix_timestamp_in_seconds,
+            fail_silently_on_insufficient_funds: false,
+        },
+        OrderType::ImmediateOrCancel => OrderPacket::ImmediateOrCancel {
+            side,
+            price_in_ticks: Some(Ticks::new(price_in_ticks)),
+            num_base_lots: BaseLots::new(num_base_lots),
+            num_quote_lots: QuoteLots::new(0),
+            min_base_lots_to_fill: BaseLots::new(0),
+            min_quote_lots_to_fill: QuoteLots::new(0),
+            self_trade_behavior,
+            match_limit: None,
+            client_order_id,
+            use_only_deposited_funds: false,
+            last_valid_slot,
+            last_valid_unix_timestamp_in_seconds,
+        },
+    };
+
+    // Resting orders require a seat, while takers only need token accounts
+    let trader = sdk.get_trader();
+    let mut instructions = match order_type {
+        OrderType::Limit | OrderType::PostOnly => {
+            sdk.get_maker_setup_instructions_for_market(market_pubkey)
+                .await?
+        }
+        OrderType::ImmediateOrCancel => {
+            get_token_account_setup_instructions(sdk, market_pubkey).await?
+        }
+    };
+    instructions.push(create_new_order_instruction(
+        market_pubkey,
+        &trader,
+        &meta.base_mint,
+        &meta.quote_mint,
+        &order_packet,
+    ));
+
+    let size = get_decimal_string(
+        sdk.base_lots_to_base_atoms(market_pubkey, num_base_lots)?,
+        meta.base_decimals,
+    );
+    let price = sdk.ticks_to_float_price(market_pubkey, price_in_ticks)?;
+
+    let signature = match send_instructions(sdk, instructions, vec![]).await? {
+        TransactionOutcome::Sent(signature) => signature,
+        TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+    };
+
+    let events = sdk
+        .parse_events_from_transaction(&signature)
+        .await
+        .ok_or_else(|| anyhow::anyhow!("Failed to parse events from transaction"))?;
+
+    Ok(TransactionOutcome::Sent(PlaceOrderResult {
+        order_type: format!("{:?}", order_type),
+        side: format!("{:?}", side),
+        size,
+        price,
+        last_valid_slot,
+        last_valid_unix_timestamp: last_valid_unix_timestamp_in_seconds,
+        signature: signature.to_string(),
+        events: get_market_event_records(sdk, events).await?,
+    }))
+}
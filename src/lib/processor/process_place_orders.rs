@@ -0,0 +1,557 @@
+use crate::helpers::output_helpers::{optional_field, CommandOutput};
+use crate::helpers::transaction_helpers::{
+    check_nonce_batches, pack_instructions, send_instructions, TransactionOutcome,
+    UnsentTransaction,
+};
+use clap::ValueEnum;
+use phoenix::program::create_new_order_instruction;
+use phoenix::quantities::{BaseLots, Ticks, WrapperU64};
+use phoenix::state::{OrderPacket, SelfTradeBehavior, Side};
+use phoenix_sdk::sdk_client::*;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::Signer;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// Prices and sizes within this many ticks or lots of a whole number are treated as whole, to absorb float error
+const UNIT_TOLERANCE: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OrderFileFormat {
+    Json,
+    Toml,
+    Csv,
+}
+
+/// One order of an order file
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderSpec {
+    /// bid (or buy) or ask (or sell)
+    pub side: String,
+    /// Price in quote units per base unit
+    pub price: f64,
+    /// Size in base units
+    pub size: f64,
+    pub client_order_id: Option<u64>,
+    /// Last slot at which the order can be filled
+    pub last_valid_slot: Option<u64>,
+    /// Last unix timestamp, in seconds, at which the order can be filled
+    pub last_valid_unix_timestamp: Option<u64>,
+}
+
+/// Order file with the orders listed under an `orders` key
+#[derive(Deserialize)]
+struct OrderFile {
+    orders: Vec<OrderSpec>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderOutcome {
+    /// Position of the order in the file, starting at 1
+    pub index: usize,
+    pub side: String,
+    pub price: f64,
+    pub size: f64,
+    pub client_order_id: u64,
+    /// One of invalid, valid (dry run), placed, filled, failed, simulated or exported
+    pub status: String,
+    /// Transaction the order was packed into, starting at 1
+    pub transaction_index: Option<usize>,
+    pub signature: Option<String>,
+    /// ID of the resting order, as printed by get-open-orders
+    pub order_id: Option<i64>,
+    /// Size filled on placement, in base units
+    pub filled_size: Option<String>,
+    /// Why the order is invalid or failed
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaceOrdersResult {
+    pub market: String,
+    pub dry_run: bool,
+    pub num_transactions: usize,
+    pub orders: Vec<OrderOutcome>,
+    /// Transactions that were not sent, in the order of their transaction index
+    pub unsent_transactions: Vec<UnsentTransaction>,
+}
+
+impl CommandOutput for PlaceOrdersResult {
+    fn print_text(&self) {
+        println!(
+            "{0: <5} | {1: <4} | {2: <12} | {3: <12} | {4: <10} | {5: <8} | {6: <3} | {7: <22} | {8: <12} | Details",
+            "#", "Side", "Price", "Size", "Client ID", "Status", "Tx", "Order ID", "Filled"
+        );
+        for order in &self.orders {
+            println!(
+                "{0: <5} | {1: <4} | {2: <12} | {3: <12} | {4: <10} | {5: <8} | {6: <3} | {7: <22} | {8: <12} | {9}",
+                order.index,
+                order.side,
+                order.price,
+                order.size,
+                order.client_order_id,
+                order.status,
+                optional_field(&order.transaction_index),
+                optional_field(&order.order_id),
+                optional_field(&order.filled_size),
+                optional_field(&order.message),
+            );
+        }
+        println!();
+        let count = |status: &str| self.orders.iter().filter(|o| o.status == status).count();
+        if self.dry_run {
+            println!(
+                "Dry run: {} valid and {} invalid order(s), packed into {} transaction(s)",
+                count("valid"),
+                count("invalid"),
+                self.num_transactions
+            );
+            return;
+        }
+        if let Some(unsent) = self.unsent_transactions.first() {
+            let status = unsent_status(unsent);
+            println!(
+                "{} {} and {} invalid order(s) in {} transaction(s)",
+                count(status),
+                status,
+                count("invalid"),
+                self.num_transactions
+            );
+            for (i, unsent) in self.unsent_transactions.iter().enumerate() {
+                println!();
+                println!("Transaction {}:", i + 1);
+                unsent.print_text();
+            }
+            return;
+        }
+        println!(
+            "{} placed, {} filled, {} failed and {} invalid order(s) in {} transaction(s)",
+            count("placed"),
+            count("filled"),
+            count("failed"),
+            count("invalid"),
+            self.num_transactions
+        );
+        let mut signatures = self
+            .orders
+            .iter()
+            .filter_map(|order| order.transaction_index.zip(order.signature.as_ref()))
+            .collect::<Vec<_>>();
+        signatures.dedup();
+        for (transaction_index, signature) in signatures {
+            println!("Transaction {}: {}", transaction_index, signature);
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "index",
+            "side",
+            "price",
+            "size",
+            "client_order_id",
+            "status",
+            "transaction_index",
+            "signature",
+            "order_id",
+            "filled_size",
+            "message",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        self.orders
+            .iter()
+            .map(|order| {
+                vec![
+                    order.index.to_string(),
+                    order.side.clone(),
+                    order.price.to_string(),
+                    order.size.to_string(),
+                    order.client_order_id.to_string(),
+                    order.status.clone(),
+                    optional_field(&order.transaction_index),
+                    optional_field(&order.signature),
+                    optional_field(&order.order_id),
+                    optional_field(&order.filled_size),
+                    optional_field(&order.message),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Reads the orders of a JSON, TOML or CSV file. The format is taken from the file extension unless given
+pub fn read_order_file(
+    path: &Path,
+    format: Option<OrderFileFormat>,
+) -> anyhow::Result<Vec<OrderSpec>> {
+    let format = match format {
+        Some(format) => format,
+        None => match path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase())
+            .as_deref()
+        {
+            Some("json") => OrderFileFormat::Json,
+            Some("toml") => OrderFileFormat::Toml,
+            Some("csv") => OrderFileFormat::Csv,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Cannot tell the format of {} from its extension, use --format",
+                    path.display()
+                ))
+            }
+        },
+    };
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+
+    let orders = match format {
+        OrderFileFormat::Json => {
+            // Accept either a list of orders or an object with an orders list
+            let value = serde_json::from_str::<serde_json::Value>(&contents)
+                .map_err(|e| anyhow::anyhow!("Invalid order file {}: {}", path.display(), e))?;
+            let orders = if value.is_array() {
+                serde_json::from_value::<Vec<OrderSpec>>(value)
+            } else {
+                serde_json::from_value::<OrderFile>(value).map(|file| file.orders)
+            };
+            orders.map_err(|e| anyhow::anyhow!("Invalid order file {}: {}", path.display(), e))?
+        }
+        OrderFileFormat::Toml => {
+            toml::from_str::<OrderFile>(&contents)
+                .map_err(|e| anyhow::anyhow!("Invalid order file {}: {}", path.display(), e))?
+                .orders
+        }
+        OrderFileFormat::Csv => parse_order_csv(&contents)
+            .map_err(|e| anyhow::anyhow!("Invalid order file {}: {}", path.display(), e))?,
+    };
+    if orders.is_empty() {
+        return Err(anyhow::anyhow!("No orders in {}", path.display()));
+    }
+    Ok(orders)
+}
+
+/// Parses a CSV file with a header row. The side, price and size columns are required, and the
+/// client_order_id, last_valid_slot and last_valid_unix_timestamp columns can be left out or empty
+fn parse_order_csv(contents: &str) -> anyhow::Result<Vec<OrderSpec>> {
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, header) = lines
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Missing header row"))?;
+    let columns = header
+        .split(',')
+        .map(|column| column.trim().to_lowercase())
+        .collect::<Vec<_>>();
+    let column_index = |name: &str| columns.iter().position(|column| column == name);
+    let required_column =
+        |name: &str| column_index(name).ok_or_else(|| anyhow::anyhow!("Missing {} column", name));
+    let (side_index, price_index, size_index) = (
+        required_column("side")?,
+        required_column("price")?,
+        required_column("size")?,
+    );
+    let client_order_id_index = column_index("client_order_id");
+    let last_valid_slot_index = column_index("last_valid_slot");
+    let last_valid_unix_timestamp_index = column_index("last_valid_unix_timestamp");
+
+    let mut orders = vec![];
+    for (line_index, line) in lines {
+        let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+        let line_number = line_index + 1;
+        let field = |index: usize| fields.get(index).copied().unwrap_or_default();
+        let optional = |index: Option<usize>| -> anyhow::Result<Option<u64>> {
+            match index.map(field).filter(|value| !value.is_empty()) {
+                Some(value) => value.parse::<u64>().map(Some).map_err(|e| {
+                    anyhow::anyhow!("Line {}: invalid value {}: {}", line_number, value, e)
+                }),
+                None => Ok(None),
+            }
+        };
+        let number = |index: usize| -> anyhow::Result<f64> {
+            field(index).parse::<f64>().map_err(|e| {
+                anyhow::anyhow!(
+                    "Line {}: invalid number {}: {}",
+                    line_number,
+                    field(index),
+                    e
+                )
+            })
+        };
+        orders.push(OrderSpec {
+            side: field(side_index).to_string(),
+            price: number(price_index)?,
+            size: number(size_index)?,
+            client_order_id: optional(client_order_id_index)?,
+            last_valid_slot: optional(last_valid_slot_index)?,
+            last_valid_unix_timestamp: optional(last_valid_unix_timestamp_index)?,
+        });
+    }
+    Ok(orders)
+}
+
+/// Validates every order against the tick and lot sizes of the market, then places the valid ones in
+/// as few transactions as possible. Invalid orders are reported and skipped
+pub async fn process_place_orders(
+    market_pubkey: &Pubkey,
+    sdk: &SDKClient,
+    orders: Vec<OrderSpec>,
+    post_only: bool,
+    self_trade_behavior: SelfTradeBehavior,
+    dry_run: bool,
+) -> anyhow::Result<PlaceOrdersResult> {
+    let meta = *sdk.get_market_metadata_from_cache(market_pubkey)?;
+    let trader = sdk.get_trader();
+
+    let mut outcomes = vec![];
+    let mut order_instructions = vec![];
+    for (i, order) in orders.iter().enumerate() {
+        let client_order_id = order.client_order_id.unwrap_or_default();
+        let mut outcome = OrderOutcome {
+            index: i + 1,
+            side: order.side.to_lowercase(),
+            price: order.price,
+            size: order.size,
+            client_order_id,
+            status: "valid".to_string(),
+            transaction_index: None,
+            signature: None,
+            order_id: None,
+            filled_size: None,
+            message: None,
+        };
+        match validate_order(&meta, order) {
+            Ok((side, price_in_ticks, num_base_lots)) => {
+                let order_packet = get_order_packet(
+                    side,
+                    price_in_ticks,
+                    num_base_lots,
+                    client_order_id as u128,
+                    order,
+                    post_only,
+                    self_trade_behavior,
+                );
+                order_instructions.push((
+                    outcomes.len(),
+                    create_new_order_instruction(
+                        market_pubkey,
+                        &trader,
+                        &meta.base_mint,
+                        &meta.quote_mint,
+                        &order_packet,
+                    ),
+                ));
+            }
+            Err(e) => {
+                outcome.status = "invalid".to_string();
+                outcome.message = Some(e.to_string());
+            }
+        }
+        outcomes.push(outcome);
+    }
+
+    let setup_instructions = if order_instructions.is_empty() {
+        vec![]
+    } else {
+        sdk.get_maker_setup_instructions_for_market(market_pubkey)
+            .await?
+    };
+    let batches = pack_instructions(
+        setup_instructions,
+        order_instructions,
+        &sdk.client.payer.pubkey(),
+    );
+
+    if !dry_run {
+        check_nonce_batches(batches.len())?;
+    }
+
+    let mut unsent_transactions = vec![];
+    for (batch_index, (instructions, order_indices)) in batches.iter().enumerate() {
+        for &order_index in order_indices {
+            outcomes[order_index].transaction_index = Some(batch_index + 1);
+        }
+        if dry_run {
+            continue;
+        }
+
+        let signature = match send_instructions(sdk, instructions.clone(), vec![]).await {
+            Ok(TransactionOutcome::Sent(signature)) => signature,
+            Ok(TransactionOutcome::Unsent(unsent)) => {
+                for &order_index in order_indices {
+                    outcomes[order_index].status = unsent_status(&unsent).to_string();
+                    outcomes[order_index].message = unsent.simulation_error().map(str::to_string);
+                }
+                unsent_transactions.push(unsent);
+                continue;
+            }
+            Err(e) => {
+                for &order_index in order_indices {
+                    outcomes[order_index].status = "failed".to_string();
+                    outcomes[order_index].message = Some(e.to_string());
+                }
+                continue;
+            }
+        };
+
+        let events = sdk
+            .parse_events_from_transaction(&signature)
+            .await
+            .unwrap_or_default();
+        let order_results = get_order_results(&events, &trader);
+        // Every order instruction logs its events under its own sequence number, in instruction order
+        let results_match_orders = order_results.len() == order_indices.len();
+        for (i, &order_index) in order_indices.iter().enumerate() {
+            let outcome = &mut outcomes[order_index];
+            outcome.signature = Some(signature.to_string());
+            outcome.status = "placed".to_string();
+            if !results_match_orders {
+                continue;
+            }
+            let (order_id, base_lots_filled) = order_results[i];
+            outcome.order_id = order_id;
+            if base_lots_filled > 0 {
+                outcome.filled_size = Some(get_decimal_string(
+                    sdk.base_lots_to_base_atoms(market_pubkey, base_lots_filled)?,
+                    meta.base_decimals,
+                ));
+            }
+            if order_id.is_none() {
+                outcome.status = "filled".to_string();
+            }
+        }
+    }
+
+    Ok(PlaceOrdersResult {
+        market: market_pubkey.to_string(),
+        dry_run,
+        num_transactions: batches.len(),
+        orders: outcomes,
+        unsent_transactions,
+    })
+}
+
+/// Status of the orders in a transaction that was not sent
+fn unsent_status(unsent: &UnsentTransaction) -> &'static str {
+    match unsent {
+        UnsentTransaction::Simulated(_) => "simulated",
+        UnsentTransaction::Exported(_) => "exported",
+    }
+}
+
+/// Checks the side of the order and that its price and size are whole numbers of ticks and base lots
+fn validate_order(meta: &MarketMetadata, order: &OrderSpec) -> anyhow::Result<(Side, u64, u64)> {
+    let side = match order.side.to_lowercase().as_str() {
+        "bid" | "buy" => Side::Bid,
+        "ask" | "sell" => Side::Ask,
+        side => return Err(anyhow::anyhow!("Unknown side {}", side)),
+    };
+
+    let tick_size = meta.tick_size_in_quote_atoms_per_base_unit as f64
+        / meta.quote_atoms_per_quote_unit as f64
+        / meta.raw_base_units_per_base_unit as f64;
+    let price_in_ticks = to_whole_units(order.price / tick_size)
+        .ok_or_else(|| anyhow::anyhow!("Price is not a multiple of the tick size {}", tick_size))?;
+    if price_in_ticks == 0 {
+        return Err(anyhow::anyhow!("Price must be at least one tick"));
+    }
+
+    let lot_size =
+        meta.raw_base_units_per_base_unit as f64 / meta.num_base_lots_per_base_unit as f64;
+    let num_base_lots = to_whole_units(order.size / lot_size)
+        .ok_or_else(|| anyhow::anyhow!("Size is not a multiple of the lot size {}", lot_size))?;
+    if num_base_lots == 0 {
+        return Err(anyhow::anyhow!("Size must be at least one lot"));
+    }
+
+    Ok((side, price_in_ticks, num_base_lots))
+}
+
+/// Rounds a value that should be a whole number of ticks or lots, allowing for float error
+pub fn to_whole_units(units: f64) -> Option<u64> {
+    let rounded = units.round();
+    (units.is_finite() && rounded >= 0.0 && (units - rounded).abs() <= UNIT_TOLERANCE)
+        .then_some(rounded as u64)
+}
+
+fn get_order_packet(
+    side: Side,
+    price_in_ticks: u64,
+    num_base_lots: u64,
+    client_order_id: u128,
+    order: &OrderSpec,
+    post_only: bool,
+    self_trade_behavior: SelfTradeBehavior,
+) -> OrderPacket {
+    if post_only {
+        OrderPacket::PostOnly {
+            side,
+            price_in_ticks: Ticks::new(price_in_ticks),
+            num_base_lots: BaseLots::new(num_base_lots),
+            client_order_id,
+            reject_post_only: true,
+            use_only_deposited_funds: false,
+            last_valid_slot: order.last_valid_slot,
+            last_valid_unix_timestamp_in_seconds: order.last_valid_unix_timestamp,
+            fail_silently_on_insufficient_funds: false,
+        }
+    } else {
+        OrderPacket::Limit {
+            side,
+            price_in_ticks: Ticks::new(price_in_ticks),
+            num_base_lots: BaseLots::new(num_base_lots),
+            self_trade_behavior,
+            match_limit: None,
+            client_order_id,
+            use_only_deposited_funds: false,
+            last_valid_slot: order.last_valid_slot,
+            last_valid_unix_timestamp_in_seconds: order.last_valid_unix_timestamp,
+            fail_silently_on_insufficient_funds: false,
+        }
+    }
+}
+
+/// Returns the resting order ID, if any, and the base lots filled of every order placed in a transaction
+fn get_order_results(events: &[PhoenixEvent], trader: &Pubkey) -> Vec<(Option<i64>, u64)> {
+    let mut events_by_instruction: BTreeMap<u64, Vec<&PhoenixEvent>> = BTreeMap::new();
+    for event in events {
+        events_by_instruction
+            .entry(event.sequence_number)
+            .or_default()
+            .push(event);
+    }
+    events_by_instruction
+        .into_values()
+        .filter_map(|events| {
+            let order_id = events.iter().find_map(|event| match event.details {
+                MarketEventDetails::Place(place) if place.maker == *trader => {
+                    Some(place.order_sequence_number as i64)
+                }
+                _ => None,
+            });
+            let fill_summary = events.iter().find_map(|event| match event.details {
+                MarketEventDetails::FillSummary(summary) => Some(summary),
+                _ => None,
+            });
+            if order_id.is_none() && fill_summary.is_none() {
+                return None;
+            }
+            let base_lots_filled = events
+                .iter()
+                .map(|event| match event.details {
+                    MarketEventDetails::Fill(fill) if fill.taker == *trader => {
+                        fill.base_lots_filled
+                    }
+                    _ => 0,
+                })
+                .sum::<u64>();
+            Some((order_id, base_lots_filled))
+        })
+        .collect()
+}
@@ -0,0 +1,429 @@
+use crate::helpers::market_helpers::read_market_header;
+use crate::helpers::output_helpers::CommandOutput;
+use crate::helpers::registry_helpers::{
+    get_registry_cache_path, get_registry_layers, get_registry_path, read_user_registry,
+    refresh_registry_cache, write_user_registry, MarketConfig, MasterConfig, TokenConfig,
+    KNOWN_CLUSTERS, REGISTRY_CACHE_TTL,
+};
+use ellipsis_client::EllipsisClient;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryToken {
+    pub mint: String,
+    pub symbol: String,
+    pub name: String,
+    /// user or remote
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryMarket {
+    pub market: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub base_symbol: Option<String>,
+    pub quote_symbol: Option<String>,
+    pub aliases: Vec<String>,
+    /// user or remote
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryList {
+    pub genesis_hash: String,
+    pub cluster: Option<String>,
+    pub registry_path: String,
+    /// Age of the cached master config in seconds, if one was available
+    pub cache_age_secs: Option<u64>,
+    pub tokens: Vec<RegistryToken>,
+    pub markets: Vec<RegistryMarket>,
+}
+
+impl CommandOutput for RegistryList {
+    fn print_text(&self) {
+        println!(
+            "Registry for {} ({})",
+            self.cluster.as_deref().unwrap_or("custom cluster"),
+            self.genesis_hash
+        );
+        println!("User registry: {}", self.registry_path);
+        match self.cache_age_secs {
+            Some(age) => println!(
+                "Master config: cached {} ago{}",
+                format_age(age),
+                if age >= REGISTRY_CACHE_TTL.as_secs() {
+                    ", refresh failed"
+                } else {
+                    ""
+                }
+            ),
+            None if self.cluster.is_some() => println!("Master config: unavailable"),
+            None => {}
+        }
+        println!("Tokens ({}):", self.tokens.len());
+        for token in &self.tokens {
+            println!(
+                "  {:<10} {} {} [{}]",
+                token.symbol, token.mint, token.name, token.source
+            );
+        }
+        println!("Markets ({}):", self.markets.len());
+        for market in &self.markets {
+            println!(
+                "  {:<16} {} [{}]{}",
+                format_pair(market),
+                market.market,
+                market.source,
+                if market.aliases.is_empty() {
+                    String::new()
+                } else {
+                    format!(" alias {}", market.aliases.join(", "))
+                }
+            );
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "kind",
+            "address",
+            "symbol",
+            "name",
+            "base_mint",
+            "quote_mint",
+            "source",
+            "aliases",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        let tokens = self.tokens.iter().map(|token| {
+            vec![
+                "token".to_string(),
+                token.mint.clone(),
+                token.symbol.clone(),
+                token.name.clone(),
+                String::new(),
+                String::new(),
+                token.source.clone(),
+                String::new(),
+            ]
+        });
+        let markets = self.markets.iter().map(|market| {
+            vec![
+                "market".to_string(),
+                market.market.clone(),
+                format_pair(market),
+                String::new(),
+                market.base_mint.clone(),
+                market.quote_mint.clone(),
+                market.source.clone(),
+                market.aliases.join(" "),
+            ]
+        });
+        tokens.chain(markets).collect()
+    }
+}
+
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+fn format_pair(market: &RegistryMarket) -> String {
+    format!(
+        "{}/{}",
+        market.base_symbol.as_deref().unwrap_or("?"),
+        market.quote_symbol.as_deref().unwrap_or("?")
+    )
+}
+
+pub async fn process_registry_list(client: &EllipsisClient) -> anyhow::Result<RegistryList> {
+    let layers = get_registry_layers(client).await?;
+    let merged = layers.merged();
+    let source = |user: bool| if user { "user" } else { "remote" }.to_string();
+    let symbol = |mint: &str| {
+        merged
+            .tokens
+            .iter()
+            .find(|t| t.mint == mint)
+            .map(|t| t.symbol.clone())
+    };
+    Ok(RegistryList {
+        genesis_hash: layers.genesis_hash.clone(),
+        cluster: layers.cluster.map(str::to_string),
+        registry_path: get_registry_path().display().to_string(),
+        cache_age_secs: layers.remote_age.map(|age| age.as_secs()),
+        tokens: merged
+            .tokens
+            .iter()
+            .map(|token| RegistryToken {
+                mint: token.mint.clone(),
+                symbol: token.symbol.clone(),
+                name: token.name.clone(),
+                source: source(layers.user.tokens.iter().any(|t| t.mint == token.mint)),
+            })
+            .collect(),
+        markets: merged
+            .markets
+            .iter()
+            .map(|market| RegistryMarket {
+                market: market.market.clone(),
+                base_mint: market.base_mint.clone(),
+                quote_mint: market.quote_mint.clone(),
+                base_symbol: symbol(&market.base_mint),
+                quote_symbol: symbol(&market.quote_mint),
+                aliases: merged
+                    .aliases
+                    .iter()
+                    .filter(|(_, address)| **address == market.market)
+                    .map(|(alias, _)| alias.clone())
+                    .collect(),
+                source: source(
+                    layers
+                        .user
+                        .markets
+                        .iter()
+                        .any(|m| m.market == market.market),
+                ),
+            })
+            .collect(),
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryAddResult {
+    pub registry_path: String,
+    pub genesis_hash: String,
+    /// token or market
+    pub kind: String,
+    pub address: String,
+    pub symbol: Option<String>,
+    pub alias: Option<String>,
+    pub base_mint: Option<String>,
+    pub quote_mint: Option<String>,
+    /// Whether an existing user entry for the address was replaced
+    pub replaced: bool,
+}
+
+impl CommandOutput for RegistryAddResult {
+    fn print_text(&self) {
+        let action = if self.replaced { "Updated" } else { "Added" };
+        match &self.symbol {
+            Some(symbol) => println!("{} {} {} ({})", action, self.kind, self.address, symbol),
+            None => println!("{} {} {}", action, self.kind, self.address),
+        }
+        if let Some(alias) = &self.alias {
+            println!("Alias: {}", alias);
+        }
+        if let (Some(base_mint), Some(quote_mint)) = (&self.base_mint, &self.quote_mint) {
+            println!("Base mint: {}", base_mint);
+            println!("Quote mint: {}", quote_mint);
+        }
+        println!(
+            "Registry: {} (genesis hash {})",
+            self.registry_path, self.genesis_hash
+        );
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "kind",
+            "address",
+            "symbol",
+            "alias",
+            "base_mint",
+            "quote_mint",
+            "replaced",
+            "registry_path",
+            "genesis_hash",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.kind.clone(),
+            self.address.clone(),
+            self.symbol.clone().unwrap_or_default(),
+            self.alias.clone().unwrap_or_default(),
+            self.base_mint.clone().unwrap_or_default(),
+            self.quote_mint.clone().unwrap_or_default(),
+            self.replaced.to_string(),
+            self.registry_path.clone(),
+            self.genesis_hash.clone(),
+        ]]
+    }
+}
+
+/// Adds a token mint or a Phoenix market to the user registry under the genesis hash of the cluster.
+/// The kind of entry is read from the account owner. Tokens need a symbol, markets take their mints
+/// from the market header and can be given an alias
+pub async fn process_registry_add(
+    client: &EllipsisClient,
+    address: &Pubkey,
+    symbol: Option<String>,
+    name: Option<String>,
+    alias: Option<String>,
+) -> anyhow::Result<RegistryAddResult> {
+    if let Some(alias) = &alias {
+        if alias.contains('/') || alias.parse::<Pubkey>().is_ok() {
+            return Err(anyhow::anyhow!(
+                "Alias {} would be read as a pair or an address",
+                alias
+            ));
+        }
+    }
+    let genesis_hash = client.get_genesis_hash().await?.to_string();
+    let account = client
+        .get_account(address)
+        .await
+        .map_err(|_| anyhow::anyhow!("Account {} not found", address))?;
+    let registry_path = get_registry_path();
+    let mut registry = read_user_registry(&registry_path)?;
+    let config: &mut MasterConfig = registry.entry(genesis_hash.clone()).or_default();
+
+    let mut result = RegistryAddResult {
+        registry_path: registry_path.display().to_string(),
+        genesis_hash: genesis_hash.clone(),
+        kind: String::new(),
+        address: address.to_string(),
+        symbol: None,
+        alias: None,
+        base_mint: None,
+        quote_mint: None,
+        replaced: false,
+    };
+    if account.owner == spl_token::id() {
+        let symbol = symbol.ok_or_else(|| {
+            anyhow::anyhow!("{} is a token mint, so a --symbol is required", address)
+        })?;
+        if alias.is_some() {
+            return Err(anyhow::anyhow!(
+                "{} is a token mint. Aliases name markets",
+                address
+            ));
+        }
+        let token = TokenConfig {
+            name: name.unwrap_or_else(|| symbol.clone()),
+            symbol: symbol.clone(),
+            mint: address.to_string(),
+            logo_uri: String::new(),
+        };
+        result.replaced = config.tokens.iter().any(|t| t.mint == token.mint);
+        config.tokens.retain(|t| t.mint != token.mint);
+        config.tokens.push(token);
+        result.kind = "token".to_string();
+        result.symbol = Some(symbol);
+    } else if account.owner == phoenix::id() {
+        if symbol.is_some() || name.is_some() {
+            return Err(anyhow::anyhow!(
+                "{} is a market, whose symbols come from its mints. Add the mints with --symbol instead",
+                address
+            ));
+        }
+        let header = read_market_header(&account.data)
+            .map_err(|_| anyhow::anyhow!("{} is not a Phoenix market", address))?;
+        let market = MarketConfig {
+            market: address.to_string(),
+            base_mint: header.base_params.mint_key.to_string(),
+            quote_mint: header.quote_params.mint_key.to_string(),
+        };
+        result.replaced = config.markets.iter().any(|m| m.market == market.market);
+        result.base_mint = Some(market.base_mint.clone());
+        result.quote_mint = Some(market.quote_mint.clone());
+        config.markets.retain(|m| m.market != market.market);
+        config.markets.push(market);
+        if let Some(alias) = alias {
+            // Aliases match regardless of case, so one spelling replaces another
+            config
+                .aliases
+                .retain(|a, _| !a.eq_ignore_ascii_case(&alias));
+            config.aliases.insert(alias.clone(), address.to_string());
+            result.alias = Some(alias);
+        }
+        result.kind = "market".to_string();
+    } else {
+        return Err(anyhow::anyhow!(
+            "{} is neither a token mint nor a Phoenix market (owner {})",
+            address,
+            account.owner
+        ));
+    }
+
+    write_user_registry(&registry_path, &registry)?;
+    Ok(result)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryCluster {
+    pub cluster: String,
+    pub genesis_hash: Option<String>,
+    pub tokens: usize,
+    pub markets: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryRefreshResult {
+    pub cache_path: String,
+    pub clusters: Vec<RegistryCluster>,
+}
+
+impl CommandOutput for RegistryRefreshResult {
+    fn print_text(&self) {
+        println!("Refreshed the master config in {}", self.cache_path);
+        for cluster in &self.clusters {
+            println!(
+                "  {}: {} tokens, {} markets",
+                cluster.cluster, cluster.tokens, cluster.markets
+            );
+        }
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec!["cluster", "genesis_hash", "tokens", "markets"]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        self.clusters
+            .iter()
+            .map(|cluster| {
+                vec![
+                    cluster.cluster.clone(),
+                    cluster.genesis_hash.clone().unwrap_or_default(),
+                    cluster.tokens.to_string(),
+                    cluster.markets.to_string(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Downloads the master config again, regardless of the age of the cached copy
+pub async fn process_registry_refresh() -> anyhow::Result<RegistryRefreshResult> {
+    let clusters = refresh_registry_cache()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch the market registry: {}", e))?;
+    let mut clusters = clusters
+        .into_iter()
+        .map(|(cluster, config)| RegistryCluster {
+            genesis_hash: KNOWN_CLUSTERS
+                .iter()
+                .find(|(_, name)| *name == cluster)
+                .map(|(hash, _)| hash.to_string()),
+            cluster,
+            tokens: config.tokens.len(),
+            markets: config.markets.len(),
+        })
+        .collect::<Vec<_>>();
+    clusters.sort_by(|a, b| a.cluster.cmp(&b.cluster));
+    Ok(RegistryRefreshResult {
+        cache_path: get_registry_cache_path().display().to_string(),
+        clusters,
+    })
+}
@@ -1,15 +1,55 @@
+use crate::helpers::output_helpers::{optional_field, CommandOutput};
+use crate::helpers::transaction_helpers::{send_instructions, TransactionOutcome};
 use phoenix::program::instruction_builders::create_request_seat_instruction;
 use phoenix_sdk::sdk_client::*;
+use serde::Serialize;
 use solana_sdk::pubkey::Pubkey;
 
-pub async fn process_request_seat(market_pubkey: &Pubkey, sdk: &SDKClient) -> anyhow::Result<()> {
-    let ix = create_request_seat_instruction(&sdk.core.trader, market_pubkey);
-    let tx = sdk.client.sign_send_instructions(vec![ix], vec![]).await;
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestSeatResult {
+    pub signature: Option<String>,
+    pub error: Option<String>,
+}
 
-    match tx {
-        Ok(tx) => println!("Requested seat, transaction signature: {}", tx),
-        Err(e) => println!("Error requesting seat: {}", e),
+impl CommandOutput for RequestSeatResult {
+    fn print_text(&self) {
+        match (&self.signature, &self.error) {
+            (Some(signature), _) => {
+                println!("Requested seat, transaction signature: {}", signature)
+            }
+            (None, Some(error)) => println!("Error requesting seat: {}", error),
+            (None, None) => {}
+        }
     }
 
-    Ok(())
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec!["signature", "error"]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            optional_field(&self.signature),
+            optional_field(&self.error),
+        ]]
+    }
+}
+
+pub async fn process_request_seat(
+    market_pubkey: &Pubkey,
+    sdk: &SDKClient,
+) -> anyhow::Result<TransactionOutcome<RequestSeatResult>> {
+    let ix = create_request_seat_instruction(&sdk.core.trader, market_pubkey);
+    let tx = send_instructions(sdk, vec![ix], vec![]).await;
+
+    Ok(match tx {
+        Ok(TransactionOutcome::Sent(tx)) => TransactionOutcome::Sent(RequestSeatResult {
+            signature: Some(tx.to_string()),
+            error: None,
+        }),
+        Ok(TransactionOutcome::Unsent(unsent)) => TransactionOutcome::Unsent(unsent),
+        Err(e) => TransactionOutcome::Sent(RequestSeatResult {
+            signature: None,
+            error: Some(e.to_string()),
+        }),
+    })
 }
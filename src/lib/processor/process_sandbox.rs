@@ -0,0 +1,594 @@
+use std::fs;
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+
+use crate::helpers::devnet_helpers::{devnet_token_faucet, find_or_create_devnet_mint};
+use crate::helpers::output_helpers::CommandOutput;
+use crate::helpers::transaction_helpers::{
+    get_transaction_options, pack_instructions, send_instructions, TransactionOutcome,
+};
+use crate::processor::process_create_market::{
+    process_create_market, CreateMarketParams, CreateMarketResult,
+};
+use crate::processor::process_place_orders::{process_place_orders, to_whole_units, OrderSpec};
+use crate::processor::process_seat_manager_admin::process_claim_seats_authorized;
+use ellipsis_client::EllipsisClient;
+use phoenix::program::status::MarketStatus;
+use phoenix::program::{get_market_size, get_seat_address, MarketHeader, MarketSizeParams};
+use phoenix::state::SelfTradeBehavior;
+use phoenix_sdk::sdk_client::SDKClient;
+use phoenix_seat_manager::get_seat_manager_address;
+use phoenix_seat_manager::instruction_builders::create_change_market_status_instruction;
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::native_token::{lamports_to_sol, sol_to_lamports};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{write_keypair_file, Keypair};
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account;
+use spl_token::state::Mint;
+
+const MAINNET_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+
+// Seats the seat manager market reserves for its designated market makers, on top of one per order
+const SEAT_MANAGER_RESERVED_SEATS: u64 = 129;
+
+// Traders whose seats are claimed in one transaction
+const SEATS_PER_TRANSACTION: usize = 4;
+
+pub struct SandboxParams {
+    pub base_ticker: String,
+    pub quote_ticker: String,
+    /// Decimals of mints the faucet creates. Existing faucet mints keep their decimals
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    /// Tick size in quote units per base unit, as a decimal string
+    pub tick_size: String,
+    /// Base lot size in base units, as a decimal string
+    pub base_lot_size: String,
+    pub taker_fee_bps: u16,
+    pub num_traders: usize,
+    /// Directory the trader keypairs and the manifest are written to
+    pub out_dir: PathBuf,
+    pub sol_per_trader: f64,
+    /// Base units airdropped to each trader
+    pub base_amount: u64,
+    /// Quote units airdropped to each trader
+    pub quote_amount: u64,
+    /// Price the book is centered on, in quote units per base unit
+    pub mid_price: f64,
+    /// Number of price levels on each side of the book
+    pub levels: usize,
+    /// Distance between price levels, in quote units per base unit
+    pub level_spacing: f64,
+    /// Size of each order, in base units
+    pub order_size: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SandboxPrograms {
+    pub phoenix: String,
+    pub seat_manager: String,
+    pub token_faucet: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SandboxToken {
+    pub ticker: String,
+    pub mint: String,
+    pub decimals: u8,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SandboxTrader {
+    pub pubkey: String,
+    pub keypair_path: String,
+    pub seat: String,
+    pub base_token_account: String,
+    pub quote_token_account: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SandboxOrder {
+    pub trader: String,
+    pub side: String,
+    pub price: f64,
+    pub size: f64,
+    pub order_id: Option<i64>,
+    pub signature: Option<String>,
+}
+
+/// Every address created by `sandbox init`, as written to the manifest
+#[derive(Debug, Clone, Serialize)]
+pub struct SandboxManifest {
+    pub manifest_path: String,
+    pub rpc_url: String,
+    pub genesis_hash: String,
+    pub payer: String,
+    pub programs: SandboxPrograms,
+    pub base: SandboxToken,
+    pub quote: SandboxToken,
+    pub market: CreateMarketResult,
+    pub seat_manager: String,
+    pub traders: Vec<SandboxTrader>,
+    pub orders: Vec<SandboxOrder>,
+}
+
+impl CommandOutput for SandboxManifest {
+    fn print_text(&self) {
+        println!("Sandbox manifest written to {}", self.manifest_path);
+        println!(
+            "Market: {} ({}/{})",
+            self.market.market, self.base.ticker, self.quote.ticker
+        );
+        println!(
+            "Base mint: {} ({} decimals)",
+            self.base.mint, self.base.decimals
+        );
+        println!(
+            "Quote mint: {} ({} decimals)",
+            self.quote.mint, self.quote.decimals
+        );
+        println!("Seat manager: {}", self.seat_manager);
+        println!("Traders:");
+        for trader in &self.traders {
+            println!("  {} {}", trader.pubkey, trader.keypair_path);
+        }
+        println!("Seeded {} orders", self.orders.len());
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "trader",
+            "keypair_path",
+            "seat",
+            "base_token_account",
+            "quote_token_account",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        self.traders
+            .iter()
+            .map(|trader| {
+                vec![
+                    trader.pubkey.clone(),
+                    trader.keypair_path.clone(),
+                    trader.seat.clone(),
+                    trader.base_token_account.clone(),
+                    trader.quote_token_account.clone(),
+                ]
+            })
+            .collect()
+    }
+}
+
+// Drops the float error of the level arithmetic, so 100 - 3 * 0.1 is shown as 99.7
+fn round_price(price: f64) -> f64 {
+    (price * 1e9).round() / 1e9
+}
+
+// Symmetric book: level i sits i spacings from the mid on each side, and traders take turns per level
+fn get_seed_orders(params: &SandboxParams) -> Vec<(usize, OrderSpec)> {
+    let mut orders = vec![];
+    for level in 1..=params.levels {
+        let trader_index = (level - 1) % params.num_traders;
+        let offset = params.level_spacing * level as f64;
+        for (side, price) in [
+            ("bid", round_price(params.mid_price - offset)),
+            ("ask", round_price(params.mid_price + offset)),
+        ] {
+            orders.push((
+                trader_index,
+                OrderSpec {
+                    side: side.to_string(),
+                    price,
+                    size: params.order_size,
+                    client_order_id: Some(level as u64),
+                    last_valid_slot: None,
+                    last_valid_unix_timestamp: None,
+                },
+            ));
+        }
+    }
+    orders
+}
+
+fn validate_params(params: &SandboxParams) -> anyhow::Result<()> {
+    if params.num_traders == 0 {
+        return Err(anyhow::anyhow!("The sandbox needs at least one trader"));
+    }
+    if params
+        .base_ticker
+        .eq_ignore_ascii_case(&params.quote_ticker)
+    {
+        return Err(anyhow::anyhow!("The base and quote tickers must differ"));
+    }
+    let lowest_bid = params.mid_price - params.level_spacing * params.levels as f64;
+    if params.levels > 0 && lowest_bid <= 0.0 {
+        return Err(anyhow::anyhow!(
+            "The lowest bid would be at {}. Use a higher mid, fewer levels or a smaller spacing",
+            lowest_bid
+        ));
+    }
+
+    let tick_size = params
+        .tick_size
+        .parse::<f64>()
+        .map_err(|_| anyhow::anyhow!("Invalid tick size {}", params.tick_size))?;
+    let lot_size = params
+        .base_lot_size
+        .parse::<f64>()
+        .map_err(|_| anyhow::anyhow!("Invalid base lot size {}", params.base_lot_size))?;
+    let orders = get_seed_orders(params);
+    for (_, order) in &orders {
+        if to_whole_units(order.price / tick_size).is_none() {
+            return Err(anyhow::anyhow!(
+                "The {} at {} is not on a tick of {}. Use a --mid and --level-spacing that are multiples of the tick size",
+                order.side,
+                order.price,
+                params.tick_size
+            ));
+        }
+    }
+    if to_whole_units(params.order_size / lot_size).is_none() {
+        return Err(anyhow::anyhow!(
+            "The order size {} is not a multiple of the base lot size {}",
+            params.order_size,
+            lot_size
+        ));
+    }
+
+    // Each trader must be able to fund its orders from the airdrop
+    for trader_index in 0..params.num_traders {
+        let trader_orders = orders.iter().filter(|(index, _)| *index == trader_index);
+        let base_needed = trader_orders
+            .clone()
+            .filter(|(_, order)| order.side == "ask")
+            .map(|(_, order)| order.size)
+            .sum::<f64>();
+        let quote_needed = trader_orders
+            .filter(|(_, order)| order.side == "bid")
+            .map(|(_, order)| order.size * order.price)
+            .sum::<f64>();
+        if base_needed > params.base_amount as f64 || quote_needed > params.quote_amount as f64 {
+            return Err(anyhow::anyhow!(
+                "Trader {} needs {} base and {} quote units for its orders, but is airdropped {} and {}. \
+                Raise --base-amount and --quote-amount, or lower --order-size",
+                trader_index + 1,
+                base_needed,
+                quote_needed,
+                params.base_amount,
+                params.quote_amount
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Refuses to run on mainnet, and makes sure the programs the sandbox uses are deployed
+async fn check_cluster(client: &EllipsisClient) -> anyhow::Result<String> {
+    let genesis_hash = client.get_genesis_hash().await?.to_string();
+    if genesis_hash == MAINNET_GENESIS_HASH {
+        return Err(anyhow::anyhow!(
+            "sandbox init only runs against a local test validator or devnet, not mainnet"
+        ));
+    }
+    for (name, program) in [
+        ("Phoenix", phoenix::id()),
+        ("seat manager", phoenix_seat_manager::id()),
+        ("token faucet", devnet_token_faucet::id()),
+    ] {
+        let deployed = client
+            .get_account(&program)
+            .await
+            .map(|account| account.executable)
+            .unwrap_or(false);
+        if !deployed {
+            return Err(anyhow::anyhow!(
+                "The {} program {} is not deployed on {}. For a local test validator, clone it from devnet with \
+                `solana-test-validator --url devnet --clone-upgradeable-program {}`",
+                name,
+                program,
+                client.url(),
+                program
+            ));
+        }
+    }
+    Ok(genesis_hash)
+}
+
+async fn get_mint_decimals(client: &EllipsisClient, mint: &Pubkey) -> anyhow::Result<u8> {
+    Mint::unpack(&client.get_account_data(mint).await?)
+        .map(|mint| mint.decimals)
+        .map_err(|_| anyhow::anyhow!("{} is not a token mint", mint))
+}
+
+// Token amount in atoms of `amount` whole units, refusing amounts a token account cannot hold
+fn airdrop_atoms(ticker: &str, amount: u64, decimals: u8) -> anyhow::Result<u64> {
+    10u64
+        .checked_pow(decimals as u32)
+        .and_then(|atoms_per_unit| amount.checked_mul(atoms_per_unit))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "An airdrop of {} {} with {} decimals overflows a token amount. Lower the airdrop amount",
+                amount,
+                ticker,
+                decimals
+            )
+        })
+}
+
+// The sandbox builds on its own transactions, so each one must have landed before the next is built
+fn sent<T>(outcome: TransactionOutcome<T>) -> anyhow::Result<T> {
+    match outcome {
+        TransactionOutcome::Sent(result) => Ok(result),
+        TransactionOutcome::Unsent(_) => Err(anyhow::anyhow!("A sandbox transaction was not sent")),
+    }
+}
+
+fn write_trader_keypairs(out_dir: &Path, traders: &[Keypair]) -> anyhow::Result<Vec<String>> {
+    fs::create_dir_all(out_dir)
+        .map_err(|e| anyhow::anyhow!("Failed to create {}: {}", out_dir.display(), e))?;
+    traders
+        .iter()
+        .enumerate()
+        .map(|(i, keypair)| {
+            let path = out_dir.join(format!("trader-{}.json", i + 1));
+            write_keypair_file(keypair, &path)
+                .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))?;
+            Ok(path.display().to_string())
+        })
+        .collect()
+}
+
+/// Builds a ready to use environment: faucet mints for the pair, a seat manager market, funded traders
+/// with seats and a symmetric book around the mid. Every address is written to a manifest in the
+/// output directory, next to the trader keypairs
+pub async fn process_sandbox_init(
+    sdk: &SDKClient,
+    params: &SandboxParams,
+) -> anyhow::Result<SandboxManifest> {
+    let client = &sdk.client;
+    let payer = client.payer.pubkey();
+    let options = get_transaction_options();
+    if options.simulate || options.is_offline() {
+        return Err(anyhow::anyhow!(
+            "sandbox init sends every transaction, it cannot be simulated or exported"
+        ));
+    }
+    let manifest_path = params.out_dir.join("manifest.json");
+    // Never overwrite keypairs, which may hold funds from an earlier sandbox
+    if manifest_path.exists() || params.out_dir.join("trader-1.json").exists() {
+        return Err(anyhow::anyhow!(
+            "{} already holds a sandbox. Pass a different --out-dir",
+            params.out_dir.display()
+        ));
+    }
+    validate_params(params)?;
+    let genesis_hash = check_cluster(client).await?;
+
+    // The payer funds the market, the traders, their base and quote token accounts and the seat deposits
+    let num_seats = 512 + 512 + SEAT_MANAGER_RESERVED_SEATS;
+    let market_size = size_of::<MarketHeader>()
+        + get_market_size(&MarketSizeParams {
+            bids_size: 512,
+            asks_size: 512,
+            num_seats,
+        })?;
+    let token_account_rent = client
+        .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
+        .await?;
+    let required_lamports = client
+        .get_minimum_balance_for_rent_exemption(market_size)
+        .await?
+        + params.num_traders as u64
+            * (sol_to_lamports(params.sol_per_trader) + 2 * token_account_rent);
+    let balance = client.get_balance(&payer).await?;
+    if balance < required_lamports {
+        return Err(anyhow::anyhow!(
+            "The payer {} has {} SOL, but the sandbox needs at least {} SOL. Fund it with `solana airdrop`",
+            payer,
+            lamports_to_sol(balance),
+            lamports_to_sol(required_lamports)
+        ));
+    }
+
+    eprintln!(
+        "Creating the {} and {} mints",
+        params.base_ticker, params.quote_ticker
+    );
+    let base_mint =
+        find_or_create_devnet_mint(sdk, &params.base_ticker, params.base_decimals).await?;
+    let quote_mint =
+        find_or_create_devnet_mint(sdk, &params.quote_ticker, params.quote_decimals).await?;
+    let base_decimals = get_mint_decimals(client, &base_mint).await?;
+    let quote_decimals = get_mint_decimals(client, &quote_mint).await?;
+    let base_atoms = airdrop_atoms(&params.base_ticker, params.base_amount, base_decimals)?;
+    let quote_atoms = airdrop_atoms(&params.quote_ticker, params.quote_amount, quote_decimals)?;
+
+    eprintln!("Creating the market");
+    let market = sent(
+        process_create_market(
+            sdk,
+            &CreateMarketParams {
+                base_mint,
+                quote_mint,
+                tick_size: params.tick_size.clone(),
+                base_lot_size: params.base_lot_size.clone(),
+                quote_lot_size: None,
+                bids_size: 512,
+                asks_size: 512,
+                num_seats: Some(num_seats),
+                taker_fee_bps: params.taker_fee_bps,
+                fee_recipient: None,
+                raw_base_units_per_base_unit: None,
+                seat_manager: true,
+            },
+            None,
+        )
+        .await?,
+    )?;
+    let market_pubkey = market.market.parse::<Pubkey>()?;
+    sent(
+        send_instructions(
+            sdk,
+            vec![create_change_market_status_instruction(
+                &market_pubkey,
+                &payer,
+                MarketStatus::Active,
+            )],
+            vec![],
+        )
+        .await?,
+    )?;
+
+    // Keypairs are only written once the sandbox exists, so a simulated, exported or failed run leaves
+    // none behind. They must be on disk before any funds are sent to them
+    let traders = (0..params.num_traders)
+        .map(|_| Keypair::new())
+        .collect::<Vec<_>>();
+    let keypair_paths = write_trader_keypairs(&params.out_dir, &traders)?;
+    let traders = traders.into_iter().zip(keypair_paths).collect::<Vec<_>>();
+
+    eprintln!("Funding {} traders", traders.len());
+    let mut funding_instructions = vec![];
+    for (trader, _) in &traders {
+        let trader = trader.pubkey();
+        funding_instructions.push(system_instruction::transfer(
+            &payer,
+            &trader,
+            sol_to_lamports(params.sol_per_trader),
+        ));
+        for (ticker, mint, amount) in [
+            (&params.base_ticker, &base_mint, base_atoms),
+            (&params.quote_ticker, &quote_mint, quote_atoms),
+        ] {
+            funding_instructions.push(create_associated_token_account(
+                &payer,
+                &trader,
+                mint,
+                &spl_token::id(),
+            ));
+            funding_instructions.push(devnet_token_faucet::airdrop_spl_with_ticker_ix(
+                &devnet_token_faucet::id(),
+                ticker.clone(),
+                &trader,
+                amount,
+            ));
+        }
+    }
+    let batches = pack_instructions(
+        vec![],
+        funding_instructions.into_iter().enumerate().collect(),
+        &payer,
+    );
+    for (instructions, _) in batches {
+        sent(send_instructions(sdk, instructions, vec![]).await?)?;
+    }
+
+    eprintln!("Claiming seats");
+    let trader_pubkeys = traders
+        .iter()
+        .map(|(trader, _)| trader.pubkey())
+        .collect::<Vec<_>>();
+    for chunk in trader_pubkeys.chunks(SEATS_PER_TRANSACTION) {
+        sent(process_claim_seats_authorized(sdk, &market_pubkey, chunk).await?)?;
+    }
+
+    eprintln!("Seeding the book around {}", params.mid_price);
+    let seed_orders = get_seed_orders(params);
+    let mut orders = vec![];
+    for (trader_index, (trader, _)) in traders.iter().enumerate() {
+        let trader_orders = seed_orders
+            .iter()
+            .filter(|(index, _)| *index == trader_index)
+            .map(|(_, order)| order.clone())
+            .collect::<Vec<_>>();
+        if trader_orders.is_empty() {
+            continue;
+        }
+        let trader_client = EllipsisClient::from_rpc(
+            RpcClient::new_with_commitment(client.url(), client.commitment()),
+            trader,
+        )?;
+        let sdk = SDKClient::new_from_ellipsis_client_with_market_keys(
+            vec![&market_pubkey],
+            trader_client,
+        )
+        .await?;
+        let result = process_place_orders(
+            &market_pubkey,
+            &sdk,
+            trader_orders,
+            true,
+            SelfTradeBehavior::Abort,
+            false,
+        )
+        .await?;
+        for outcome in result.orders {
+            if outcome.status != "placed" {
+                return Err(anyhow::anyhow!(
+                    "Failed to seed the {} at {} for trader {}: {}",
+                    outcome.side,
+                    outcome.price,
+                    trader.pubkey(),
+                    outcome.message.unwrap_or(outcome.status)
+                ));
+            }
+            orders.push(SandboxOrder {
+                trader: trader.pubkey().to_string(),
+                side: outcome.side,
+                price: outcome.price,
+                size: outcome.size,
+                order_id: outcome.order_id,
+                signature: outcome.signature,
+            });
+        }
+    }
+
+    let manifest = SandboxManifest {
+        manifest_path: manifest_path.display().to_string(),
+        rpc_url: client.url(),
+        genesis_hash,
+        payer: payer.to_string(),
+        programs: SandboxPrograms {
+            phoenix: phoenix::id().to_string(),
+            seat_manager: phoenix_seat_manager::id().to_string(),
+            token_faucet: devnet_token_faucet::id().to_string(),
+        },
+        base: SandboxToken {
+            ticker: params.base_ticker.clone(),
+            mint: base_mint.to_string(),
+            decimals: base_decimals,
+        },
+        quote: SandboxToken {
+            ticker: params.quote_ticker.clone(),
+            mint: quote_mint.to_string(),
+            decimals: quote_decimals,
+        },
+        seat_manager: get_seat_manager_address(&market_pubkey).0.to_string(),
+        market,
+        traders: traders
+            .iter()
+            .map(|(trader, keypair_path)| SandboxTrader {
+                pubkey: trader.pubkey().to_string(),
+                keypair_path: keypair_path.clone(),
+                seat: get_seat_address(&market_pubkey, &trader.pubkey())
+                    .0
+                    .to_string(),
+                base_token_account: get_associated_token_address(&trader.pubkey(), &base_mint)
+                    .to_string(),
+                quote_token_account: get_associated_token_address(&trader.pubkey(), &quote_mint)
+                    .to_string(),
+            })
+            .collect(),
+        orders,
+    };
+    fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+        .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", manifest_path.display(), e))?;
+    Ok(manifest)
+}
@@ -0,0 +1,433 @@
+use crate::helpers::market_helpers::{get_seat_manager_data_with_market, get_seat_statuses};
+use crate::helpers::output_helpers::CommandOutput;
+use crate::helpers::transaction_helpers::{
+    get_signing_authority, get_transaction_options, instructions_fit_in_transaction,
+    send_instructions, TransactionOutcome,
+};
+use crate::processor::process_get_seat_manager_info::SeatManagerDetails;
+use crate::processor::process_market_admin::{get_market_state, MarketState};
+use phoenix::program::status::SeatApprovalStatus;
+use phoenix::quantities::WrapperU64;
+use phoenix_sdk::sdk_client::SDKClient;
+use phoenix_seat_manager::get_seat_manager_address;
+use phoenix_seat_manager::instruction_builders::{
+    create_add_dmm_instruction, create_claim_seat_authorized_instruction,
+    create_claim_seat_manager_authority_instruction, create_evict_seat_instruction,
+    create_name_seat_manager_successor_instruction, create_remove_dmm_instruction,
+    EvictTraderAccountBackup,
+};
+use phoenix_seat_manager::seat_manager::SeatManager;
+use serde::Serialize;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::Signer;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SeatManagerAdminResult {
+    pub market: String,
+    pub seat_manager: String,
+    /// Action taken: add_dmm, remove_dmm, name_successor, claim_authority, claim_seat or evict_seat
+    pub action: String,
+    /// The DMM, successor or traders the action applied to
+    pub targets: Vec<String>,
+    pub authority: String,
+    pub successor: String,
+    pub dmms_before: Vec<String>,
+    pub dmms_after: Vec<String>,
+    pub signature: String,
+}
+
+impl CommandOutput for SeatManagerAdminResult {
+    fn print_text(&self) {
+        let targets = self.targets.join(", ");
+        match self.action.as_str() {
+            "add_dmm" => println!("Added designated market maker {}", targets),
+            "remove_dmm" => println!("Removed designated market maker {}", targets),
+            "name_successor" => println!("Named {} as the seat manager successor", targets),
+            "claim_authority" => println!("Claimed the seat manager authority for {}", targets),
+            "claim_seat" => println!("Claimed seats for {}", targets),
+            "evict_seat" => println!("Evicted {}", targets),
+            action => println!("{} {}", action, targets),
+        }
+        println!("Market: {}", self.market);
+        println!("Seat manager: {}", self.seat_manager);
+        println!("SM Authority: {}", self.authority);
+        println!("SM Successor: {}", self.successor);
+        println!(
+            "DMMs before ({}): [{}]",
+            self.dmms_before.len(),
+            self.dmms_before.join(", ")
+        );
+        println!(
+            "DMMs after ({}): [{}]",
+            self.dmms_after.len(),
+            self.dmms_after.join(", ")
+        );
+        println!("Signature: {}", self.signature);
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "market",
+            "seat_manager",
+            "action",
+            "targets",
+            "authority",
+            "successor",
+            "dmms_before",
+            "dmms_after",
+            "signature",
+        ]
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.market.clone(),
+            self.seat_manager.clone(),
+            self.action.clone(),
+            self.targets.join(" "),
+            self.authority.clone(),
+            self.successor.clone(),
+            self.dmms_before.join(" "),
+            self.dmms_after.join(" "),
+            self.signature.clone(),
+        ]]
+    }
+}
+
+struct SeatManagerState {
+    address: Pubkey,
+    seat_manager: SeatManager,
+    market: MarketState,
+}
+
+async fn get_seat_manager_state(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+) -> anyhow::Result<SeatManagerState> {
+    let market = get_market_state(sdk, market_pubkey).await?;
+    let address = get_seat_manager_address(market_pubkey).0;
+    if market.header.authority != address {
+        return Err(anyhow::anyhow!(
+            "Market {} is not governed by the seat manager, its authority is {}. Use the `admin` commands instead",
+            market_pubkey,
+            market.header.authority
+        ));
+    }
+    let seat_manager = get_seat_manager_data_with_market(&sdk.client, market_pubkey).await?;
+    Ok(SeatManagerState {
+        address,
+        seat_manager,
+        market,
+    })
+}
+
+// The seat manager program rejects these instructions from anyone but its authority, so check before paying for the transaction.
+// An exported transaction signed once the authority has changed can skip the check with --skip-authority-check
+fn assert_seat_manager_authority(
+    seat_manager: &SeatManager,
+    market_pubkey: &Pubkey,
+    signer: &Pubkey,
+) -> anyhow::Result<()> {
+    if seat_manager.authority == *signer || get_transaction_options().skip_authority_check {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "{} is not the seat manager authority of market {}, the authority is {}",
+        signer,
+        market_pubkey,
+        seat_manager.authority
+    ))
+}
+
+fn designated_market_makers(seat_manager: &SeatManager, address: &Pubkey) -> Vec<String> {
+    SeatManagerDetails::new(seat_manager, address).designated_market_makers
+}
+
+// Sends the instructions, then reads the seat manager again so the result shows its state after the change
+async fn send_seat_manager_instructions(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+    state: &SeatManagerState,
+    action: &str,
+    targets: &[Pubkey],
+    instructions: Vec<Instruction>,
+) -> anyhow::Result<TransactionOutcome<SeatManagerAdminResult>> {
+    let signature = match send_instructions(sdk, instructions, vec![]).await? {
+        TransactionOutcome::Sent(signature) => signature,
+        TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+    };
+    let seat_manager = get_seat_manager_data_with_market(&sdk.client, market_pubkey).await?;
+    Ok(TransactionOutcome::Sent(SeatManagerAdminResult {
+        market: market_pubkey.to_string(),
+        seat_manager: state.address.to_string(),
+        action: action.to_string(),
+        targets: targets.iter().map(|target| target.to_string()).collect(),
+        authority: seat_manager.authority.to_string(),
+        successor: seat_manager.successor.to_string(),
+        dmms_before: designated_market_makers(&state.seat_manager, &state.address),
+        dmms_after: designated_market_makers(&seat_manager, &state.address),
+        signature: signature.to_string(),
+    }))
+}
+
+/// Adds a designated market maker. DMMs cannot be evicted, and the trader must already have a seat
+pub async fn process_add_dmm(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+    trader: &Pubkey,
+) -> anyhow::Result<TransactionOutcome<SeatManagerAdminResult>> {
+    let authority = get_signing_authority(&sdk.client);
+    let state = get_seat_manager_state(sdk, market_pubkey).await?;
+    assert_seat_manager_authority(&state.seat_manager, market_pubkey, &authority)?;
+    if state.seat_manager.contains(trader) {
+        return Err(anyhow::anyhow!(
+            "{} is already a designated market maker on market {}",
+            trader,
+            market_pubkey
+        ));
+    }
+    if state.seat_manager.is_full() {
+        return Err(anyhow::anyhow!(
+            "Market {} already has the maximum of {} designated market makers. Remove one first",
+            market_pubkey,
+            state.seat_manager.capacity()
+        ));
+    }
+    if !state.market.registered_traders.contains_key(trader) {
+        return Err(anyhow::anyhow!(
+            "{} does not have a seat on market {}. Claim one with `seat-manager claim-seat` first",
+            trader,
+            market_pubkey
+        ));
+    }
+
+    let ix = create_add_dmm_instruction(market_pubkey, &authority, trader);
+    send_seat_manager_instructions(sdk, market_pubkey, &state, "add_dmm", &[*trader], vec![ix])
+        .await
+}
+
+/// Removes a designated market maker. The program only accepts this while the trader still has a seat
+pub async fn process_remove_dmm(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+    trader: &Pubkey,
+) -> anyhow::Result<TransactionOutcome<SeatManagerAdminResult>> {
+    let authority = get_signing_authority(&sdk.client);
+    let state = get_seat_manager_state(sdk, market_pubkey).await?;
+    assert_seat_manager_authority(&state.seat_manager, market_pubkey, &authority)?;
+    if !state.seat_manager.contains(trader) {
+        return Err(anyhow::anyhow!(
+            "{} is not a designated market maker on market {}",
+            trader,
+            market_pubkey
+        ));
+    }
+    if !state.market.registered_traders.contains_key(trader) {
+        return Err(anyhow::anyhow!(
+            "{} no longer has a seat on market {}, so the seat manager cannot remove it as a designated market maker",
+            trader,
+            market_pubkey
+        ));
+    }
+
+    let ix = create_remove_dmm_instruction(market_pubkey, &authority, trader);
+    send_seat_manager_instructions(
+        sdk,
+        market_pubkey,
+        &state,
+        "remove_dmm",
+        &[*trader],
+        vec![ix],
+    )
+    .await
+}
+
+/// Names the account that can claim the seat manager authority with `seat-manager claim-authority`
+pub async fn process_name_seat_manager_successor(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+    successor: &Pubkey,
+) -> anyhow::Result<TransactionOutcome<SeatManagerAdminResult>> {
+    let authority = get_signing_authority(&sdk.client);
+    let state = get_seat_manager_state(sdk, market_pubkey).await?;
+    assert_seat_manager_authority(&state.seat_manager, market_pubkey, &authority)?;
+
+    let ix = create_name_seat_manager_successor_instruction(&authority, market_pubkey, successor);
+    send_seat_manager_instructions(
+        sdk,
+        market_pubkey,
+        &state,
+        "name_successor",
+        &[*successor],
+        vec![ix],
+    )
+    .await
+}
+
+/// Takes over the seat manager authority. Signed by the successor named by the current authority, which
+/// --authority names when exporting
+pub async fn process_claim_seat_manager_authority(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+) -> anyhow::Result<TransactionOutcome<SeatManagerAdminResult>> {
+    let successor = get_signing_authority(&sdk.client);
+    let state = get_seat_manager_state(sdk, market_pubkey).await?;
+    let skip_authority_check = get_transaction_options().skip_authority_check;
+    if state.seat_manager.authority == successor && !skip_authority_check {
+        return Err(anyhow::anyhow!(
+            "{} is already the seat manager authority of market {}",
+            successor,
+            market_pubkey
+        ));
+    }
+    if state.seat_manager.successor != successor && !skip_authority_check {
+        return Err(anyhow::anyhow!(
+            "{} is not the seat manager successor of market {}, the successor is {}. The authority names one with `seat-manager name-successor`",
+            successor,
+            market_pubkey,
+            state.seat_manager.successor
+        ));
+    }
+
+    let ix = create_claim_seat_manager_authority_instruction(market_pubkey, &successor);
+    send_seat_manager_instructions(
+        sdk,
+        market_pubkey,
+        &state,
+        "claim_authority",
+        &[successor],
+        vec![ix],
+    )
+    .await
+}
+
+/// Requests and approves seats on behalf of traders. The authority pays the seat rent and the
+/// deposit the seat manager holds for each seat
+pub async fn process_claim_seats_authorized(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+    traders: &[Pubkey],
+) -> anyhow::Result<TransactionOutcome<SeatManagerAdminResult>> {
+    let authority = get_signing_authority(&sdk.client);
+    let state = get_seat_manager_state(sdk, market_pubkey).await?;
+    assert_seat_manager_authority(&state.seat_manager, market_pubkey, &authority)?;
+
+    let seat_statuses = get_seat_statuses(&sdk.client, market_pubkey, traders).await?;
+    for (trader, status) in traders.iter().zip(seat_statuses) {
+        match status {
+            Some(SeatApprovalStatus::Approved) => {
+                return Err(anyhow::anyhow!(
+                    "{} already has an approved seat on market {}",
+                    trader,
+                    market_pubkey
+                ))
+            }
+            Some(SeatApprovalStatus::Retired) => {
+                return Err(anyhow::anyhow!(
+                    "The seat of {} on market {} is retired and cannot be claimed again",
+                    trader,
+                    market_pubkey
+                ))
+            }
+            _ => {}
+        }
+    }
+    let free_trader_slots = state
+        .market
+        .header
+        .market_size_params
+        .num_seats
+        .saturating_sub(state.market.registered_traders.len() as u64);
+    if traders.len() as u64 > free_trader_slots {
+        return Err(anyhow::anyhow!(
+            "Market {} has {} free trader slots, but {} seats were requested. Evict traders first",
+            market_pubkey,
+            free_trader_slots,
+            traders.len()
+        ));
+    }
+
+    let instructions = traders
+        .iter()
+        .map(|trader| create_claim_seat_authorized_instruction(trader, market_pubkey, &authority))
+        .collect::<Vec<_>>();
+    if !instructions_fit_in_transaction(&instructions, &sdk.client.payer.pubkey()) {
+        return Err(anyhow::anyhow!(
+            "Claiming {} seats does not fit in one transaction, pass fewer traders",
+            traders.len()
+        ));
+    }
+    send_seat_manager_instructions(
+        sdk,
+        market_pubkey,
+        &state,
+        "claim_seat",
+        traders,
+        instructions,
+    )
+    .await
+}
+
+/// Evicts traders as the seat manager authority, which can evict any trader without locked funds even
+/// when the market is not full. Free funds are withdrawn to the traders' token accounts
+pub async fn process_evict_seats_authorized(
+    sdk: &SDKClient,
+    market_pubkey: &Pubkey,
+    traders: &[Pubkey],
+) -> anyhow::Result<TransactionOutcome<SeatManagerAdminResult>> {
+    let authority = get_signing_authority(&sdk.client);
+    let state = get_seat_manager_state(sdk, market_pubkey).await?;
+    assert_seat_manager_authority(&state.seat_manager, market_pubkey, &authority)?;
+
+    for trader in traders {
+        // The program skips DMMs and traders with locked funds without failing, so refuse them here
+        if state.seat_manager.contains(trader) {
+            return Err(anyhow::anyhow!(
+                "{} is a designated market maker on market {}. Remove it with `seat-manager remove-dmm` first",
+                trader,
+                market_pubkey
+            ));
+        }
+        let trader_state = state.market.registered_traders.get(trader).ok_or_else(|| {
+            anyhow::anyhow!(
+                "{} does not have a seat on market {}",
+                trader,
+                market_pubkey
+            )
+        })?;
+        if trader_state.base_lots_locked.as_u64() > 0 || trader_state.quote_lots_locked.as_u64() > 0
+        {
+            return Err(anyhow::anyhow!(
+                "{} has {} base lots and {} quote lots locked in open orders on market {}. Cancel them first",
+                trader,
+                trader_state.base_lots_locked,
+                trader_state.quote_lots_locked,
+                market_pubkey
+            ));
+        }
+    }
+
+    let ix = create_evict_seat_instruction(
+        market_pubkey,
+        &state.market.header.base_params.mint_key,
+        &state.market.header.quote_params.mint_key,
+        &authority,
+        traders
+            .iter()
+            .map(|trader| EvictTraderAccountBackup {
+                trader_pubkey: *trader,
+                base_token_account_backup: None,
+                quote_token_account_backup: None,
+            })
+            .collect(),
+    );
+    if !instructions_fit_in_transaction(std::slice::from_ref(&ix), &sdk.client.payer.pubkey()) {
+        return Err(anyhow::anyhow!(
+            "Evicting {} traders does not fit in one transaction, pass fewer traders",
+            traders.len()
+        ));
+    }
+    send_seat_manager_instructions(sdk, market_pubkey, &state, "evict_seat", traders, vec![ix])
+        .await
+}
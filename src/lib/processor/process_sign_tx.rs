@@ -0,0 +1,29 @@
+use crate::helpers::transaction_helpers::{
+    decode_transaction, read_encoded_transaction, EncodedTransaction, TransactionEncoding,
+};
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+
+/// Adds signatures from local keypairs to a transaction exported with `--export-tx` or `--sign-only`.
+/// The transaction keeps its encoding unless another one is given
+pub fn process_sign_tx(
+    transaction: &str,
+    signers: &[Keypair],
+    encoding: Option<TransactionEncoding>,
+) -> anyhow::Result<EncodedTransaction> {
+    let (mut transaction, input_encoding) =
+        decode_transaction(&read_encoded_transaction(transaction)?)?;
+    let required_signers = transaction.message.signer_keys();
+    for signer in signers {
+        if !required_signers.contains(&&signer.pubkey()) {
+            return Err(anyhow::anyhow!(
+                "{} is not a signer of the transaction",
+                signer.pubkey()
+            ));
+        }
+    }
+
+    let blockhash = transaction.message.recent_blockhash;
+    transaction.try_partial_sign(&signers.iter().collect::<Vec<_>>(), blockhash)?;
+    EncodedTransaction::new(&transaction, encoding.unwrap_or(input_encoding))
+}
@@ -0,0 +1,184 @@
+use crate::helpers::output_helpers::{render_stream_item, OutputFormat};
+use crate::helpers::print_helpers::{get_market_event_records, MarketEventRecord};
+use clap::ValueEnum;
+use ellipsis_client::EllipsisClient;
+use phoenix_sdk::sdk_client::*;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::str::FromStr;
+use std::time::Duration;
+
+// Maximum number of signatures returned by a single getSignaturesForAddress call
+const SIGNATURES_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MarketEventType {
+    Fill,
+    Place,
+    Reduce,
+    Evict,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    /// Event types to keep. Every event type is kept when empty
+    pub event_types: Vec<MarketEventType>,
+    pub maker: Option<Pubkey>,
+    pub taker: Option<Pubkey>,
+    /// Minimum event size in base units
+    pub min_size: Option<f64>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &MarketEventRecord) -> bool {
+        let event_type_matches = self.event_types.is_empty()
+            || self
+                .event_types
+                .iter()
+                .any(|event_type| format!("{:?}", event_type) == event.event_type);
+        let maker_matches = self
+            .maker
+            .is_none_or(|maker| event.maker.as_deref() == Some(maker.to_string().as_str()));
+        let taker_matches = self
+            .taker
+            .is_none_or(|taker| event.taker.as_deref() == Some(taker.to_string().as_str()));
+        let size_matches = self.min_size.is_none_or(|min_size| {
+            event
+                .quantity
+                .as_ref()
+                .and_then(|quantity| quantity.parse::<f64>().ok())
+                .is_some_and(|quantity| quantity >= min_size)
+        });
+        event_type_matches && maker_matches && taker_matches && size_matches
+    }
+}
+
+pub async fn process_stream_events(
+    market_pubkey: &Pubkey,
+    sdk: &mut SDKClient,
+    filter: EventFilter,
+    poll_interval: Duration,
+    output: OutputFormat,
+) -> anyhow::Result<()> {
+    // Start from the latest transaction so only new events are streamed
+    let mut last_signature =
+        get_signatures_for_market(&sdk.client, market_pubkey, None, None, Some(1))
+            .await?
+            .first()
+            .map(|status| Signature::from_str(&status.signature))
+            .transpose()?;
+
+    if output == OutputFormat::Text {
+        println!(
+            "Streaming events for market {}. Press Ctrl-C to exit",
+            market_pubkey
+        );
+    }
+
+    let stream = async {
+        let mut is_first = true;
+        let mut interval = tokio::time::interval(poll_interval);
+        loop {
+            interval.tick().await;
+            let statuses =
+                match get_new_signatures_for_market(&sdk.client, market_pubkey, last_signature)
+                    .await
+                {
+                    Ok(statuses) => statuses,
+                    Err(e) => {
+                        eprintln!("Failed to fetch signatures: {}", e);
+                        continue;
+                    }
+                };
+
+            for status in statuses {
+                // A transaction that cannot be read is skipped, so it does not end the stream
+                let signature = match Signature::from_str(&status.signature) {
+                    Ok(signature) => signature,
+                    Err(e) => {
+                        eprintln!("Skipping invalid signature {}: {}", status.signature, e);
+                        continue;
+                    }
+                };
+                last_signature = Some(signature);
+                if status.err.is_some() {
+                    continue;
+                }
+                let events = match sdk.parse_events_from_transaction(&signature).await {
+                    Some(events) => events
+                        .into_iter()
+                        .filter(|event| event.market == *market_pubkey)
+                        .collect::<Vec<_>>(),
+                    None => continue,
+                };
+                let events = match get_market_event_records(sdk, events).await {
+                    Ok(events) => events,
+                    Err(e) => {
+                        eprintln!("Skipping the events of {}: {}", signature, e);
+                        continue;
+                    }
+                };
+                for event in events {
+                    if filter.matches(&event) {
+                        render_stream_item(&event, output, is_first)?;
+                        is_first = false;
+                    }
+                }
+            }
+        }
+    };
+
+    tokio::select! {
+        result = stream => result,
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    }
+}
+
+/// Returns the signatures for the market newer than `until`, oldest first
+pub async fn get_new_signatures_for_market(
+    client: &EllipsisClient,
+    market_pubkey: &Pubkey,
+    until: Option<Signature>,
+) -> anyhow::Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+    let mut statuses = vec![];
+    let mut before = None;
+    loop {
+        let page = get_signatures_for_market(client, market_pubkey, before, until, None).await?;
+        let page_len = page.len();
+        before = page
+            .last()
+            .map(|status| Signature::from_str(&status.signature))
+            .transpose()?;
+        statuses.extend(page);
+        // Without a lower bound, a single page of the latest signatures is enough
+        if until.is_none() || page_len < SIGNATURES_PAGE_SIZE {
+            break;
+        }
+    }
+    statuses.reverse();
+    Ok(statuses)
+}
+
+/// Returns the signatures for the market between `before` and `until` (both exclusive), newest first
+pub async fn get_signatures_for_market(
+    client: &EllipsisClient,
+    market_pubkey: &Pubkey,
+    before: Option<Signature>,
+    until: Option<Signature>,
+    limit: Option<usize>,
+) -> anyhow::Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+    Ok(client
+        .get_signatures_for_address_with_config(
+            market_pubkey,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until,
+                limit,
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )
+        .await?)
+}
@@ -0,0 +1,211 @@
+use crate::helpers::market_helpers::{
+    get_book_levels, get_market_taker_fee_bps, get_token_account_setup_instructions,
+};
+use crate::helpers::output_helpers::CommandOutput;
+use crate::helpers::print_helpers::{get_market_event_records, MarketEventRecord};
+use crate::helpers::transaction_helpers::{send_instructions, TransactionOutcome};
+use phoenix::program::create_new_order_instruction;
+use phoenix::quantities::{BaseLots, QuoteLots, Ticks, WrapperU64};
+use phoenix::state::{OrderPacket, SelfTradeBehavior, Side};
+use phoenix_sdk::sdk_client::*;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+const BPS_DIVISOR: u64 = 10_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SwapQuote {
+    pub side: String,
+    pub size: String,
+    pub best_price: f64,
+    pub expected_average_price: f64,
+    pub worst_price: f64,
+    pub price_impact_bps: f64,
+    pub expected_quote_amount: String,
+    pub taker_fee_bps: u64,
+    pub expected_taker_fees: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SwapResult {
+    pub quote: SwapQuote,
+    pub signature: String,
+    pub fills: Vec<MarketEventRecord>,
+}
+
+impl SwapQuote {
+    pub fn print_text(&self) {
+        println!("Pre-trade quote");
+        println!("Side: {}", self.side);
+        println!("Size: {}", self.size);
+        println!("Best price: {}", self.best_price);
+        println!("Expected average price: {}", self.expected_average_price);
+        println!("Worst price filled: {}", self.worst_price);
+        println!("Price impact in basis points: {:.2}", self.price_impact_bps);
+        println!(
+            "Expected quote amount before fees: {}",
+            self.expected_quote_amount
+        );
+        println!(
+            "Expected taker fees ({} bps): {}",
+            self.taker_fee_bps, self.expected_taker_fees
+        );
+    }
+}
+
+impl CommandOutput for SwapResult {
+    fn print_text(&self) {
+        self.quote.print_text();
+        println!("Swap transaction: {}", self.signature);
+        self.fills.iter().for_each(|fill| fill.print_text());
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        MarketEventRecord::CSV_HEADER.to_vec()
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        self.fills.iter().map(|fill| fill.csv_record()).collect()
+    }
+}
+
+pub async fn process_swap(
+    market_pubkey: &Pubkey,
+    sdk: &mut SDKClient,
+    side: Side,
+    size: f64,
+    max_slippage_bps: u64,
+) -> anyhow::Result<TransactionOutcome<SwapResult>> {
+    let meta = *sdk.get_market_metadata_from_cache(market_pubkey)?;
+    let num_base_lots = sdk.raw_base_units_to_base_lots_rounded_down(market_pubkey, size)?;
+    if num_base_lots == 0 {
+        return Err(anyhow::anyhow!(
+            "Size {} is below the minimum base lot size of the market",
+            size
+        ));
+    }
+
+    let book = get_book_levels(market_pubkey, &sdk.client, u64::MAX).await?;
+    let taker_fee_bps = get_market_taker_fee_bps(&sdk.client, market_pubkey).await?;
+
+    // A buy walks the asks and a sell walks the bids
+    let levels = match side {
+        Side::Bid => &book.asks,
+        Side::Ask => &book.bids,
+    };
+    let best_price_in_ticks = levels
+        .first()
+        .map(|level| level.price_in_ticks)
+        .ok_or_else(|| anyhow::anyhow!("No liquidity on the opposite side of the book"))?;
+
+    let mut remaining_base_lots = num_base_lots;
+    let mut expected_quote_atoms = 0;
+    let mut worst_price_in_ticks = best_price_in_ticks;
+    for level in levels.iter() {
+        if remaining_base_lots == 0 {
+            break;
+        }
+        let base_lots_filled = remaining_base_lots.min(level.size_in_base_lots);
+        expected_quote_atoms += sdk.base_lots_and_price_to_quote_atoms(
+            market_pubkey,
+            base_lots_filled,
+            level.price_in_ticks,
+        )?;
+        worst_price_in_ticks = level.price_in_ticks;
+        remaining_base_lots -= base_lots_filled;
+    }
+    if remaining_base_lots > 0 {
+        return Err(anyhow::anyhow!(
+            "Insufficient liquidity: only {} of {} can be filled",
+            get_decimal_string(
+                sdk.base_lots_to_base_atoms(market_pubkey, num_base_lots - remaining_base_lots)?,
+                meta.base_decimals
+            ),
+            get_decimal_string(
+                sdk.base_lots_to_base_atoms(market_pubkey, num_base_lots)?,
+                meta.base_decimals
+            ),
+        ));
+    }
+
+    let base_units = meta.base_atoms_to_raw_base_units_as_float(
+        sdk.base_lots_to_base_atoms(market_pubkey, num_base_lots)?,
+    );
+    let best_price = sdk.ticks_to_float_price(market_pubkey, best_price_in_ticks)?;
+    let average_price = meta.quote_atoms_to_quote_units_as_float(expected_quote_atoms) / base_units;
+    let price_impact_bps = (average_price - best_price).abs() / best_price * BPS_DIVISOR as f64;
+    let expected_fee_atoms = (expected_quote_atoms * taker_fee_bps).div_ceil(BPS_DIVISOR);
+
+    let quote = SwapQuote {
+        side: format!("{:?}", side),
+        size: get_decimal_string(
+            sdk.base_lots_to_base_atoms(market_pubkey, num_base_lots)?,
+            meta.base_decimals,
+        ),
+        best_price,
+        expected_average_price: average_price,
+        worst_price: sdk.ticks_to_float_price(market_pubkey, worst_price_in_ticks)?,
+        price_impact_bps,
+        expected_quote_amount: get_decimal_string(expected_quote_atoms, meta.quote_decimals),
+        taker_fee_bps,
+        expected_taker_fees: get_decimal_string(expected_fee_atoms, meta.quote_decimals),
+    };
+
+    if price_impact_bps > max_slippage_bps as f64 {
+        return Err(anyhow::anyhow!(
+            "Price impact of {:.2} bps exceeds the max slippage of {} bps. Not sending the order",
+            price_impact_bps,
+            max_slippage_bps
+        ));
+    }
+
+    // The order is limited to the worst price of the quote, so it never fills beyond the levels
+    // the quote walked
+    let order_packet = OrderPacket::ImmediateOrCancel {
+        side,
+        price_in_ticks: Some(Ticks::new(worst_price_in_ticks)),
+        num_base_lots: BaseLots::new(num_base_lots),
+        num_quote_lots: QuoteLots::new(0),
+        min_base_lots_to_fill: BaseLots::new(0),
+        min_quote_lots_to_fill: QuoteLots::new(0),
+        self_trade_behavior: SelfTradeBehavior::CancelProvide,
+        match_limit: None,
+        client_order_id: 0,
+        use_only_deposited_funds: false,
+        last_valid_slot: None,
+        last_valid_unix_timestamp_in_seconds: None,
+    };
+
+    let mut instructions = get_token_account_setup_instructions(sdk, market_pubkey).await?;
+    instructions.push(create_new_order_instruction(
+        market_pubkey,
+        &sdk.get_trader(),
+        &meta.base_mint,
+        &meta.quote_mint,
+        &order_packet,
+    ));
+
+    let signature = match send_instructions(sdk, instructions, vec![]).await? {
+        TransactionOutcome::Sent(signature) => signature,
+        TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+    };
+
+    let fills = sdk
+        .parse_events_from_transaction(&signature)
+        .await
+        .ok_or_else(|| anyhow::anyhow!("Failed to parse events from transaction"))?
+        .into_iter()
+        .filter(|event| {
+            matches!(
+                event.details,
+                MarketEventDetails::Fill(..) | MarketEventDetails::FillSummary(..)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    Ok(TransactionOutcome::Sent(SwapResult {
+        quote,
+        signature: signature.to_string(),
+        fills: get_market_event_records(sdk, fills).await?,
+    }))
+}
@@ -0,0 +1,218 @@
+use crate::helpers::output_helpers::{render_stream_item, OutputFormat};
+use crate::helpers::print_helpers::{
+    format_float, get_order_book_with_trader, print_book_with_trader_and_changes, BookLevel,
+    OrderBook,
+};
+use crate::processor::process_get_book_levels::get_book_level_entries;
+use futures::StreamExt;
+use phoenix_sdk::sdk_client::*;
+use solana_account_decoder::UiAccountEncoding;
+use solana_cli_config::Config;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy)]
+pub struct WatchConfig {
+    /// Maximum number of levels shown per side of the book
+    pub levels: u64,
+    /// Interval between RPC polls when not using the websocket
+    pub poll_interval: Duration,
+    /// Skip the websocket subscription and poll the RPC from the start
+    pub force_polling: bool,
+    pub output: OutputFormat,
+}
+
+struct BookWatcher<'a> {
+    sdk: &'a SDKClient,
+    market_pubkey: Pubkey,
+    config: WatchConfig,
+    clock: Clock,
+    clock_fetched_at: Instant,
+    last_market_data: Vec<u8>,
+    previous_book: Option<OrderBook>,
+}
+
+pub async fn process_watch_book(
+    market_pubkey: &Pubkey,
+    sdk: &SDKClient,
+    network_url: &str,
+    config: WatchConfig,
+) -> anyhow::Result<()> {
+    let clock_account_data = sdk.client.get_account_data(&sysvar::clock::id()).await?;
+    let clock: Clock = bincode::deserialize(&clock_account_data)
+        .map_err(|_| anyhow::Error::msg("Error deserializing clock"))?;
+
+    let mut watcher = BookWatcher {
+        sdk,
+        market_pubkey: *market_pubkey,
+        config,
+        clock,
+        clock_fetched_at: Instant::now(),
+        last_market_data: vec![],
+        previous_book: None,
+    };
+
+    tokio::select! {
+        result = watch(&mut watcher, network_url) => result,
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    }
+}
+
+async fn watch(watcher: &mut BookWatcher<'_>, network_url: &str) -> anyhow::Result<()> {
+    if !watcher.config.force_polling {
+        let websocket_url = Config::compute_websocket_url(network_url);
+        match PubsubClient::new(&websocket_url).await {
+            Ok(pubsub) => {
+                // Draw the current book right away, since the subscription only reports changes
+                poll_market(watcher, "websocket").await?;
+                let error = watch_websocket(&pubsub, watcher)
+                    .await
+                    .err()
+                    .unwrap_or_else(|| anyhow::anyhow!("Subscription closed"));
+                eprintln!(
+                    "Websocket subscription ended: {}. Falling back to RPC polling",
+                    error
+                );
+            }
+            Err(e) => eprintln!(
+                "Failed to connect to websocket {}: {}. Falling back to RPC polling",
+                websocket_url, e
+            ),
+        }
+    }
+
+    let source = format!(
+        "polling every {} ms",
+        watcher.config.poll_interval.as_millis()
+    );
+    let mut interval = tokio::time::interval(watcher.config.poll_interval);
+    loop {
+        interval.tick().await;
+        if let Err(e) = poll_market(watcher, &source).await {
+            eprintln!("Failed to fetch market account: {}", e);
+        }
+    }
+}
+
+async fn watch_websocket(
+    pubsub: &PubsubClient,
+    watcher: &mut BookWatcher<'_>,
+) -> anyhow::Result<()> {
+    let (mut updates, unsubscribe) = pubsub
+        .account_subscribe(
+            &watcher.market_pubkey,
+            Some(RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                ..RpcAccountInfoConfig::default()
+            }),
+        )
+        .await?;
+
+    while let Some(update) = updates.next().await {
+        let account: Account = update
+            .value
+            .decode()
+            .ok_or_else(|| anyhow::Error::msg("Failed to decode market account update"))?;
+        watcher.update(&account.data, update.context.slot, "websocket")?;
+    }
+
+    unsubscribe().await;
+    Ok(())
+}
+
+async fn poll_market(watcher: &mut BookWatcher<'_>, source: &str) -> anyhow::Result<()> {
+    let response = watcher
+        .sdk
+        .client
+        .get_account_with_commitment(&watcher.market_pubkey, CommitmentConfig::confirmed())
+        .await?;
+    let account = response
+        .value
+        .ok_or_else(|| anyhow::Error::msg("Market account not found"))?;
+    watcher.update(&account.data, response.context.slot, source)
+}
+
+impl BookWatcher<'_> {
+    fn update(&mut self, market_data: &[u8], slot: u64, source: &str) -> anyhow::Result<()> {
+        if market_data == self.last_market_data {
+            return Ok(());
+        }
+        self.last_market_data = market_data.to_vec();
+
+        // Estimate the on-chain time from the clock fetched at startup to filter out expired orders
+        let unix_timestamp =
+            self.clock.unix_timestamp as u64 + self.clock_fetched_at.elapsed().as_secs();
+        let (bid_entries, ask_entries) = get_book_level_entries(
+            market_data,
+            slot.max(self.clock.slot),
+            unix_timestamp,
+            &self.sdk.trader,
+            self.config.levels,
+        )?;
+        let book =
+            get_order_book_with_trader(self.sdk, &self.market_pubkey, &bid_entries, &ask_entries)?;
+
+        if self.config.output != OutputFormat::Text {
+            render_stream_item(&book, self.config.output, self.previous_book.is_none())?;
+            self.previous_book = Some(book);
+            return Ok(());
+        }
+
+        let (changed_bid_ticks, changed_ask_ticks) = match &self.previous_book {
+            Some(previous) => (
+                get_changed_ticks(&previous.bids, &book.bids),
+                get_changed_ticks(&previous.asks, &book.asks),
+            ),
+            None => (HashSet::new(), HashSet::new()),
+        };
+
+        // Clear the screen and move the cursor to the top left before redrawing
+        print!("\x1B[2J\x1B[H");
+        println!("Market: {}", self.market_pubkey);
+        println!("Slot: {} ({})", slot, source);
+        println!();
+        print_book_with_trader_and_changes(&book, &changed_bid_ticks, &changed_ask_ticks);
+        println!();
+        print_spread_and_mid(&book);
+        println!("Press Ctrl-C to exit");
+
+        self.previous_book = Some(book);
+        Ok(())
+    }
+}
+
+fn get_changed_ticks(previous: &[BookLevel], current: &[BookLevel]) -> HashSet<u64> {
+    let previous_lots = previous
+        .iter()
+        .map(|level| (level.price_in_ticks, level.size_in_base_lots))
+        .collect::<HashMap<_, _>>();
+    current
+        .iter()
+        .filter(|level| previous_lots.get(&level.price_in_ticks) != Some(&level.size_in_base_lots))
+        .map(|level| level.price_in_ticks)
+        .collect()
+}
+
+fn print_spread_and_mid(book: &OrderBook) {
+    match (book.bids.first(), book.asks.first()) {
+        (Some(best_bid), Some(best_ask)) => {
+            let spread = best_ask.price - best_bid.price;
+            let mid = (best_ask.price + best_bid.price) / 2.0;
+            println!(
+                "Spread: {} ({:.2} bps)",
+                format_float(spread, book.price_precision),
+                spread / mid * 10_000.0
+            );
+            println!("Mid price: {}", format_float(mid, book.price_precision + 1));
+        }
+        _ => println!("Spread: N/A, one side of the book is empty"),
+    }
+}
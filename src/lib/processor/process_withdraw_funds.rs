@@ -0,0 +1,102 @@
+use crate::helpers::market_helpers::get_token_account_setup_instructions;
+use crate::helpers::output_helpers::CommandOutput;
+use crate::helpers::print_helpers::{
+    get_trader_state_summary_for_market, print_trader_state_or_missing_seat, TraderStateSummary,
+};
+use crate::helpers::transaction_helpers::{send_instructions, TransactionOutcome};
+use phoenix::program::{
+    create_withdraw_funds_instruction, create_withdraw_funds_with_custom_amounts_instruction,
+};
+use phoenix_sdk::sdk_client::*;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WithdrawFundsResult {
+    pub trader: String,
+    pub signature: String,
+    /// Trader state before the withdrawal, or `None` if the trader had no seat
+    pub state_before: Option<TraderStateSummary>,
+    /// Trader state after the withdrawal, or `None` if the trader has no seat
+    pub state_after: Option<TraderStateSummary>,
+}
+
+impl CommandOutput for WithdrawFundsResult {
+    fn print_text(&self) {
+        println!("Trader state before withdrawal:");
+        print_trader_state_or_missing_seat(&self.trader, &self.state_before);
+        println!("Withdraw funds transaction: {}", self.signature);
+        println!("Trader state after withdrawal:");
+        print_trader_state_or_missing_seat(&self.trader, &self.state_after);
+    }
+
+    fn csv_header(&self) -> Vec<&'static str> {
+        let mut header = vec!["stage", "signature"];
+        header.extend(TraderStateSummary::CSV_HEADER);
+        header
+    }
+
+    fn csv_records(&self) -> Vec<Vec<String>> {
+        [("before", &self.state_before), ("after", &self.state_after)]
+            .into_iter()
+            .filter_map(|(stage, state)| {
+                let mut record = vec![stage.to_string(), self.signature.clone()];
+                record.extend(state.as_ref()?.csv_record());
+                Some(record)
+            })
+            .collect()
+    }
+}
+
+pub async fn process_withdraw_funds(
+    market_pubkey: &Pubkey,
+    sdk: &SDKClient,
+    base_amount: Option<f64>,
+    quote_amount: Option<f64>,
+    withdraw_all: bool,
+) -> anyhow::Result<TransactionOutcome<WithdrawFundsResult>> {
+    let meta = sdk.get_market_metadata(market_pubkey).await?;
+    let trader = sdk.get_trader();
+
+    let state_before = get_trader_state_summary_for_market(sdk, market_pubkey, &trader).await?;
+
+    let mut instructions = get_token_account_setup_instructions(sdk, market_pubkey).await?;
+    if withdraw_all {
+        instructions.push(create_withdraw_funds_instruction(
+            market_pubkey,
+            &trader,
+            &meta.base_mint,
+            &meta.quote_mint,
+        ));
+    } else {
+        let base_lots_to_withdraw = sdk
+            .raw_base_units_to_base_lots_rounded_down(market_pubkey, base_amount.unwrap_or(0.0))?;
+        let quote_lots_to_withdraw =
+            sdk.quote_units_to_quote_lots(market_pubkey, quote_amount.unwrap_or(0.0))?;
+        if base_lots_to_withdraw == 0 && quote_lots_to_withdraw == 0 {
+            return Err(anyhow::anyhow!(
+                "Withdrawal amounts are below the minimum lot sizes of the market"
+            ));
+        }
+        instructions.push(create_withdraw_funds_with_custom_amounts_instruction(
+            market_pubkey,
+            &trader,
+            &meta.base_mint,
+            &meta.quote_mint,
+            base_lots_to_withdraw,
+            quote_lots_to_withdraw,
+        ));
+    }
+
+    let signature = match send_instructions(sdk, instructions, vec![]).await? {
+        TransactionOutcome::Sent(signature) => signature,
+        TransactionOutcome::Unsent(unsent) => return Ok(TransactionOutcome::Unsent(unsent)),
+    };
+
+    Ok(TransactionOutcome::Sent(WithdrawFundsResult {
+        trader: trader.to_string(),
+        signature: signature.to_string(),
+        state_before,
+        state_after: get_trader_state_summary_for_market(sdk, market_pubkey, &trader).await?,
+    }))
+}
@@ -1,23 +1,44 @@
 mod command;
 
-use crate::command::PhoenixCLICommand;
+use crate::command::{
+    AdminCommand, PhoenixCLICommand, RegistryCommand, SandboxCommand, SeatManagerCommand,
+    WatchOptions,
+};
 use anyhow::anyhow;
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser};
 use ellipsis_client::EllipsisClient;
+use phoenix_cli_processor::helpers::market_helpers::{add_market_from_rpc_or_file, resolve_market};
+use phoenix_cli_processor::helpers::output_helpers::{render, CommandOutput, OutputFormat};
+use phoenix_cli_processor::helpers::registry_helpers::set_registry_path;
+use phoenix_cli_processor::helpers::transaction_helpers::{
+    set_transaction_options, TransactionEncoding, TransactionOptions, TransactionOutcome,
+    UnsentTransaction,
+};
+use phoenix_cli_processor::processor::process_amend_order::*;
+use phoenix_cli_processor::processor::process_broadcast_tx::*;
+use phoenix_cli_processor::processor::process_cancel_orders::*;
+use phoenix_cli_processor::processor::process_change_seat_status::*;
 use phoenix_cli_processor::processor::process_claim_seat::process_claim_seat;
 use phoenix_cli_processor::processor::process_evict_seat::process_evict_seat;
 use phoenix_cli_processor::processor::{
-    process_get_all_markets::*, process_get_book_levels::*, process_get_full_book::*,
-    process_get_market::*, process_get_market_status::*, process_get_open_orders::*,
-    process_get_seat_info::*, process_get_seat_manager_info::*, process_get_top_of_book::*,
-    process_get_traders_for_market::*, process_get_transaction::*, process_mint_tokens::*,
-    process_mint_tokens_for_market::*, process_request_seat::*,
+    process_create_market::*, process_deposit_funds::*, process_export_trades::*,
+    process_get_all_markets::*, process_get_book_levels::*, process_get_candles::*,
+    process_get_expiring_orders::*, process_get_full_book::*, process_get_market::*,
+    process_get_market_status::*, process_get_open_orders::*, process_get_seat_info::*,
+    process_get_seat_manager_info::*, process_get_top_of_book::*,
+    process_get_traders_for_market::*, process_get_transaction::*, process_list_pending_seats::*,
+    process_market_admin::*, process_mint_tokens::*, process_mint_tokens_for_market::*,
+    process_place_limit_order::*, process_place_orders::*, process_registry::*,
+    process_request_seat::*, process_sandbox::*, process_seat_manager_admin::*, process_sign_tx::*,
+    process_stream_events::*, process_swap::*, process_watch_book::*, process_withdraw_funds::*,
 };
 use phoenix_sdk::sdk_client::*;
 use solana_cli_config::{Config, ConfigInput, CONFIG_FILE};
 use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signer::keypair::{read_keypair_file, Keypair};
 use solana_sdk::signer::Signer;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(author, version, about)]
@@ -33,6 +54,50 @@ struct Args {
     /// Optionally include a commitment level. Defaults to your Solana CLI config file.
     #[clap(global = true, short, long)]
     commitment: Option<String>,
+    /// Output format. Defaults to human readable text.
+    #[clap(global = true, long, value_enum, default_value = "text")]
+    output: OutputFormat,
+    /// Simulate the transaction a command would send instead of sending it, and print the program logs,
+    /// compute units, account changes and Phoenix events of the simulation.
+    #[clap(global = true, long, conflicts_with = "offline")]
+    simulate: bool,
+    /// Priority fee in micro-lamports per compute unit, added to every transaction a command sends.
+    #[clap(global = true, long)]
+    priority_fee: Option<u64>,
+    /// Compute unit limit requested by every transaction a command sends.
+    #[clap(global = true, long, conflicts_with = "auto_compute")]
+    compute_unit_limit: Option<u32>,
+    /// Simulate every transaction before sending it and set its compute unit limit to the measured usage plus a margin.
+    #[clap(global = true, long)]
+    auto_compute: bool,
+    /// Print the unsigned transaction a command would send instead of sending it, for signing elsewhere with sign-tx.
+    #[clap(global = true, long, group = "offline")]
+    export_tx: bool,
+    /// Print the transaction a command would send, signed by the local keypair, instead of sending it.
+    #[clap(global = true, long, group = "offline")]
+    sign_only: bool,
+    /// Encoding of transactions printed by --export-tx and --sign-only.
+    #[clap(global = true, long, value_enum, default_value = "base64")]
+    tx_encoding: TransactionEncoding,
+    /// Durable nonce account to use instead of a recent blockhash with --export-tx or --sign-only, so the transaction
+    /// can be signed and broadcast much later. The nonce authority must sign the transaction.
+    #[clap(global = true, long, requires = "offline")]
+    nonce: Option<Pubkey>,
+    /// Fee payer of the transaction with --export-tx or --sign-only. Defaults to the keypair from --keypair-path.
+    #[clap(global = true, long, requires = "offline")]
+    fee_payer: Option<Pubkey>,
+    /// Authority that signs market admin, seat manager and seat status instructions with --export-tx or --sign-only,
+    /// such as a multisig. Defaults to the keypair from --keypair-path.
+    #[clap(global = true, long, requires = "offline")]
+    authority: Option<Pubkey>,
+    /// Build admin transactions with --export-tx or --sign-only without checking the authority against the market,
+    /// for transactions that are signed once the market authority or successor has changed.
+    #[clap(global = true, long, requires = "offline")]
+    skip_authority_check: bool,
+    /// User registry of token symbols and markets, keyed by cluster genesis hash. Defaults to
+    /// ~/.config/phoenix-cli/registry.json
+    #[clap(global = true, long)]
+    registry: Option<String>,
 }
 
 pub fn get_network(network_str: &str) -> &str {
@@ -48,20 +113,112 @@ pub fn get_payer_keypair_from_path(path: &str) -> anyhow::Result<Keypair> {
     read_keypair_file(&*shellexpand::tilde(path)).map_err(|e| anyhow!(e.to_string()))
 }
 
+// Read-only commands default to the payer's orders and seat, which are unknown without a keypair
+fn get_trader_or_payer(
+    trader_pubkey: Option<Pubkey>,
+    payer_pubkey: Option<Pubkey>,
+) -> anyhow::Result<Pubkey> {
+    trader_pubkey.or(payer_pubkey).ok_or_else(|| {
+        anyhow!("No keypair was found, so the trader must be given with --trader-pubkey")
+    })
+}
+
+fn get_watch_config(levels: u64, options: &WatchOptions, output: OutputFormat) -> WatchConfig {
+    WatchConfig {
+        levels,
+        poll_interval: Duration::from_millis(options.poll_interval_ms),
+        force_polling: options.poll,
+        output,
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let cli = Args::parse();
+    let matches = Args::command().get_matches();
+    // Command path such as `admin claim-authority`, used in error messages
+    let mut command_names = vec![];
+    let mut subcommand = matches.subcommand();
+    while let Some((name, subcommand_matches)) = subcommand {
+        command_names.push(name);
+        subcommand = subcommand_matches.subcommand();
+    }
+    let command_name = command_names.join(" ");
+    let cli = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    set_transaction_options(TransactionOptions {
+        simulate: cli.simulate,
+        priority_fee: cli.priority_fee,
+        compute_unit_limit: cli.compute_unit_limit,
+        auto_compute: cli.auto_compute,
+        export_tx: cli.export_tx,
+        sign_only: cli.sign_only,
+        tx_encoding: cli.tx_encoding,
+        nonce_account: cli.nonce,
+        fee_payer: cli.fee_payer,
+        authority: cli.authority,
+        skip_authority_check: cli.skip_authority_check,
+    })?;
+    set_registry_path(cli.registry.clone())?;
+    run(cli, &command_name).await
+}
+
+/// Renders the result of a command, then fails if one of its transactions failed in simulation, so
+/// the command exits with an error
+fn render_checked<'a, T: CommandOutput>(
+    result: &T,
+    unsent_transactions: impl IntoIterator<Item = &'a UnsentTransaction>,
+    output: OutputFormat,
+) -> anyhow::Result<()> {
+    render(result, output)?;
+    match unsent_transactions
+        .into_iter()
+        .find_map(|unsent| unsent.simulation_error())
+    {
+        Some(error) => Err(anyhow!("Simulated transaction failed: {}", error)),
+        None => Ok(()),
+    }
+}
+
+fn render_outcome<T: CommandOutput>(
+    outcome: &TransactionOutcome<T>,
+    output: OutputFormat,
+) -> anyhow::Result<()> {
+    render_checked(outcome, outcome.unsent(), output)
+}
+
+async fn run(cli: Args, command_name: &str) -> anyhow::Result<()> {
+    let output = cli.output;
     let config = match CONFIG_FILE.as_ref() {
         Some(config_file) => Config::load(config_file).unwrap_or_else(|_| {
-            println!("Failed to load config file: {}", config_file);
+            eprintln!("Failed to load config file: {}", config_file);
             Config::default()
         }),
         None => Config::default(),
     };
     let commitment =
         ConfigInput::compute_commitment_config("", &cli.commitment.unwrap_or(config.commitment)).1;
-    let payer = get_payer_keypair_from_path(&cli.keypair_path.unwrap_or(config.keypair_path))
-        .expect("Keypair file does not exist. Please run `solana-keygen new`");
+    let keypair_path = cli.keypair_path.unwrap_or(config.keypair_path);
+    // Read-only commands run with an ephemeral keypair when none is found, since they never sign
+    let (payer, payer_pubkey) = match get_payer_keypair_from_path(&keypair_path) {
+        Ok(payer) => {
+            let payer_pubkey = payer.pubkey();
+            (payer, Some(payer_pubkey))
+        }
+        Err(_)
+            if !cli.command.requires_keypair(
+                cli.export_tx && cli.fee_payer.is_some() && cli.authority.is_some(),
+            ) =>
+        {
+            (Keypair::new(), None)
+        }
+        Err(e) => {
+            return Err(anyhow!(
+                "{} signs transactions and needs a keypair, but {} could not be read ({}). Pass one with --keypair-path or run `solana-keygen new`",
+                command_name,
+                keypair_path,
+                e
+            ))
+        }
+    };
     let network_url = &get_network(&cli.url.unwrap_or(config.json_rpc_url)).to_string();
     let client = EllipsisClient::from_rpc(
         RpcClient::new_with_commitment(network_url.to_string(), commitment),
@@ -71,107 +228,762 @@ async fn main() -> anyhow::Result<()> {
     let mut sdk = SDKClient::new(&payer, network_url).await?;
 
     match cli.command {
-        PhoenixCLICommand::GetMarket { market_pubkey } => {
-            sdk.add_market(&market_pubkey).await?;
-            process_get_market(&market_pubkey, &sdk).await?
+        PhoenixCLICommand::GetMarket {
+            market_pubkey,
+            account_file_options,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            let market_account_data = add_market_from_rpc_or_file(
+                &mut sdk,
+                &market_pubkey,
+                account_file_options.account_file.as_deref(),
+            )
+            .await?;
+            render(
+                &process_get_market(&market_pubkey, &sdk, market_account_data).await?,
+                output,
+            )?
         }
         PhoenixCLICommand::GetAllMarkets { no_gpa } => {
             if no_gpa {
-                process_get_all_markets_no_gpa(&client, network_url).await?
+                render(
+                    &process_get_all_markets_no_gpa(&client, network_url).await?,
+                    output,
+                )?
             } else {
-                process_get_all_markets(&client).await?
+                render(&process_get_all_markets(&client).await?, output)?
             }
         }
-        PhoenixCLICommand::GetTradersForMarket { market_pubkey } => {
-            sdk.add_market(&market_pubkey).await?;
-            process_get_traders_for_market(&market_pubkey, &sdk).await?
+        PhoenixCLICommand::GetTradersForMarket {
+            market_pubkey,
+            account_file_options,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            let market_account_data = add_market_from_rpc_or_file(
+                &mut sdk,
+                &market_pubkey,
+                account_file_options.account_file.as_deref(),
+            )
+            .await?;
+            render(
+                &process_get_traders_for_market(&market_pubkey, &sdk, market_account_data).await?,
+                output,
+            )?
         }
-        PhoenixCLICommand::GetTopOfBook { market_pubkey } => {
+        PhoenixCLICommand::GetTopOfBook {
+            market_pubkey,
+            watch,
+            watch_options,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
             sdk.add_market(&market_pubkey).await?;
-            process_get_top_of_book(&market_pubkey, &sdk).await?
+            if watch {
+                let config = get_watch_config(1, &watch_options, output);
+                process_watch_book(&market_pubkey, &sdk, network_url, config).await?
+            } else {
+                render(
+                    &process_get_top_of_book(&market_pubkey, &sdk).await?,
+                    output,
+                )?
+            }
         }
         PhoenixCLICommand::GetBookLevels {
             market_pubkey,
             levels,
+            watch,
+            watch_options,
+            account_file_options,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            let market_account_data = add_market_from_rpc_or_file(
+                &mut sdk,
+                &market_pubkey,
+                account_file_options.account_file.as_deref(),
+            )
+            .await?;
+            if watch {
+                let config = get_watch_config(levels, &watch_options, output);
+                process_watch_book(&market_pubkey, &sdk, network_url, config).await?
+            } else {
+                render(
+                    &process_get_book_levels(&market_pubkey, &sdk, levels, market_account_data)
+                        .await?,
+                    output,
+                )?
+            }
+        }
+        PhoenixCLICommand::GetFullBook {
+            market_pubkey,
+            watch,
+            watch_options,
+            account_file_options,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            let market_account_data = add_market_from_rpc_or_file(
+                &mut sdk,
+                &market_pubkey,
+                account_file_options.account_file.as_deref(),
+            )
+            .await?;
+            if watch {
+                let config = get_watch_config(u64::MAX, &watch_options, output);
+                process_watch_book(&market_pubkey, &sdk, network_url, config).await?
+            } else {
+                render(
+                    &process_get_full_book(&market_pubkey, &sdk, market_account_data).await?,
+                    output,
+                )?
+            }
+        }
+        PhoenixCLICommand::WatchBook {
+            market_pubkey,
+            levels,
+            watch_options,
         } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
             sdk.add_market(&market_pubkey).await?;
-            process_get_book_levels(&market_pubkey, &sdk, levels).await?
+            let config = get_watch_config(levels, &watch_options, output);
+            process_watch_book(&market_pubkey, &sdk, network_url, config).await?
         }
-        PhoenixCLICommand::GetFullBook { market_pubkey } => {
+        PhoenixCLICommand::StreamEvents {
+            market_pubkey,
+            event_types,
+            maker,
+            taker,
+            min_size,
+            poll_interval_ms,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            sdk.add_market(&market_pubkey).await?;
+            let filter = EventFilter {
+                event_types,
+                maker,
+                taker,
+                min_size,
+            };
+            process_stream_events(
+                &market_pubkey,
+                &mut sdk,
+                filter,
+                Duration::from_millis(poll_interval_ms),
+                output,
+            )
+            .await?
+        }
+        PhoenixCLICommand::ExportTrades {
+            market_pubkey,
+            out_file,
+            format,
+            before,
+            until,
+            start_slot,
+            end_slot,
+            start_time,
+            end_time,
+            checkpoint,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
             sdk.add_market(&market_pubkey).await?;
-            process_get_full_book(&market_pubkey, &sdk).await?
+            let range = TradeExportRange {
+                before,
+                until,
+                start_slot,
+                end_slot,
+                start_time,
+                end_time,
+            };
+            render(
+                &process_export_trades(
+                    &market_pubkey,
+                    &sdk,
+                    range,
+                    format,
+                    &out_file,
+                    checkpoint.as_deref(),
+                    output,
+                )
+                .await?,
+                output,
+            )?
         }
-        PhoenixCLICommand::GetTransaction { signature } => {
-            process_get_transaction(&signature, &mut sdk).await?
+        PhoenixCLICommand::GetCandles {
+            market_pubkey,
+            resolution,
+            bars,
+            start_time,
+            end_time,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            sdk.add_market(&market_pubkey).await?;
+            render(
+                &process_get_candles(&market_pubkey, &sdk, resolution, start_time, end_time, bars)
+                    .await?,
+                output,
+            )?
         }
+        PhoenixCLICommand::GetTransaction { signature } => render(
+            &process_get_transaction(&signature, &mut sdk).await?,
+            output,
+        )?,
         PhoenixCLICommand::GetMarketStatus { market_pubkey } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
             sdk.add_market(&market_pubkey).await?;
-            process_get_market_status(&market_pubkey, &sdk).await?
+            render(
+                &process_get_market_status(&market_pubkey, &sdk).await?,
+                output,
+            )?
         }
         PhoenixCLICommand::GetSeatInfo {
             market_pubkey,
             trader_pubkey,
         } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
             sdk.add_market(&market_pubkey).await?;
-            process_get_seat_info(
-                &market_pubkey,
-                &trader_pubkey.unwrap_or_else(|| payer.pubkey()),
-                &sdk,
-            )
-            .await?
+            render(
+                &process_get_seat_info(
+                    &market_pubkey,
+                    &get_trader_or_payer(trader_pubkey, payer_pubkey)?,
+                    &sdk,
+                )
+                .await?,
+                output,
+            )?
         }
         PhoenixCLICommand::GetOpenOrders {
             market_pubkey,
             trader_pubkey,
+            account_file_options,
         } => {
-            sdk.add_market(&market_pubkey).await?;
-            process_get_open_orders(
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            let market_account_data = add_market_from_rpc_or_file(
+                &mut sdk,
                 &market_pubkey,
-                &trader_pubkey.unwrap_or_else(|| payer.pubkey()),
-                &sdk,
+                account_file_options.account_file.as_deref(),
             )
-            .await?
+            .await?;
+            render(
+                &process_get_open_orders(
+                    &market_pubkey,
+                    &get_trader_or_payer(trader_pubkey, payer_pubkey)?,
+                    &sdk,
+                    market_account_data,
+                )
+                .await?,
+                output,
+            )?
+        }
+        PhoenixCLICommand::GetExpiringOrders {
+            market_pubkey,
+            trader_pubkey,
+            within_slots,
+            within_seconds,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            sdk.add_market(&market_pubkey).await?;
+            render(
+                &process_get_expiring_orders(
+                    &market_pubkey,
+                    &get_trader_or_payer(trader_pubkey, payer_pubkey)?,
+                    &sdk,
+                    within_slots,
+                    within_seconds,
+                )
+                .await?,
+                output,
+            )?
         }
         PhoenixCLICommand::RequestSeat { market_pubkey } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
             sdk.add_market(&market_pubkey).await?;
-            process_request_seat(&market_pubkey, &sdk).await?
+            render_outcome(&process_request_seat(&market_pubkey, &sdk).await?, output)?
+        }
+        PhoenixCLICommand::ListPendingSeats { market_pubkey } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            render(
+                &process_list_pending_seats(&sdk, &market_pubkey).await?,
+                output,
+            )?
+        }
+        PhoenixCLICommand::ChangeSeatStatus {
+            market_pubkey,
+            status,
+            mut traders,
+            traders_file,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            if let Some(traders_file) = traders_file {
+                traders.extend(read_traders_file(&traders_file)?);
+            }
+            let result =
+                process_change_seat_status(&sdk, &market_pubkey, &traders, status.into()).await?;
+            render_checked(&result, &result.unsent_transactions, output)?
         }
         PhoenixCLICommand::MintTokens {
             mint_ticker,
             recipient_pubkey,
             amount,
-        } => process_mint_tokens(&client, &payer, &recipient_pubkey, mint_ticker, amount).await?,
+        } => render_outcome(
+            &process_mint_tokens(&sdk, &payer, &recipient_pubkey, mint_ticker, amount).await?,
+            output,
+        )?,
         PhoenixCLICommand::MintTokensForMarket {
             market_pubkey,
             recipient_pubkey,
             base_amount,
             quote_amount,
         } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
             sdk.add_market(&market_pubkey).await?;
-            process_mint_tokens_for_market(
-                &sdk,
-                &market_pubkey,
-                &recipient_pubkey,
-                base_amount,
-                quote_amount,
-            )
-            .await?
+            render_outcome(
+                &process_mint_tokens_for_market(
+                    &sdk,
+                    &market_pubkey,
+                    &recipient_pubkey,
+                    base_amount,
+                    quote_amount,
+                )
+                .await?,
+                output,
+            )?
         }
         PhoenixCLICommand::GetSeatManagerInfo { market_pubkey } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
             sdk.add_market(&market_pubkey).await?;
-            process_get_seat_manager_info(&sdk.client, &market_pubkey).await?;
+            render(
+                &process_get_seat_manager_info(&sdk.client, &market_pubkey).await?,
+                output,
+            )?;
         }
         PhoenixCLICommand::ClaimSeat { market_pubkey } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
             sdk.add_market(&market_pubkey).await?;
-            process_claim_seat(&sdk.client, &market_pubkey).await?
+            render_outcome(&process_claim_seat(&sdk, &market_pubkey).await?, output)?
         }
         PhoenixCLICommand::EvictSeat {
             market_pubkey,
             trader_to_evict,
         } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            sdk.add_market(&market_pubkey).await?;
+            render_outcome(
+                &process_evict_seat(&sdk, &market_pubkey, &trader_to_evict).await?,
+                output,
+            )?
+        }
+        PhoenixCLICommand::PlaceLimitOrder {
+            market_pubkey,
+            side,
+            price,
+            size,
+            client_order_id,
+            post_only,
+            ioc,
+            self_trade_behavior,
+            expire_in_slots,
+            expire_in_seconds,
+            expire_at,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            sdk.add_market(&market_pubkey).await?;
+            let order_type = if post_only {
+                OrderType::PostOnly
+            } else if ioc {
+                OrderType::ImmediateOrCancel
+            } else {
+                OrderType::Limit
+            };
+            let expiry = expire_in_slots
+                .map(OrderExpiry::InSlots)
+                .or(expire_in_seconds.map(OrderExpiry::InSeconds))
+                .or(expire_at.map(OrderExpiry::At));
+            render_outcome(
+                &process_place_limit_order(
+                    &market_pubkey,
+                    &mut sdk,
+                    side.into(),
+                    price,
+                    size,
+                    client_order_id,
+                    order_type,
+                    self_trade_behavior.into(),
+                    expiry,
+                )
+                .await?,
+                output,
+            )?
+        }
+        PhoenixCLICommand::PlaceOrders {
+            market_pubkey,
+            order_file,
+            format,
+            post_only,
+            self_trade_behavior,
+            dry_run,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            let orders = read_order_file(&order_file, format)?;
+            sdk.add_market(&market_pubkey).await?;
+            let result = process_place_orders(
+                &market_pubkey,
+                &sdk,
+                orders,
+                post_only,
+                self_trade_behavior.into(),
+                dry_run,
+            )
+            .await?;
+            render_checked(&result, &result.unsent_transactions, output)?
+        }
+        PhoenixCLICommand::CancelOrder {
+            market_pubkey,
+            order_ids,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            sdk.add_market(&market_pubkey).await?;
+            let result =
+                process_cancel_orders(&market_pubkey, &mut sdk, CancelSelection::Ids(order_ids))
+                    .await?;
+            render_checked(&result, &result.unsent_transactions, output)?
+        }
+        PhoenixCLICommand::CancelAllOrders {
+            market_pubkey,
+            side,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
             sdk.add_market(&market_pubkey).await?;
-            process_evict_seat(&sdk.client, &market_pubkey, &trader_to_evict).await?
+            let result = process_cancel_orders(
+                &market_pubkey,
+                &mut sdk,
+                CancelSelection::All(side.map(Into::into)),
+            )
+            .await?;
+            render_checked(&result, &result.unsent_transactions, output)?
         }
+        PhoenixCLICommand::CancelUpTo {
+            market_pubkey,
+            side,
+            ticks,
+            price,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            sdk.add_market(&market_pubkey).await?;
+            let threshold = match (ticks, price) {
+                (Some(ticks), _) => PriceThreshold::Ticks(ticks),
+                (None, Some(price)) => PriceThreshold::Price(price),
+                (None, None) => return Err(anyhow!("Either --ticks or --price is required")),
+            };
+            let result = process_cancel_orders(
+                &market_pubkey,
+                &mut sdk,
+                CancelSelection::UpTo {
+                    side: side.into(),
+                    threshold,
+                },
+            )
+            .await?;
+            render_checked(&result, &result.unsent_transactions, output)?
+        }
+        PhoenixCLICommand::AmendOrder {
+            market_pubkey,
+            order_id,
+            price,
+            size,
+            client_order_id,
+            post_only,
+            self_trade_behavior,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            sdk.add_market(&market_pubkey).await?;
+            render_outcome(
+                &process_amend_order(
+                    &market_pubkey,
+                    &mut sdk,
+                    order_id,
+                    price,
+                    size,
+                    client_order_id,
+                    post_only,
+                    self_trade_behavior.into(),
+                )
+                .await?,
+                output,
+            )?
+        }
+        PhoenixCLICommand::Swap {
+            market_pubkey,
+            side,
+            size,
+            max_slippage_bps,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            sdk.add_market(&market_pubkey).await?;
+            render_outcome(
+                &process_swap(
+                    &market_pubkey,
+                    &mut sdk,
+                    side.into(),
+                    size,
+                    max_slippage_bps,
+                )
+                .await?,
+                output,
+            )?
+        }
+        PhoenixCLICommand::DepositFunds {
+            market_pubkey,
+            base_amount,
+            quote_amount,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            sdk.add_market(&market_pubkey).await?;
+            render_outcome(
+                &process_deposit_funds(
+                    &market_pubkey,
+                    &sdk,
+                    base_amount.unwrap_or(0.0),
+                    quote_amount.unwrap_or(0.0),
+                )
+                .await?,
+                output,
+            )?
+        }
+        PhoenixCLICommand::WithdrawFunds {
+            market_pubkey,
+            base_amount,
+            quote_amount,
+            all,
+        } => {
+            let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+            sdk.add_market(&market_pubkey).await?;
+            render_outcome(
+                &process_withdraw_funds(&market_pubkey, &sdk, base_amount, quote_amount, all)
+                    .await?,
+                output,
+            )?
+        }
+        PhoenixCLICommand::SignTx {
+            transaction,
+            signers,
+            encoding,
+        } => {
+            let signers = if signers.is_empty() {
+                vec![payer]
+            } else {
+                signers
+                    .iter()
+                    .map(|path| get_payer_keypair_from_path(path))
+                    .collect::<anyhow::Result<Vec<_>>>()?
+            };
+            render(&process_sign_tx(&transaction, &signers, encoding)?, output)?
+        }
+        PhoenixCLICommand::BroadcastTx { transaction } => {
+            let receipt = process_broadcast_tx(&sdk, &transaction).await?;
+            render(&receipt, output)?;
+            if let Some(error) = receipt.error {
+                return Err(anyhow!("Transaction failed: {}", error));
+            }
+        }
+        PhoenixCLICommand::CreateMarket {
+            base_mint,
+            quote_mint,
+            tick_size,
+            base_lot_size,
+            quote_lot_size,
+            bids_size,
+            asks_size,
+            num_seats,
+            taker_fee_bps,
+            fee_recipient,
+            raw_base_units_per_base_unit,
+            market_keypair,
+            seat_manager,
+        } => {
+            let market_keypair = market_keypair
+                .map(|path| get_payer_keypair_from_path(&path))
+                .transpose()?;
+            let params = CreateMarketParams {
+                base_mint,
+                quote_mint,
+                tick_size,
+                base_lot_size,
+                quote_lot_size,
+                bids_size,
+                asks_size,
+                num_seats,
+                taker_fee_bps,
+                fee_recipient,
+                raw_base_units_per_base_unit,
+                seat_manager,
+            };
+            render_outcome(
+                &process_create_market(&sdk, &params, market_keypair).await?,
+                output,
+            )?
+        }
+        PhoenixCLICommand::Admin { command } => match command {
+            AdminCommand::ChangeMarketStatus {
+                market_pubkey,
+                status,
+            } => {
+                let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+                render_outcome(
+                    &process_change_market_status(&sdk, &market_pubkey, status.into()).await?,
+                    output,
+                )?
+            }
+            AdminCommand::ChangeFeeRecipient {
+                market_pubkey,
+                new_fee_recipient,
+                fee_recipient_keypair,
+            } => {
+                let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+                let fee_recipient_keypair = fee_recipient_keypair
+                    .map(|path| get_payer_keypair_from_path(&path))
+                    .transpose()?;
+                render_outcome(
+                    &process_change_fee_recipient(
+                        &sdk,
+                        &market_pubkey,
+                        &new_fee_recipient,
+                        fee_recipient_keypair.as_ref(),
+                    )
+                    .await?,
+                    output,
+                )?
+            }
+            AdminCommand::NameSuccessor {
+                market_pubkey,
+                successor,
+            } => {
+                let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+                render_outcome(
+                    &process_name_successor(&sdk, &market_pubkey, &successor).await?,
+                    output,
+                )?
+            }
+            AdminCommand::ClaimAuthority { market_pubkey } => {
+                let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+                render_outcome(
+                    &process_claim_authority(&sdk, &market_pubkey).await?,
+                    output,
+                )?
+            }
+        },
+        PhoenixCLICommand::SeatManager { command } => match command {
+            SeatManagerCommand::AddDmm {
+                market_pubkey,
+                trader,
+            } => {
+                let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+                render_outcome(
+                    &process_add_dmm(&sdk, &market_pubkey, &trader).await?,
+                    output,
+                )?
+            }
+            SeatManagerCommand::RemoveDmm {
+                market_pubkey,
+                trader,
+            } => {
+                let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+                render_outcome(
+                    &process_remove_dmm(&sdk, &market_pubkey, &trader).await?,
+                    output,
+                )?
+            }
+            SeatManagerCommand::NameSuccessor {
+                market_pubkey,
+                successor,
+            } => {
+                let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+                render_outcome(
+                    &process_name_seat_manager_successor(&sdk, &market_pubkey, &successor).await?,
+                    output,
+                )?
+            }
+            SeatManagerCommand::ClaimAuthority { market_pubkey } => {
+                let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+                render_outcome(
+                    &process_claim_seat_manager_authority(&sdk, &market_pubkey).await?,
+                    output,
+                )?
+            }
+            SeatManagerCommand::ClaimSeat {
+                market_pubkey,
+                traders,
+            } => {
+                let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+                render_outcome(
+                    &process_claim_seats_authorized(&sdk, &market_pubkey, &traders).await?,
+                    output,
+                )?
+            }
+            SeatManagerCommand::EvictSeat {
+                market_pubkey,
+                traders,
+            } => {
+                let market_pubkey = resolve_market(&client, &market_pubkey).await?;
+                render_outcome(
+                    &process_evict_seats_authorized(&sdk, &market_pubkey, &traders).await?,
+                    output,
+                )?
+            }
+        },
+        PhoenixCLICommand::Registry { command } => match command {
+            RegistryCommand::List => render(&process_registry_list(&client).await?, output)?,
+            RegistryCommand::Add {
+                address,
+                symbol,
+                name,
+                alias,
+            } => render(
+                &process_registry_add(&client, &address, symbol, name, alias).await?,
+                output,
+            )?,
+            RegistryCommand::Refresh => render(&process_registry_refresh().await?, output)?,
+        },
+        PhoenixCLICommand::Sandbox { command } => match command {
+            SandboxCommand::Init {
+                pair,
+                base_decimals,
+                quote_decimals,
+                tick_size,
+                base_lot_size,
+                taker_fee_bps,
+                num_traders,
+                out_dir,
+                sol_per_trader,
+                base_amount,
+                quote_amount,
+                mid,
+                levels,
+                level_spacing,
+                order_size,
+            } => {
+                let (base_ticker, quote_ticker) = pair
+                    .split_once('/')
+                    .filter(|(base, quote)| !base.is_empty() && !quote.is_empty())
+                    .ok_or_else(|| anyhow!("Invalid pair {}, expected BASE/QUOTE", pair))?;
+                let params = SandboxParams {
+                    base_ticker: base_ticker.to_string(),
+                    quote_ticker: quote_ticker.to_string(),
+                    base_decimals,
+                    quote_decimals,
+                    tick_size,
+                    base_lot_size,
+                    taker_fee_bps,
+                    num_traders,
+                    out_dir,
+                    sol_per_trader,
+                    base_amount,
+                    quote_amount,
+                    mid_price: mid,
+                    levels,
+                    level_spacing,
+                    order_size,
+                };
+                render(&process_sandbox_init(&sdk, &params).await?, output)?
+            }
+        },
     }
 
     Ok(())